@@ -1,3 +1,5 @@
+pub mod nbt;
+
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::io::{Cursor, Read, Write};
 
@@ -25,3 +27,38 @@ pub fn into_nibble_array(data: Vec<u8>) -> Vec<u8> {
         .map(|c| (c[0] << 4) | (c[1] & 0x0F))
         .collect()
 }
+
+/// Reads an unsigned LEB128 varint that takes up at most `max_bytes` bytes
+pub fn read_var_u64<R: Read>(r: &mut R, max_bytes: u32) -> Result<u64, String> {
+    let mut value: u64 = 0;
+
+    for i in 0..max_bytes {
+        let b = r.read_u8().map_err(|e| format!("Failed to read varint: {}", e))?;
+        value |= ((b & 0x7F) as u64) << (7 * i);
+
+        if b & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err("Varint is too long".to_string())
+}
+
+/// Writes an unsigned LEB128 varint
+pub fn write_var_u64<W: Write>(w: &mut W, mut value: u64) {
+    while value & !0x7F != 0 {
+        w.write_u8((value & 0x7F) as u8 | 0x80).expect("Varint");
+        value >>= 7;
+    }
+    w.write_u8(value as u8).expect("Varint");
+}
+
+/// Reads a LEB128 varint (as used by Sponge schematics and the network protocol)
+pub fn read_varint<R: Read>(r: &mut R) -> Result<i32, String> {
+    read_var_u64(r, 5).map(|v| v as u32 as i32)
+}
+
+/// Writes a LEB128 varint (as used by Sponge schematics and the network protocol)
+pub fn write_varint<W: Write>(w: &mut W, value: i32) {
+    write_var_u64(w, value as u32 as u64);
+}
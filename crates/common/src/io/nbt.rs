@@ -0,0 +1,321 @@
+use crate::io::{read_var_u64, write_var_u64};
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use quartz_nbt::{NbtCompound, NbtList, NbtTag};
+use std::io::{Read, Write};
+
+const TAG_END: u8 = 0;
+const TAG_BYTE: u8 = 1;
+const TAG_SHORT: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_LONG: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_DOUBLE: u8 = 6;
+const TAG_BYTE_ARRAY: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+const TAG_INT_ARRAY: u8 = 11;
+const TAG_LONG_ARRAY: u8 = 12;
+
+/// How NBT numbers and lengths are encoded, the tags themselves are the same everywhere
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NbtFlavor {
+    /// Java Edition
+    BigEndian,
+    /// Pocket/Bedrock Edition files (`level.dat`, `.mcstructure`, LevelDB values)
+    LittleEndian,
+    /// Bedrock network protocol, ints, longs and lengths are (zigzag) varints, everything else is little-endian
+    Varint,
+}
+
+struct NbtReader<'a, R: Read> {
+    r: &'a mut R,
+    flavor: NbtFlavor,
+}
+
+impl<R: Read> NbtReader<'_, R> {
+    fn read_i16(&mut self) -> Result<i16, String> {
+        match self.flavor {
+            NbtFlavor::BigEndian => self.r.read_i16::<BigEndian>(),
+            _ => self.r.read_i16::<LittleEndian>(),
+        }
+        .map_err(|e| e.to_string())
+    }
+
+    fn read_i32(&mut self) -> Result<i32, String> {
+        match self.flavor {
+            NbtFlavor::BigEndian => self.r.read_i32::<BigEndian>().map_err(|e| e.to_string()),
+            NbtFlavor::LittleEndian => self.r.read_i32::<LittleEndian>().map_err(|e| e.to_string()),
+            NbtFlavor::Varint => {
+                let v = read_var_u64(self.r, 5)? as u32;
+                Ok((v >> 1) as i32 ^ -((v & 1) as i32))
+            }
+        }
+    }
+
+    fn read_i64(&mut self) -> Result<i64, String> {
+        match self.flavor {
+            NbtFlavor::BigEndian => self.r.read_i64::<BigEndian>().map_err(|e| e.to_string()),
+            NbtFlavor::LittleEndian => self.r.read_i64::<LittleEndian>().map_err(|e| e.to_string()),
+            NbtFlavor::Varint => {
+                let v = read_var_u64(self.r, 10)?;
+                Ok((v >> 1) as i64 ^ -((v & 1) as i64))
+            }
+        }
+    }
+
+    fn read_f32(&mut self) -> Result<f32, String> {
+        match self.flavor {
+            NbtFlavor::BigEndian => self.r.read_f32::<BigEndian>(),
+            _ => self.r.read_f32::<LittleEndian>(),
+        }
+        .map_err(|e| e.to_string())
+    }
+
+    fn read_f64(&mut self) -> Result<f64, String> {
+        match self.flavor {
+            NbtFlavor::BigEndian => self.r.read_f64::<BigEndian>(),
+            _ => self.r.read_f64::<LittleEndian>(),
+        }
+        .map_err(|e| e.to_string())
+    }
+
+    fn read_len(&mut self) -> Result<usize, String> {
+        let len = self.read_i32()?;
+        usize::try_from(len).map_err(|_| format!("Invalid NBT length {}", len))
+    }
+
+    fn read_string(&mut self) -> Result<String, String> {
+        let len = match self.flavor {
+            NbtFlavor::BigEndian => {
+                self.r.read_u16::<BigEndian>().map_err(|e| e.to_string())? as usize
+            }
+            NbtFlavor::LittleEndian => self
+                .r
+                .read_u16::<LittleEndian>()
+                .map_err(|e| e.to_string())? as usize,
+            NbtFlavor::Varint => read_var_u64(self.r, 5)? as usize,
+        };
+
+        let mut str = vec![0u8; len];
+        self.r.read_exact(&mut str).map_err(|e| e.to_string())?;
+
+        // Java uses modified UTF-8, which is the same as UTF-8 for anything we'd realistically read
+        String::from_utf8(str).map_err(|e| e.to_string())
+    }
+
+    fn read_tag(&mut self, id: u8) -> Result<NbtTag, String> {
+        Ok(match id {
+            TAG_BYTE => NbtTag::Byte(self.r.read_i8().map_err(|e| e.to_string())?),
+            TAG_SHORT => NbtTag::Short(self.read_i16()?),
+            TAG_INT => NbtTag::Int(self.read_i32()?),
+            TAG_LONG => NbtTag::Long(self.read_i64()?),
+            TAG_FLOAT => NbtTag::Float(self.read_f32()?),
+            TAG_DOUBLE => NbtTag::Double(self.read_f64()?),
+            TAG_BYTE_ARRAY => {
+                let mut arr = vec![0i8; self.read_len()?];
+                self.r.read_i8_into(&mut arr).map_err(|e| e.to_string())?;
+                NbtTag::ByteArray(arr)
+            }
+            TAG_STRING => NbtTag::String(self.read_string()?),
+            TAG_LIST => {
+                let element = self.r.read_u8().map_err(|e| e.to_string())?;
+                let len = self.read_len()?;
+
+                let mut list = NbtList::with_capacity(len);
+                for _ in 0..len {
+                    list.push(self.read_tag(element)?);
+                }
+                NbtTag::List(list)
+            }
+            TAG_COMPOUND => NbtTag::Compound(self.read_compound()?),
+            TAG_INT_ARRAY => {
+                let len = self.read_len()?;
+                NbtTag::IntArray(
+                    (0..len)
+                        .map(|_| self.read_i32())
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            TAG_LONG_ARRAY => {
+                let len = self.read_len()?;
+                NbtTag::LongArray(
+                    (0..len)
+                        .map(|_| self.read_i64())
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            id => return Err(format!("Unknown NBT tag {}", id)),
+        })
+    }
+
+    fn read_compound(&mut self) -> Result<NbtCompound, String> {
+        let mut compound = NbtCompound::new();
+
+        loop {
+            let id = self.r.read_u8().map_err(|e| e.to_string())?;
+            if id == TAG_END {
+                return Ok(compound);
+            }
+
+            let name = self.read_string()?;
+            compound.insert(name, self.read_tag(id)?);
+        }
+    }
+}
+
+struct NbtWriter<'a, W: Write> {
+    w: &'a mut W,
+    flavor: NbtFlavor,
+}
+
+impl<W: Write> NbtWriter<'_, W> {
+    fn write_i16(&mut self, v: i16) {
+        match self.flavor {
+            NbtFlavor::BigEndian => self.w.write_i16::<BigEndian>(v),
+            _ => self.w.write_i16::<LittleEndian>(v),
+        }
+        .expect("Short");
+    }
+
+    fn write_i32(&mut self, v: i32) {
+        match self.flavor {
+            NbtFlavor::BigEndian => self.w.write_i32::<BigEndian>(v).expect("Int"),
+            NbtFlavor::LittleEndian => self.w.write_i32::<LittleEndian>(v).expect("Int"),
+            NbtFlavor::Varint => write_var_u64(self.w, ((v << 1) ^ (v >> 31)) as u32 as u64),
+        }
+    }
+
+    fn write_i64(&mut self, v: i64) {
+        match self.flavor {
+            NbtFlavor::BigEndian => self.w.write_i64::<BigEndian>(v).expect("Long"),
+            NbtFlavor::LittleEndian => self.w.write_i64::<LittleEndian>(v).expect("Long"),
+            NbtFlavor::Varint => write_var_u64(self.w, ((v << 1) ^ (v >> 63)) as u64),
+        }
+    }
+
+    fn write_f32(&mut self, v: f32) {
+        match self.flavor {
+            NbtFlavor::BigEndian => self.w.write_f32::<BigEndian>(v),
+            _ => self.w.write_f32::<LittleEndian>(v),
+        }
+        .expect("Float");
+    }
+
+    fn write_f64(&mut self, v: f64) {
+        match self.flavor {
+            NbtFlavor::BigEndian => self.w.write_f64::<BigEndian>(v),
+            _ => self.w.write_f64::<LittleEndian>(v),
+        }
+        .expect("Double");
+    }
+
+    fn write_string(&mut self, str: &str) {
+        match self.flavor {
+            NbtFlavor::BigEndian => self
+                .w
+                .write_u16::<BigEndian>(str.len() as u16)
+                .expect("String length"),
+            NbtFlavor::LittleEndian => self
+                .w
+                .write_u16::<LittleEndian>(str.len() as u16)
+                .expect("String length"),
+            NbtFlavor::Varint => write_var_u64(self.w, str.len() as u64),
+        }
+
+        self.w.write_all(str.as_bytes()).expect("Write string");
+    }
+
+    fn write_tag(&mut self, tag: &NbtTag) {
+        match tag {
+            NbtTag::Byte(v) => self.w.write_i8(*v).expect("Byte"),
+            NbtTag::Short(v) => self.write_i16(*v),
+            NbtTag::Int(v) => self.write_i32(*v),
+            NbtTag::Long(v) => self.write_i64(*v),
+            NbtTag::Float(v) => self.write_f32(*v),
+            NbtTag::Double(v) => self.write_f64(*v),
+            NbtTag::ByteArray(arr) => {
+                self.write_i32(arr.len() as i32);
+                for v in arr {
+                    self.w.write_i8(*v).expect("Byte array");
+                }
+            }
+            NbtTag::String(str) => self.write_string(str),
+            NbtTag::List(list) => {
+                let element = list.iter().next().map(get_tag_id).unwrap_or(TAG_END);
+
+                self.w.write_u8(element).expect("List type");
+                self.write_i32(list.len() as i32);
+                for tag in list.iter() {
+                    self.write_tag(tag);
+                }
+            }
+            NbtTag::Compound(compound) => self.write_compound(compound),
+            NbtTag::IntArray(arr) => {
+                self.write_i32(arr.len() as i32);
+                for v in arr {
+                    self.write_i32(*v);
+                }
+            }
+            NbtTag::LongArray(arr) => {
+                self.write_i32(arr.len() as i32);
+                for v in arr {
+                    self.write_i64(*v);
+                }
+            }
+        }
+    }
+
+    fn write_compound(&mut self, compound: &NbtCompound) {
+        for (name, tag) in compound.inner() {
+            self.w.write_u8(get_tag_id(tag)).expect("Tag type");
+            self.write_string(name);
+            self.write_tag(tag);
+        }
+
+        self.w.write_u8(TAG_END).expect("End tag");
+    }
+}
+
+fn get_tag_id(tag: &NbtTag) -> u8 {
+    match tag {
+        NbtTag::Byte(_) => TAG_BYTE,
+        NbtTag::Short(_) => TAG_SHORT,
+        NbtTag::Int(_) => TAG_INT,
+        NbtTag::Long(_) => TAG_LONG,
+        NbtTag::Float(_) => TAG_FLOAT,
+        NbtTag::Double(_) => TAG_DOUBLE,
+        NbtTag::ByteArray(_) => TAG_BYTE_ARRAY,
+        NbtTag::String(_) => TAG_STRING,
+        NbtTag::List(_) => TAG_LIST,
+        NbtTag::Compound(_) => TAG_COMPOUND,
+        NbtTag::IntArray(_) => TAG_INT_ARRAY,
+        NbtTag::LongArray(_) => TAG_LONG_ARRAY,
+    }
+}
+
+/// Reads an uncompressed NBT root compound
+///
+/// # Returns
+///
+/// The root compound and its name
+pub fn read_nbt<R: Read>(r: &mut R, flavor: NbtFlavor) -> Result<(NbtCompound, String), String> {
+    let mut reader = NbtReader { r, flavor };
+
+    let id = reader.r.read_u8().map_err(|e| e.to_string())?;
+    if id != TAG_COMPOUND {
+        return Err(format!("NBT root is not a compound (tag {})", id));
+    }
+
+    let name = reader.read_string()?;
+    Ok((reader.read_compound()?, name))
+}
+
+/// Writes an uncompressed NBT root compound
+pub fn write_nbt<W: Write>(w: &mut W, name: &str, nbt: &NbtCompound, flavor: NbtFlavor) {
+    let mut writer = NbtWriter { w, flavor };
+
+    writer.w.write_u8(TAG_COMPOUND).expect("Root type");
+    writer.write_string(name);
+    writer.write_compound(nbt);
+}
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 // Thanks, @robofinch!
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Value {
     String(String),
     Vec(Vec<u8>),
@@ -14,6 +14,7 @@ pub enum Value {
     Uint32(u32),
     Uint16(u16),
     Uint8(u8),
+    Float32(f32),
     Bool(bool),
     // possible values
 }
@@ -36,6 +37,7 @@ hashmap_impl_type!(i16, Int16);
 hashmap_impl_type!(u16, Uint16);
 hashmap_impl_type!(i8, Int8);
 hashmap_impl_type!(u8, Uint8);
+hashmap_impl_type!(f32, Float32);
 hashmap_impl_type!(bool, Bool);
 
 pub trait HashMapExt {
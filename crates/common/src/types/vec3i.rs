@@ -1,4 +1,4 @@
-#[derive(Clone, Ord, Eq, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Ord, Eq, PartialEq, PartialOrd)]
 pub struct Vec3i {
     pub x: i32,
     pub y: i32,
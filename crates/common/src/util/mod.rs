@@ -49,7 +49,18 @@ pub enum McVersion {
     Release1_1,
     Release1_2_1,
     Release1_13,
+    Release1_14,
+    Release1_16,
+    MCPEAlpha0_1_0,
+    MCPEAlpha0_2_0,
+    MCPEAlpha0_3_0,
+    MCPEAlpha0_4_0,
+    MCPEAlpha0_5_0,
+    MCPEAlpha0_6_0,
+    MCPEAlpha0_7_0,
+    MCPEAlpha0_8_0,
     MCPEAlpha0_8_1,
+    Bedrock1_18_10,
 }
 
 /// Gets the 1D array index for a given 3D array index in YZX order
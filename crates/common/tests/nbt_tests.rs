@@ -0,0 +1,73 @@
+mod nbt_tests {
+    use lodestone_common::io::nbt::{read_nbt, write_nbt, NbtFlavor};
+    use quartz_nbt::io::{self, Flavor};
+    use quartz_nbt::{NbtCompound, NbtList};
+    use std::io::Cursor;
+
+    fn test_compound() -> NbtCompound {
+        let mut inner = NbtCompound::new();
+        inner.insert("Name", "minecraft:stone");
+        inner.insert("Negative", -300i32);
+
+        let mut nbt = NbtCompound::new();
+        nbt.insert("Byte", 5i8);
+        nbt.insert("Short", -2i16);
+        nbt.insert("Int", 123456789i32);
+        nbt.insert("Long", -9876543210i64);
+        nbt.insert("Float", 1.5f32);
+        nbt.insert("Double", -2.25f64);
+        nbt.insert("Bytes", vec![1i8, -1, 127]);
+        nbt.insert("Ints", vec![i32::MIN, 0, i32::MAX]);
+        nbt.insert("Longs", vec![i64::MIN, 0, i64::MAX]);
+        nbt.insert("List", NbtList::from(vec![inner.clone(), inner.clone()]));
+        nbt.insert("Empty", NbtList::new());
+        nbt.insert("Compound", inner);
+        nbt
+    }
+
+    #[test]
+    fn big_endian_matches_java() {
+        let nbt = test_compound();
+
+        let mut ours = Vec::new();
+        write_nbt(&mut ours, "root", &nbt, NbtFlavor::BigEndian);
+
+        let mut java = Vec::new();
+        io::write_nbt(&mut java, Some("root"), &nbt, Flavor::Uncompressed).unwrap();
+
+        let (read, name) = read_nbt(&mut Cursor::new(java), NbtFlavor::BigEndian).unwrap();
+        assert_eq!(name, "root");
+        assert_eq!(read, nbt);
+
+        let (read, _) = io::read_nbt(&mut Cursor::new(ours), Flavor::Uncompressed).unwrap();
+        assert_eq!(read, nbt);
+    }
+
+    #[test]
+    fn bedrock_flavors_round_trip() {
+        let nbt = test_compound();
+
+        for flavor in [NbtFlavor::LittleEndian, NbtFlavor::Varint] {
+            let mut out = Vec::new();
+            write_nbt(&mut out, "", &nbt, flavor);
+
+            let (read, name) = read_nbt(&mut Cursor::new(out), flavor).unwrap();
+            assert_eq!(name, "");
+            assert_eq!(read, nbt);
+        }
+    }
+
+    #[test]
+    fn little_endian_layout() {
+        let mut nbt = NbtCompound::new();
+        nbt.insert("a", 1i32);
+
+        let mut out = Vec::new();
+        write_nbt(&mut out, "", &nbt, NbtFlavor::LittleEndian);
+        assert_eq!(out, vec![10, 0, 0, 3, 1, 0, b'a', 1, 0, 0, 0, 0]);
+
+        let mut out = Vec::new();
+        write_nbt(&mut out, "", &nbt, NbtFlavor::Varint);
+        assert_eq!(out, vec![10, 0, 3, 1, b'a', 2, 0]);
+    }
+}
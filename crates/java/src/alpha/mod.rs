@@ -8,6 +8,7 @@ use lodestone_common::util::{base36, McVersion};
 use lodestone_level::block::conversion::get_internal_block_id;
 use lodestone_level::block::BlockId;
 use lodestone_level::entity::block_entity::BlockEntity;
+use lodestone_level::entity::stash::{get_stashed_entities, stash_entities, EntityFormat};
 use lodestone_level::level::chunk::{Chunk, CHUNK_LENGTH};
 use lodestone_level::level::{metadata, Coords, Level};
 use quartz_nbt::io::{write_nbt, Flavor};
@@ -77,6 +78,9 @@ impl AlphaChunk for Chunk {
             metadata::TERRAIN_POPULATED.to_string(),
             Bool(terrain_populated),
         );
+        if let Ok(entities) = root.get::<str, &NbtList>(metadata::ENTITIES) {
+            stash_entities(&mut chunk.custom_data, EntityFormat::Java, entities);
+        }
 
         // TODO: Batch this
         // TODO: Set state and lighting
@@ -148,8 +152,10 @@ impl AlphaChunk for Chunk {
         let mut chunk_nbt = NbtCompound::new();
         let mut chunk_level = NbtCompound::new();
 
-        // TODO: Implement entities properly
-        let _entities = chunk_level.insert(metadata::ENTITIES.to_string(), NbtList::new());
+        chunk_level.insert(
+            metadata::ENTITIES.to_string(),
+            get_stashed_entities(&self.custom_data, EntityFormat::Java),
+        );
         let mut tile_entities = NbtList::new();
         for (coords, tile_entity) in self.block_entities.iter() {
             let nbt = tile_entity.to_nbt(McVersion::Alpha1_2_6);
@@ -5,6 +5,7 @@ use lodestone_common::util::McVersion;
 use lodestone_level::block::conversion::get_internal_block_id;
 use lodestone_level::block::BlockId::Numeric;
 use lodestone_level::block::{Block, BlockId};
+use lodestone_level::entity::stash::{get_stashed_entities, stash_entities, EntityFormat};
 use lodestone_level::level::chunk::Chunk;
 use lodestone_level::level::chunk_section::BlockPaletteVec;
 use lodestone_level::level::region::{ChunkLocation, Compression};
@@ -520,7 +521,6 @@ impl AnvilChunk for Chunk {
         let z: i32 = level.get("zPos").expect("Chunk z position");
         let last_update: i64 = level.get("LastUpdate").expect("Chunk last modified");
         let inhabited_time: i64 = level.get("InhabitedTime").unwrap_or(0);
-        // let entities: &NbtList = level.get("Entities").expect("Chunk entities");
         // let tile_entities: &NbtList = level.get("TileEntities").expect("Chunk tile entities");
         let has_populated: i8 = level.get("TerrainPopulated").expect("Chunk has populated"); // DOES NOT EXIST ON 1.16 (TODO: DataVersion check to see what is available)
         let has_light_populated: i8 = level.get("LightPopulated").unwrap_or(1);
@@ -537,6 +537,9 @@ impl AnvilChunk for Chunk {
             .set_value(metadata::TERRAIN_POPULATED.to_string(), has_populated);
         c.custom_data
             .set_value(metadata::LIGHT_POPULATED.to_string(), has_light_populated);
+        if let Ok(entities) = level.get::<_, &NbtList>(metadata::ENTITIES) {
+            stash_entities(&mut c.custom_data, EntityFormat::Java, entities);
+        }
 
         // love sections
         let sections: &NbtList = level.get(metadata::SECTIONS).expect("Chunk sections");
@@ -595,8 +598,10 @@ impl AnvilChunk for Chunk {
             .unwrap_or(0);
         chunk_level.insert(metadata::INHABITED_TIME, inhabited_time);
 
-        // TODO: Implement entities properly
-        let _entities = chunk_level.insert(metadata::ENTITIES.to_string(), NbtList::new());
+        chunk_level.insert(
+            metadata::ENTITIES.to_string(),
+            get_stashed_entities(&self.custom_data, EntityFormat::Java),
+        );
         let _tile_entities =
             chunk_level.insert(metadata::TILE_ENTITIES.to_string(), NbtList::new());
 
@@ -1,24 +1,242 @@
-use lodestone_common::types::hashmap_ext::HashMapExt;
+use crate::classic::{get_classic_block, set_classic_block};
+use lodestone_common::types::hashmap_ext::{HashMapExt, Value};
 use lodestone_common::util::McVersion;
-use lodestone_level::block::conversion::{convert_blocks_from_internal_format, get_internal_block_id, get_version_block_id};
-use lodestone_level::block::BlockId;
+use lodestone_level::block::conversion::{get_internal_block_id, get_version_block_id};
+use lodestone_level::block::{Block, BlockId};
 use lodestone_level::level::chunk::{CHUNK_LENGTH, CHUNK_WIDTH};
+use lodestone_level::level::environment::{Rgb, Weather};
+use lodestone_level::level::export::FiniteLimits;
 use lodestone_level::level::metadata;
 use lodestone_level::level::metadata::UUID;
 use lodestone_level::level::Level;
 use quartz_nbt::io::{self, Flavor};
-use quartz_nbt::{NbtCompound, NbtReprError};
+use quartz_nbt::{NbtCompound, NbtReprError, NbtTag};
 use rayon::iter::IndexedParallelIterator;
 use rayon::iter::IntoParallelRefMutIterator;
 use rayon::iter::ParallelIterator;
 use std::io::Cursor;
 use uuid::Uuid;
-use lodestone_level::block::BlockId::NumericAndFlattened;
+
+/// Sizes are stored as shorts and the blocks as a single NBT byte array
+pub const CW_LIMITS: FiniteLimits = FiniteLimits {
+    max_width: i16::MAX as i32,
+    max_height: i16::MAX,
+    max_length: i16::MAX as i32,
+    max_volume: Some(i32::MAX as u64),
+};
 
 pub trait CWLevel {
     fn new_cw(height: i16, name: String, author: String) -> Level;
     fn read_cw(version: McVersion, data: Vec<u8>) -> Result<Level, String>;
-    fn write_cw(&mut self, version: McVersion) -> Vec<u8>;
+    fn write_cw(&mut self, version: McVersion) -> Result<Vec<u8>, String>;
+}
+
+/// Gets the key that a CPE metadata tag is stored under inside `Level.custom_data`
+///
+/// # Params
+///
+/// * `extension` - The CPE extension, e.g. [`metadata::CPE_ENV_COLORS`]
+/// * `key` - The tag inside of the extension, nested tags are separated by a `.` (e.g. `Sky.R`)
+pub fn cpe_metadata_key(extension: &str, key: &str) -> String {
+    format!("{}.{}.{}", metadata::CPE_METADATA, extension, key)
+}
+
+/// Flattens a CPE metadata tag (and all of its children) into the level's custom data
+fn read_cpe_tag(level: &mut Level, key: String, tag: &NbtTag) {
+    match tag {
+        NbtTag::Compound(c) => {
+            for (k, v) in c.inner() {
+                read_cpe_tag(level, format!("{}.{}", key, k), v);
+            }
+        }
+        NbtTag::Byte(v) => level.custom_data.set_value(key, *v),
+        NbtTag::Short(v) => level.custom_data.set_value(key, *v),
+        NbtTag::Int(v) => level.custom_data.set_value(key, *v),
+        NbtTag::Long(v) => level.custom_data.set_value(key, *v),
+        NbtTag::Float(v) => level.custom_data.set_value(key, *v),
+        NbtTag::String(v) => level.custom_data.set_value(key, v.clone()),
+        NbtTag::ByteArray(v) => level
+            .custom_data
+            .set_value(key, v.iter().map(|b| *b as u8).collect::<Vec<u8>>()),
+        _ => log::debug!("Skipping unsupported CPE metadata tag {}", key),
+    }
+}
+
+/// Puts a tag back into the CPE compound, creating any compounds along the way
+fn write_cpe_tag(compound: &mut NbtCompound, path: &[&str], tag: NbtTag) {
+    let [name, rest @ ..] = path else {
+        return;
+    };
+
+    if rest.is_empty() {
+        compound.insert(*name, tag);
+        return;
+    }
+
+    if compound.get::<_, &NbtCompound>(*name).is_err() {
+        compound.insert(*name, NbtCompound::new());
+    }
+
+    let child: &mut NbtCompound = compound.get_mut(*name).expect("CPE metadata compound");
+    write_cpe_tag(child, rest, tag);
+}
+
+/// Tags that are read into `Level.environment` instead of being kept in the custom data
+const CW_ENVIRONMENT_TAGS: [(&str, &str); 5] = [
+    (metadata::CPE_ENV_MAP_ASPECT, "CloudsHeight"),
+    (metadata::CPE_ENV_MAP_ASPECT, "EdgeHeight"),
+    (metadata::CPE_ENV_MAP_ASPECT, "SidesOffset"),
+    (metadata::CPE_ENV_MAP_ASPECT, "EdgeBlock"),
+    (metadata::CPE_ENV_MAP_ASPECT, "SideBlock"),
+];
+
+fn get_cpe_int(compound: &NbtCompound, key: &str) -> Option<i32> {
+    match compound.inner().get(key)? {
+        NbtTag::Byte(v) => Some(*v as u8 as i32),
+        NbtTag::Short(v) => Some(*v as i32),
+        NbtTag::Int(v) => Some(*v),
+        _ => None,
+    }
+}
+
+fn get_cpe_color(colors: &NbtCompound, keys: &[&str]) -> Option<Rgb> {
+    let color: &NbtCompound = keys.iter().find_map(|k| colors.get(*k).ok())?;
+
+    Rgb::from_components(
+        color.get("R").unwrap_or(-1),
+        color.get("G").unwrap_or(-1),
+        color.get("B").unwrap_or(-1),
+    )
+}
+
+fn get_cpe_block(version: McVersion, id: Option<i32>) -> Option<Block> {
+    get_internal_block_id(version, &BlockId::Numeric(id? as u16))
+}
+
+fn set_cpe_block(version: McVersion, blk: Option<Block>) -> Option<u16> {
+    usize::try_from(get_version_block_id(version, &blk?))
+        .ok()
+        .map(|id| id as u16)
+}
+
+/// Reads the CPE environment extensions into the level's environment
+fn read_cw_environment(level: &mut Level, version: McVersion, cpe: &NbtCompound) {
+    let env = &mut level.environment;
+
+    if let Ok(colors) = cpe.get::<_, &NbtCompound>(metadata::CPE_ENV_COLORS) {
+        env.sky_color = get_cpe_color(colors, &["Sky"]);
+        env.cloud_color = get_cpe_color(colors, &["Cloud"]);
+        env.fog_color = get_cpe_color(colors, &["Fog"]);
+        // ClassiCube calls these Shadow and Sun
+        env.shadow_color = get_cpe_color(colors, &["Ambient", "Shadow"]);
+        env.sunlight_color = get_cpe_color(colors, &["Sunlight", "Sun"]);
+    }
+
+    if let Ok(appearance) = cpe.get::<_, &NbtCompound>(metadata::CPE_ENV_MAP_APPEARANCE) {
+        env.texture_url = appearance
+            .get::<_, &str>("TextureURL")
+            .ok()
+            .filter(|url| !url.is_empty())
+            .map(str::to_string);
+        env.side_block = get_cpe_block(version, get_cpe_int(appearance, "SideBlock"));
+        env.edge_block = get_cpe_block(version, get_cpe_int(appearance, "EdgeBlock"));
+        env.edge_height = get_cpe_int(appearance, "SideLevel").map(|h| h as i16);
+    }
+
+    if let Ok(aspect) = cpe.get::<_, &NbtCompound>(metadata::CPE_ENV_MAP_ASPECT) {
+        if let Some(blk) = get_cpe_block(version, get_cpe_int(aspect, "SideBlock")) {
+            env.side_block = Some(blk);
+        }
+        if let Some(blk) = get_cpe_block(version, get_cpe_int(aspect, "EdgeBlock")) {
+            env.edge_block = Some(blk);
+        }
+        if let Some(h) = get_cpe_int(aspect, "EdgeHeight") {
+            env.edge_height = Some(h as i16);
+        }
+        if let Some(h) = get_cpe_int(aspect, "CloudsHeight") {
+            env.cloud_height = Some(h as i16);
+        }
+        if let Some(offset) = get_cpe_int(aspect, "SidesOffset") {
+            env.set_sides_offset(offset as i16);
+        }
+    }
+
+    if let Ok(weather) = cpe.get::<_, &NbtCompound>(metadata::CPE_ENV_WEATHER_TYPE) {
+        env.weather = get_cpe_int(weather, "WeatherType").and_then(|w| Weather::from_u8(w as u8));
+    }
+}
+
+/// Writes the level's environment into the CPE compound
+fn write_cw_environment(level: &Level, version: McVersion, cpe: &mut NbtCompound) {
+    let env = &level.environment;
+
+    let colors = [
+        ("Sky", env.sky_color),
+        ("Cloud", env.cloud_color),
+        ("Fog", env.fog_color),
+        ("Ambient", env.shadow_color),
+        ("Sunlight", env.sunlight_color),
+    ];
+
+    if colors.iter().any(|(_, c)| c.is_some()) {
+        let mut env_colors = NbtCompound::new();
+        env_colors.insert("ExtensionVersion", 1i32);
+
+        for (name, color) in colors {
+            let mut c = NbtCompound::new();
+            let (r, g, b) = color.map_or((-1, -1, -1), |c| (c.r as i16, c.g as i16, c.b as i16));
+            c.insert("R", r);
+            c.insert("G", g);
+            c.insert("B", b);
+            env_colors.insert(name, c);
+        }
+
+        cpe.insert(metadata::CPE_ENV_COLORS, env_colors);
+    }
+
+    let side_block = set_cpe_block(version, env.side_block);
+    let edge_block = set_cpe_block(version, env.edge_block);
+
+    if env.texture_url.is_some()
+        || side_block.is_some()
+        || edge_block.is_some()
+        || env.edge_height.is_some()
+    {
+        let mut appearance = NbtCompound::new();
+        appearance.insert("ExtensionVersion", 1i32);
+        appearance.insert("TextureURL", env.texture_url.clone().unwrap_or_default());
+        appearance.insert("SideBlock", side_block.unwrap_or(7) as u8 as i8);
+        appearance.insert("EdgeBlock", edge_block.unwrap_or(8) as u8 as i8);
+        appearance.insert(
+            "SideLevel",
+            env.edge_height
+                .unwrap_or(level.get_block_height() / 2),
+        );
+
+        cpe.insert(metadata::CPE_ENV_MAP_APPEARANCE, appearance);
+    }
+
+    let aspect = [
+        ("SideBlock", side_block.map(|b| NbtTag::Short(b as i16))),
+        ("EdgeBlock", edge_block.map(|b| NbtTag::Short(b as i16))),
+        ("EdgeHeight", env.edge_height.map(|h| NbtTag::Int(h as i32))),
+        ("CloudsHeight", env.cloud_height.map(|h| NbtTag::Int(h as i32))),
+        ("SidesOffset", env.get_sides_offset().map(|o| NbtTag::Int(o as i32))),
+    ];
+
+    for (key, tag) in aspect {
+        if let Some(tag) = tag {
+            write_cpe_tag(cpe, &[metadata::CPE_ENV_MAP_ASPECT, key], tag);
+        }
+    }
+
+    if let Some(weather) = env.weather {
+        let mut weather_type = NbtCompound::new();
+        weather_type.insert("ExtensionVersion", 1i32);
+        weather_type.insert("WeatherType", weather as u8 as i8);
+
+        cpe.insert(metadata::CPE_ENV_WEATHER_TYPE, weather_type);
+    }
 }
 
 impl CWLevel for Level {
@@ -117,6 +335,23 @@ impl CWLevel for Level {
         log::debug!("Reading block array");
 
         let blocks: &[u8] = nbt.get("BlockArray").expect("Level blocks");
+        // upper 8 bits of every block ID, only there if the level uses IDs above 255
+        let blocks2: Option<&[u8]> = nbt.get("BlockArray2").ok();
+        if let Some(b2) = blocks2
+            && b2.len() != blocks.len()
+        {
+            return Err(format!(
+                "BlockArray2 has {} entries but BlockArray has {}",
+                b2.len(),
+                blocks.len()
+            ));
+        }
+
+        let ids: Vec<u16> = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, b)| *b as u16 | blocks2.map_or(0, |b2| (b2[i] as u16) << 8))
+            .collect();
 
         log::debug!("Preparing level");
         let mut level = Level::new_with_name(name.to_owned());
@@ -139,6 +374,46 @@ impl CWLevel for Level {
             .custom_data
             .set_value(metadata::SPAWN_PITCH.to_string(), spawn_pitch);
 
+        if let Ok(blocks3) = nbt.get::<_, &[u8]>("BlockArray3") {
+            level
+                .custom_data
+                .set_value(metadata::CW_BLOCK_ARRAY_3.to_string(), blocks3.to_vec());
+        }
+
+        log::debug!("Reading CPE metadata");
+        if let Ok(cpe) = nbt
+            .get::<_, &NbtCompound>("Metadata")
+            .and_then(|m| m.get::<_, &NbtCompound>("CPE"))
+        {
+            for (extension, tag) in cpe.inner() {
+                match (extension.as_str(), tag) {
+                    // Fully read into the environment
+                    (
+                        metadata::CPE_ENV_COLORS
+                        | metadata::CPE_ENV_MAP_APPEARANCE
+                        | metadata::CPE_ENV_WEATHER_TYPE,
+                        _,
+                    ) => {}
+                    (_, NbtTag::Compound(c)) => {
+                        for (k, v) in c.inner() {
+                            if CW_ENVIRONMENT_TAGS.contains(&(extension.as_str(), k.as_str())) {
+                                continue;
+                            }
+
+                            read_cpe_tag(&mut level, cpe_metadata_key(extension, k), v);
+                        }
+                    }
+                    _ => read_cpe_tag(
+                        &mut level,
+                        format!("{}.{}", metadata::CPE_METADATA, extension),
+                        tag,
+                    ),
+                }
+            }
+
+            read_cw_environment(&mut level, version, cpe);
+        }
+
         log::debug!("Setting level spawn point");
         level.set_spawn_point(spawn_x as i32, spawn_y as i32, spawn_z as i32);
         log::debug!("Creating chunks");
@@ -149,7 +424,7 @@ impl CWLevel for Level {
         // let s = Instant::now();
         let chunks = level.get_chunks_mut();
 
-        let _ = chunks.par_iter_mut().for_each(|c| {
+        chunks.par_iter_mut().for_each(|c| {
             for y in 0..height {
                 for z in 0..CHUNK_LENGTH {
                     let lz = c.0.z * CHUNK_LENGTH as i32 + z as i32;
@@ -168,12 +443,8 @@ impl CWLevel for Level {
                             + (lz as usize) * (width as usize)
                             + (lx as usize);
 
-                        let blk =
-                            get_internal_block_id(version, &BlockId::Numeric(blocks[i] as u16));
-
-                        match blk {
-                            Some(blk) => c.1.set_block(x, y, z, blk),
-                            None => {}
+                        if ids[i] != 0 {
+                            set_classic_block(c.1, version, x, y, z, ids[i]);
                         }
                     }
                 }
@@ -183,7 +454,12 @@ impl CWLevel for Level {
         Ok(level)
     }
 
-    fn write_cw(&mut self, version: McVersion) -> Vec<u8> {
+    fn write_cw(&mut self, version: McVersion) -> Result<Vec<u8>, String> {
+        let bounds = self.get_finite_bounds();
+        CW_LIMITS
+            .check(bounds.width, bounds.height, bounds.length)
+            .map_err(|e| format!("Level of {} for ClassicWorld", e))?;
+
         let mut out: Vec<u8> = Vec::new();
 
         let mut mclvl = NbtCompound::new();
@@ -192,9 +468,9 @@ impl CWLevel for Level {
         let mut metadata = NbtCompound::new();
         let mut spawn_tag = NbtCompound::new();
 
-        let width = self.get_block_width() as usize;
-        let height = self.get_block_height() as usize;
-        let length = self.get_block_length() as usize;
+        let width = bounds.width as usize;
+        let height = bounds.height as usize;
+        let length = bounds.length as usize;
 
         mclvl.insert("FormatVersion".to_string(), 1i8);
         mclvl.insert("Name".to_string(), &self.name);
@@ -261,21 +537,73 @@ impl CWLevel for Level {
 
         mclvl.insert("Spawn", spawn_tag);
 
-        let mut blocks = vec![0u8; width * length * height];
-
-        let mx = self.get_min_block_x();
-        let mz = self.get_min_block_z();
+        let mut ids = vec![0u16; width * length * height];
 
-        blocks.par_iter_mut().enumerate().for_each(|(i, v)| {
+        ids.par_iter_mut().enumerate().for_each(|(i, v)| {
             let y = i / (length * width);
             let z = (i / width) % length;
             let x = i % width;
 
-            // TODO: this is REALLY messy, this needs to be focused on during code cleanup I fear.
-            *v = usize::try_from(get_version_block_id(version, &self.get_block(x as i32 + mx, y as i16, z as i32 + mz))).unwrap_or(0) as u8;
+            *v = get_classic_block(
+                self,
+                version,
+                x as i32 + bounds.x,
+                y as i16 + bounds.y,
+                z as i32 + bounds.z,
+            );
         });
 
-        mclvl.insert("BlockArray".to_string(), blocks);
+        mclvl.insert(
+            "BlockArray".to_string(),
+            ids.iter().map(|id| *id as u8).collect::<Vec<u8>>(),
+        );
+
+        if ids.iter().any(|id| *id > 0xFF) {
+            mclvl.insert(
+                "BlockArray2".to_string(),
+                ids.iter().map(|id| (*id >> 8) as u8).collect::<Vec<u8>>(),
+            );
+        }
+
+        if let Some(blocks3) = self
+            .custom_data
+            .get_value::<Vec<u8>, _>(metadata::CW_BLOCK_ARRAY_3)
+            .filter(|b| b.len() == ids.len())
+        {
+            mclvl.insert("BlockArray3".to_string(), blocks3);
+        }
+
+        let mut cpe = NbtCompound::new();
+        let prefix = format!("{}.", metadata::CPE_METADATA);
+
+        for (key, value) in &self.custom_data {
+            let Some(path) = key.strip_prefix(prefix.as_str()) else {
+                continue;
+            };
+
+            let tag = match value {
+                Value::String(v) => NbtTag::String(v.clone()),
+                Value::Vec(v) => NbtTag::ByteArray(v.iter().map(|b| *b as i8).collect()),
+                Value::Int64(v) => NbtTag::Long(*v),
+                Value::Int32(v) => NbtTag::Int(*v),
+                Value::Int16(v) => NbtTag::Short(*v),
+                Value::Int8(v) => NbtTag::Byte(*v),
+                Value::Uint64(v) => NbtTag::Long(*v as i64),
+                Value::Uint32(v) => NbtTag::Int(*v as i32),
+                Value::Uint16(v) => NbtTag::Short(*v as i16),
+                Value::Uint8(v) => NbtTag::Byte(*v as i8),
+                Value::Float32(v) => NbtTag::Float(*v),
+                Value::Bool(v) => NbtTag::Byte(*v as i8),
+            };
+
+            write_cpe_tag(&mut cpe, &path.split('.').collect::<Vec<&str>>(), tag);
+        }
+
+        write_cw_environment(self, version, &mut cpe);
+
+        if !cpe.inner().is_empty() {
+            metadata.insert("CPE", cpe);
+        }
 
         let mut lodestone_tag = NbtCompound::new();
         let mut level_info = NbtCompound::new();
@@ -290,6 +618,6 @@ impl CWLevel for Level {
         io::write_nbt(&mut out, Some("ClassicWorld"), &mclvl, Flavor::GzCompressed)
             .expect("Write compound");
 
-        out
+        Ok(out)
     }
 }
@@ -0,0 +1,446 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use flate2::read::{DeflateDecoder, GzDecoder};
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use lodestone_common::types::hashmap_ext::{HashMapExt, Value};
+use lodestone_common::util::McVersion;
+use lodestone_level::block::conversion::{get_internal_block_id, get_version_block_id};
+use lodestone_level::block::BlockId;
+use lodestone_level::level::chunk::{CHUNK_LENGTH, CHUNK_WIDTH};
+use lodestone_level::level::environment::Rgb;
+use lodestone_level::level::export::FiniteLimits;
+use lodestone_level::level::{metadata, Level};
+use rayon::iter::IndexedParallelIterator;
+use rayon::iter::IntoParallelRefMutIterator;
+use rayon::iter::ParallelIterator;
+use std::io::{Cursor, Read, Write};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+/// fCraft FCMv2 identifier
+pub const FCM_V2_IDENTIFIER: u32 = 0xFC000002;
+/// fCraft FCMv3 identifier (also used by 800Craft and LegendCraft)
+pub const FCM_V3_IDENTIFIER: u32 = 0x0FC2AF40;
+/// The only FCMv3 revision that was ever released
+pub const FCM_V3_REVISION: u8 = 13;
+
+/// Size of a single entry in the FCMv3 layer index
+const FCM_V3_LAYER_INDEX_SIZE: usize = 25;
+/// Layer type used for the block array
+const FCM_V3_BLOCK_LAYER: u8 = 0;
+
+/// Sizes are stored as shorts and the block layer length as an int
+pub const FCM_LIMITS: FiniteLimits = FiniteLimits {
+    max_width: i16::MAX as i32,
+    max_height: i16::MAX,
+    max_length: i16::MAX as i32,
+    max_volume: Some(i32::MAX as u64),
+};
+
+pub trait FCMLevel {
+    /// Reads an fCraft map, either FCMv2 or FCMv3
+    fn read_fcm(version: McVersion, data: Vec<u8>) -> Result<Level, String>;
+    /// Writes out the level as an FCMv3 map, fails if it doesn't fit in [`FCM_LIMITS`]
+    fn write_fcm(&self, version: McVersion) -> Result<Vec<u8>, String>;
+}
+
+/// Gets the key that an fCraft metadata entry is stored under inside `Level.custom_data`
+///
+/// # Params
+///
+/// * `group` - The metadata group (e.g. `zones`)
+/// * `key` - The key inside of the group
+pub fn fcm_metadata_key(group: &str, key: &str) -> String {
+    format!("{}.{}.{}", metadata::FCM_METADATA, group, key)
+}
+
+/// Splits a `Level.custom_data` key back into its fCraft metadata group and key
+pub fn split_fcm_metadata_key(key: &str) -> Option<(&str, &str)> {
+    key.strip_prefix(metadata::FCM_METADATA)?
+        .strip_prefix('.')?
+        .split_once('.')
+}
+
+/// Environment entries (in the `environment` group) that are read into `Level.environment`
+const FCM_ENV_KEYS: [&str; 4] = ["SkyColor", "CloudColor", "FogColor", "EdgeLevel"];
+
+/// Moves the environment metadata entries into the level's environment
+fn read_fcm_environment(level: &mut Level) {
+    for key in FCM_ENV_KEYS {
+        // fCraft uses -1 for the default value
+        let Some(value) = level
+            .custom_data
+            .remove(&fcm_metadata_key(metadata::FCM_ENVIRONMENT, key))
+            .and_then(|v| String::try_from(v).ok())
+            .and_then(|v| v.trim().parse::<i32>().ok())
+            .filter(|v| *v >= 0)
+        else {
+            continue;
+        };
+
+        let env = &mut level.environment;
+        match key {
+            "SkyColor" => env.sky_color = Some(Rgb::from_packed(value)),
+            "CloudColor" => env.cloud_color = Some(Rgb::from_packed(value)),
+            "FogColor" => env.fog_color = Some(Rgb::from_packed(value)),
+            "EdgeLevel" => env.edge_height = Some(value as i16),
+            _ => {}
+        }
+    }
+}
+
+/// Gets the level's environment as metadata entries
+fn write_fcm_environment(level: &Level) -> Vec<(&'static str, String)> {
+    let env = &level.environment;
+    let color = |c: Option<Rgb>| c.map_or(-1, |c| c.to_packed()).to_string();
+
+    vec![
+        ("SkyColor", color(env.sky_color)),
+        ("CloudColor", color(env.cloud_color)),
+        ("FogColor", color(env.fog_color)),
+        ("EdgeLevel", env.edge_height.map_or(-1, |h| h as i32).to_string()),
+    ]
+}
+
+fn read_fcm_string<R: Read>(r: &mut R) -> String {
+    let len = r.read_u16::<LittleEndian>().expect("String length") as usize;
+
+    let mut str = vec![0u8; len];
+    r.read_exact(&mut str).expect("Read string");
+
+    String::from_utf8_lossy(&str).into_owned()
+}
+
+fn write_fcm_string<W: Write>(w: &mut W, str: &str) {
+    if str.len() > u16::MAX as usize {
+        panic!("String is too long to store inside an FCM metadata entry");
+    }
+
+    w.write_u16::<LittleEndian>(str.len() as u16)
+        .expect("String length");
+    w.write_all(str.as_bytes()).expect("Write string");
+}
+
+// FCMv2 uses 4 byte lengths instead
+fn read_fcm_v2_string<R: Read>(r: &mut R) -> String {
+    let len = r.read_i32::<LittleEndian>().expect("String length") as usize;
+
+    let mut str = vec![0u8; len];
+    r.read_exact(&mut str).expect("Read string");
+
+    String::from_utf8_lossy(&str).into_owned()
+}
+
+fn read_blocks_into_level(
+    level: &mut Level,
+    version: McVersion,
+    blocks: &[u8],
+    width: i16,
+    height: i16,
+    length: i16,
+) {
+    level.create_finite(width as i32, height, length as i32);
+
+    let chunks = level.get_chunks_mut();
+
+    chunks.par_iter_mut().for_each(|c| {
+        for y in 0..height {
+            for z in 0..CHUNK_LENGTH {
+                let lz = c.0.z * CHUNK_LENGTH as i32 + z as i32;
+                if lz >= length as i32 {
+                    continue;
+                }
+
+                for x in 0..CHUNK_WIDTH {
+                    let lx = c.0.x * CHUNK_WIDTH as i32 + x as i32;
+                    if lx >= width as i32 {
+                        continue;
+                    }
+
+                    // same YZX ordering as every other classic format
+                    let i = (y as usize) * (length as usize) * (width as usize)
+                        + (lz as usize) * (width as usize)
+                        + (lx as usize);
+
+                    let blk = get_internal_block_id(version, &BlockId::Numeric(blocks[i] as u16));
+
+                    if let Some(blk) = blk {
+                        c.1.set_block(x, y, z, blk);
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn read_fcm_v2(version: McVersion, mut c: Cursor<Vec<u8>>) -> Result<Level, String> {
+    // FCMv2 stores the vertical axis last
+    let width = c.read_i16::<LittleEndian>().expect("Width");
+    let length = c.read_i16::<LittleEndian>().expect("Length");
+    let height = c.read_i16::<LittleEndian>().expect("Height");
+
+    let spawn_x = c.read_i16::<LittleEndian>().expect("Spawn X");
+    let spawn_z = c.read_i16::<LittleEndian>().expect("Spawn Z");
+    let spawn_y = c.read_i16::<LittleEndian>().expect("Spawn Y");
+    let spawn_yaw = c.read_u8().expect("Spawn yaw");
+    let spawn_pitch = c.read_u8().expect("Spawn pitch");
+
+    log::debug!("w: {}, h: {}, l: {}", width, height, length);
+
+    let mut level = Level::new();
+    // positions are stored in player units (1/32 of a block)
+    level.set_spawn_point(
+        spawn_x as i32 / 32,
+        spawn_y as i32 / 32,
+        spawn_z as i32 / 32,
+    );
+    level
+        .custom_data
+        .set_value::<u8>(metadata::SPAWN_YAW.to_string(), spawn_yaw);
+    level
+        .custom_data
+        .set_value::<u8>(metadata::SPAWN_PITCH.to_string(), spawn_pitch);
+
+    // FCMv2 has no metadata groups, everything ends up in the origin group
+    let meta_count = c.read_u16::<LittleEndian>().expect("Metadata count");
+    for _ in 0..meta_count {
+        let key = read_fcm_v2_string(&mut c);
+        let value = read_fcm_v2_string(&mut c);
+
+        level
+            .custom_data
+            .set_value(fcm_metadata_key(metadata::FCM_ORIGIN, &key), value);
+    }
+
+    let mut blocks: Vec<u8> = vec![0; (width as usize) * (length as usize) * (height as usize)];
+    GzDecoder::new(&mut c)
+        .read_exact(&mut blocks)
+        .map_err(|e| format!("Failed to read block array: {}", e))?;
+
+    read_blocks_into_level(&mut level, version, &blocks, width, height, length);
+
+    Ok(level)
+}
+
+fn read_fcm_v3(version: McVersion, mut c: Cursor<Vec<u8>>) -> Result<Level, String> {
+    let revision = c.read_u8().expect("Revision");
+    if revision != FCM_V3_REVISION {
+        return Err(format!(
+            "Unsupported FCMv3 revision {}, only revision {} is supported",
+            revision, FCM_V3_REVISION
+        ));
+    }
+
+    let width = c.read_i16::<LittleEndian>().expect("Width");
+    let height = c.read_i16::<LittleEndian>().expect("Height");
+    let length = c.read_i16::<LittleEndian>().expect("Length");
+
+    // Yes, Z comes before Y here.
+    let spawn_x = c.read_i32::<LittleEndian>().expect("Spawn X");
+    let spawn_z = c.read_i32::<LittleEndian>().expect("Spawn Z");
+    let spawn_y = c.read_i32::<LittleEndian>().expect("Spawn Y");
+    let spawn_yaw = c.read_u8().expect("Spawn yaw");
+    let spawn_pitch = c.read_u8().expect("Spawn pitch");
+
+    let last_modified = c.read_u32::<LittleEndian>().expect("Date modified");
+    let created = c.read_u32::<LittleEndian>().expect("Date created");
+
+    let mut uuid = vec![0u8; 16];
+    c.read_exact(&mut uuid).expect("Level UUID");
+
+    // We only care about the block layer, which is always first.
+    let layer_count = c.read_u8().expect("Layer count");
+    c.set_position(c.position() + (layer_count as usize * FCM_V3_LAYER_INDEX_SIZE) as u64);
+
+    let meta_count = c.read_u32::<LittleEndian>().expect("Metadata count");
+
+    log::debug!("w: {}, h: {}, l: {}", width, height, length);
+    log::debug!("Layers: {}, metadata entries: {}", layer_count, meta_count);
+
+    let mut level = Level::new();
+    level.set_spawn_point(spawn_x / 32, spawn_y / 32, spawn_z / 32);
+    level
+        .custom_data
+        .set_value::<u8>(metadata::SPAWN_YAW.to_string(), spawn_yaw);
+    level
+        .custom_data
+        .set_value::<u8>(metadata::SPAWN_PITCH.to_string(), spawn_pitch);
+    level
+        .custom_data
+        .set_value(metadata::LAST_MODIFIED.to_string(), last_modified as i64);
+    level
+        .custom_data
+        .set_value(metadata::CREATION_TIME.to_string(), created as i64);
+    level
+        .custom_data
+        .set_value(metadata::UUID.to_string(), uuid);
+
+    // metadata and blocks share the same deflate stream
+    let mut d = DeflateDecoder::new(&mut c);
+
+    for _ in 0..meta_count {
+        let group = read_fcm_string(&mut d);
+        let key = read_fcm_string(&mut d);
+        let value = read_fcm_string(&mut d);
+
+        level
+            .custom_data
+            .set_value(fcm_metadata_key(&group, &key), value);
+    }
+
+    read_fcm_environment(&mut level);
+
+    let mut blocks: Vec<u8> = vec![0; (width as usize) * (length as usize) * (height as usize)];
+    d.read_exact(&mut blocks)
+        .map_err(|e| format!("Failed to read block array: {}", e))?;
+
+    read_blocks_into_level(&mut level, version, &blocks, width, height, length);
+
+    Ok(level)
+}
+
+impl FCMLevel for Level {
+    fn read_fcm(version: McVersion, data: Vec<u8>) -> Result<Level, String> {
+        let mut c = Cursor::new(data);
+        let identifier = c.read_u32::<LittleEndian>().expect("Identifier");
+
+        match identifier {
+            FCM_V2_IDENTIFIER => read_fcm_v2(version, c),
+            FCM_V3_IDENTIFIER => read_fcm_v3(version, c),
+            _ => Err(format!(
+                "Identifier {:#010X} does not match FCMv2 or FCMv3",
+                identifier
+            )),
+        }
+    }
+
+    fn write_fcm(&self, version: McVersion) -> Result<Vec<u8>, String> {
+        let bounds = self.get_finite_bounds();
+        FCM_LIMITS
+            .check(bounds.width, bounds.height, bounds.length)
+            .map_err(|e| format!("Level of {} for FCMv3", e))?;
+
+        let mut c = Cursor::new(Vec::new());
+
+        let width = bounds.width as usize;
+        let height = bounds.height as usize;
+        let length = bounds.length as usize;
+
+        c.write_u32::<LittleEndian>(FCM_V3_IDENTIFIER)
+            .expect("Identifier");
+        c.write_u8(FCM_V3_REVISION).expect("Revision");
+
+        c.write_i16::<LittleEndian>(width as i16).expect("Width");
+        c.write_i16::<LittleEndian>(height as i16).expect("Height");
+        c.write_i16::<LittleEndian>(length as i16).expect("Length");
+
+        c.write_i32::<LittleEndian>(self.spawn.x * 32)
+            .expect("Spawn X");
+        c.write_i32::<LittleEndian>(self.spawn.z * 32)
+            .expect("Spawn Z");
+        c.write_i32::<LittleEndian>(self.spawn.y * 32)
+            .expect("Spawn Y");
+        c.write_u8(
+            self.custom_data
+                .get_value::<u8, _>(metadata::SPAWN_YAW)
+                .unwrap_or(0),
+        )
+        .expect("Spawn yaw");
+        c.write_u8(
+            self.custom_data
+                .get_value::<u8, _>(metadata::SPAWN_PITCH)
+                .unwrap_or(0),
+        )
+        .expect("Spawn pitch");
+
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("Time since unix epoch")
+            .as_secs() as i64;
+
+        c.write_u32::<LittleEndian>(now as u32)
+            .expect("Date modified");
+        c.write_u32::<LittleEndian>(
+            self.custom_data
+                .get_value::<i64, _>(metadata::CREATION_TIME)
+                .unwrap_or(now) as u32,
+        )
+        .expect("Date created");
+
+        let uuid = self
+            .custom_data
+            .get_value::<Vec<u8>, _>(metadata::UUID)
+            .filter(|u| u.len() == 16)
+            .unwrap_or(Uuid::new_v4().as_bytes().to_vec());
+        c.write_all(&uuid).expect("Level UUID");
+
+        // Only the block layer is written, the index gets filled in once we know the compressed size.
+        c.write_u8(1).expect("Layer count");
+        let index_position = c.position();
+        c.write_all(&[0u8; FCM_V3_LAYER_INDEX_SIZE])
+            .expect("Layer index");
+
+        let mut meta: Vec<((&str, &str), String)> = self
+            .custom_data
+            .iter()
+            .filter_map(|(k, v)| match (split_fcm_metadata_key(k), v) {
+                (Some((metadata::FCM_ENVIRONMENT, key)), _) if FCM_ENV_KEYS.contains(&key) => None,
+                (Some(gk), Value::String(s)) => Some((gk, s.clone())),
+                _ => None,
+            })
+            .collect();
+        meta.extend(
+            write_fcm_environment(self)
+                .into_iter()
+                .map(|(key, value)| ((metadata::FCM_ENVIRONMENT, key), value)),
+        );
+
+        c.write_u32::<LittleEndian>(meta.len() as u32)
+            .expect("Metadata count");
+
+        let data_position = c.position();
+
+        let mut blocks = vec![0u8; width * length * height];
+
+        blocks.par_iter_mut().enumerate().for_each(|(i, v)| {
+            let y = i / (length * width);
+            let z = (i / width) % length;
+            let x = i % width;
+
+            *v = usize::try_from(get_version_block_id(
+                version,
+                &self.get_block(
+                    x as i32 + bounds.x,
+                    y as i16 + bounds.y,
+                    z as i32 + bounds.z,
+                ),
+            ))
+            .unwrap_or(0) as u8;
+        });
+
+        let mut e = DeflateEncoder::new(&mut c, Compression::default());
+        for ((group, key), value) in meta {
+            write_fcm_string(&mut e, group);
+            write_fcm_string(&mut e, key);
+            write_fcm_string(&mut e, &value);
+        }
+        e.write_all(&blocks).expect("Block array");
+        e.finish().expect("Finish deflate stream");
+
+        let compressed_length = c.position() - data_position;
+
+        c.set_position(index_position);
+        c.write_u8(FCM_V3_BLOCK_LAYER).expect("Layer type");
+        c.write_i64::<LittleEndian>(data_position as i64)
+            .expect("Layer offset");
+        c.write_i32::<LittleEndian>(compressed_length as i32)
+            .expect("Layer compressed length");
+        c.write_i32::<LittleEndian>(blocks.len() as i32)
+            .expect("Layer uncompressed length");
+        c.write_i32::<LittleEndian>(1).expect("Layer element size");
+        c.write_i32::<LittleEndian>(blocks.len() as i32)
+            .expect("Layer element count");
+
+        Ok(c.into_inner())
+    }
+}
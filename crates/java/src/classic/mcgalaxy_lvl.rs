@@ -1,20 +1,149 @@
+use crate::classic::{get_classic_block, set_classic_block};
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
-use lodestone_common::types::hashmap_ext::HashMapExt;
+use lodestone_common::types::hashmap_ext::{HashMapExt, Value};
 use lodestone_common::util::McVersion;
 use lodestone_level::block::conversion::{get_internal_block_id, get_version_block_id};
-use lodestone_level::block::BlockId;
+use lodestone_level::block::{Block, BlockId};
 use lodestone_level::level::chunk::{CHUNK_LENGTH, CHUNK_WIDTH};
+use lodestone_level::level::environment::{Environment, Rgb, Weather};
+use lodestone_level::level::export::FiniteLimits;
 use lodestone_level::level::{metadata, Level};
 use rayon::iter::IndexedParallelIterator;
 use rayon::iter::ParallelIterator;
 use rayon::prelude::IntoParallelRefMutIterator;
 use std::io::{Cursor, Read, Write};
-use lodestone_level::block::BlockId::NumericAndFlattened;
+use std::path::{Path, PathBuf};
+
+/// Section holding the lower byte of every block ID above 255, split into 16x16x16 chunks
+const MCG_CUSTOM_BLOCKS_SECTION: u8 = 0xBD;
+/// Section holding the scheduled physics checks
+const MCG_PHYSICS_SECTION: u8 = 0xFC;
+/// Main block array placeholders for extended block IDs (256-511, 512-767 and 768-1023)
+const MCG_CUSTOM_BLOCK: [u8; 3] = [163, 198, 199];
+/// Size of a custom block chunk
+const MCG_CUSTOM_CHUNK_SIZE: usize = 16;
+/// Sizes are stored as shorts, MCGalaxy indexes the block array with an int
+pub const MCG_LIMITS: FiniteLimits = FiniteLimits {
+    max_width: i16::MAX as i32,
+    max_height: i16::MAX,
+    max_length: i16::MAX as i32,
+    max_volume: Some(i32::MAX as u64),
+};
 
 pub trait MCGLevel {
     fn read_mcgalaxy_level(version: McVersion, data: Vec<u8>) -> Result<Level, String>;
     fn get_mcgalaxy_level_file_size(&self) -> usize;
-    fn write_mcgalaxy_level(&self, version: McVersion) -> Vec<u8>;
+    fn write_mcgalaxy_level(&self, version: McVersion) -> Result<Vec<u8>, String>;
+    /// Reads MCGalaxy's companion `<name>.properties` file into the level
+    fn read_mcgalaxy_properties(&mut self, data: Vec<u8>);
+    /// Writes out every MCGalaxy level property as a `.properties` file
+    fn write_mcgalaxy_properties(&self) -> Vec<u8>;
+}
+
+/// Gets the key that an MCGalaxy level property is stored under inside `Level.custom_data`
+pub fn mcg_property_key(key: &str) -> String {
+    format!("{}.{}", metadata::MCG_PROPERTIES, key)
+}
+
+/// Gets the path of a level's properties file from MCGalaxy's `levels` directory
+///
+/// # Params
+///
+/// * `levels_dir` - The server's `levels` directory
+/// * `name` - The name of the level (without the `.lvl` extension)
+pub fn get_mcgalaxy_properties_path(levels_dir: &Path, name: &str) -> PathBuf {
+    levels_dir
+        .join("level properties")
+        .join(format!("{}.properties", name))
+}
+
+/// Level properties that are read into `Level.environment`
+const MCG_ENV_PROPERTIES: [&str; 11] = [
+    "SkyColor",
+    "CloudColor",
+    "FogColor",
+    "ShadowColor",
+    "LightColor",
+    "EdgeBlock",
+    "HorizonBlock",
+    "EdgeLevel",
+    "SidesOffset",
+    "CloudsHeight",
+    "Weather",
+];
+
+/// Reads an environment property into the environment
+///
+/// # Returns
+///
+/// `true` if the property was an environment property
+fn read_mcgalaxy_env_property(env: &mut Environment, key: &str, value: &str) -> bool {
+    // MCGalaxy uses -1 (or 255 for blocks) for the default value
+    let int = value.parse::<i32>().ok().filter(|v| *v >= 0);
+    let block = int
+        .filter(|b| *b != 255)
+        .and_then(|b| get_internal_block_id(McVersion::Classic0_30, &BlockId::Numeric(b as u16)));
+
+    match key {
+        "SkyColor" => env.sky_color = Rgb::from_hex(value),
+        "CloudColor" => env.cloud_color = Rgb::from_hex(value),
+        "FogColor" => env.fog_color = Rgb::from_hex(value),
+        "ShadowColor" => env.shadow_color = Rgb::from_hex(value),
+        "LightColor" => env.sunlight_color = Rgb::from_hex(value),
+        "EdgeBlock" => env.side_block = block,
+        "HorizonBlock" => env.edge_block = block,
+        "EdgeLevel" => env.edge_height = int.map(|h| h as i16),
+        // read once everything else is, since it depends on the edge level
+        "SidesOffset" => return false,
+        "CloudsHeight" => env.cloud_height = int.map(|h| h as i16),
+        "Weather" => env.weather = value.parse::<u8>().ok().and_then(Weather::from_u8),
+        _ => return false,
+    }
+
+    true
+}
+
+/// Gets the environment as MCGalaxy level properties
+fn write_mcgalaxy_env_properties(level: &Level) -> Vec<(String, String)> {
+    let env = &level.environment;
+    let hex = |c: Option<Rgb>| c.map(|c| c.to_hex()).unwrap_or_default();
+    let int = |v: Option<i16>| v.unwrap_or(-1).to_string();
+    let block = |b: Option<Block>| {
+        b.and_then(|b| usize::try_from(get_version_block_id(McVersion::Classic0_30, &b)).ok())
+            .unwrap_or(255)
+            .to_string()
+    };
+
+    let mut properties = vec![
+        ("SkyColor", hex(env.sky_color)),
+        ("CloudColor", hex(env.cloud_color)),
+        ("FogColor", hex(env.fog_color)),
+        ("ShadowColor", hex(env.shadow_color)),
+        ("LightColor", hex(env.sunlight_color)),
+        ("EdgeBlock", block(env.side_block)),
+        ("HorizonBlock", block(env.edge_block)),
+        ("EdgeLevel", int(env.edge_height)),
+        ("SidesOffset", int(env.get_sides_offset())),
+        ("CloudsHeight", int(env.cloud_height)),
+    ];
+
+    if let Some(weather) = env.weather {
+        properties.push(("Weather", (weather as u8).to_string()));
+    }
+
+    properties
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect()
+}
+
+/// Gets the amount of 16x16x16 custom block chunks along each axis
+fn get_custom_chunk_counts(width: usize, height: usize, length: usize) -> (usize, usize, usize) {
+    (
+        width.div_ceil(MCG_CUSTOM_CHUNK_SIZE),
+        height.div_ceil(MCG_CUSTOM_CHUNK_SIZE),
+        length.div_ceil(MCG_CUSTOM_CHUNK_SIZE),
+    )
 }
 
 impl MCGLevel for Level {
@@ -59,13 +188,73 @@ impl MCGLevel for Level {
         c.read_exact(&mut blocks)
             .expect("Failed to read block array");
 
+        let mut ids: Vec<u16> = blocks.iter().map(|b| *b as u16).collect();
+
+        // Trailing sections are optional, older levels end right after the block array
+        while let Ok(section) = c.read_u8() {
+            match section {
+                MCG_CUSTOM_BLOCKS_SECTION => {
+                    let (cw, ch, cl) =
+                        get_custom_chunk_counts(width as usize, height as usize, length as usize);
+
+                    for cy in 0..ch {
+                        for cz in 0..cl {
+                            for cx in 0..cw {
+                                if c.read_u8().expect("Custom block chunk flag") != 1 {
+                                    continue;
+                                }
+
+                                let mut chunk = vec![0u8; MCG_CUSTOM_CHUNK_SIZE.pow(3)];
+                                c.read_exact(&mut chunk)
+                                    .expect("Failed to read custom block chunk");
+
+                                for (ci, ext) in chunk.iter().enumerate() {
+                                    let x = cx * MCG_CUSTOM_CHUNK_SIZE + ci % 16;
+                                    let z = cz * MCG_CUSTOM_CHUNK_SIZE + (ci / 16) % 16;
+                                    let y = cy * MCG_CUSTOM_CHUNK_SIZE + ci / 256;
+                                    if x >= width as usize
+                                        || y >= height as usize
+                                        || z >= length as usize
+                                    {
+                                        continue;
+                                    }
+
+                                    let i = (y * length as usize + z) * width as usize + x;
+                                    if let Some(p) =
+                                        MCG_CUSTOM_BLOCK.iter().position(|b| *b == blocks[i])
+                                    {
+                                        ids[i] = ((p as u16 + 1) << 8) | *ext as u16;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                MCG_PHYSICS_SECTION => {
+                    let count = c.read_i32::<LittleEndian>().expect("Physics check count");
+
+                    // index + flags, we keep these as-is
+                    let mut physics = vec![0u8; count.max(0) as usize * 8];
+                    c.read_exact(&mut physics)
+                        .expect("Failed to read physics checks");
+
+                    mcg.custom_data
+                        .set_value(metadata::MCG_PHYSICS_DATA.to_string(), physics);
+                }
+                _ => {
+                    log::debug!("Unknown MCGalaxy section {:#04X}, stopping", section);
+                    break;
+                }
+            }
+        }
+
         mcg.create_finite(width as i32, height, length as i32);
 
         log::debug!("Read into chunks");
 
         let chunks = mcg.get_chunks_mut();
 
-        let _ = chunks.par_iter_mut().for_each(|c| {
+        chunks.par_iter_mut().for_each(|c| {
             for y in 0..height {
                 for z in 0..CHUNK_LENGTH {
                     let lz = c.0.z * CHUNK_LENGTH as i32 + z as i32;
@@ -84,12 +273,8 @@ impl MCGLevel for Level {
                             + (lz as usize) * (width as usize)
                             + (lx as usize);
 
-                        let blk =
-                            get_internal_block_id(version, &BlockId::Numeric(blocks[i] as u16));
-
-                        match blk {
-                            Some(blk) => c.1.set_block(x, y, z, blk),
-                            None => {}
+                        if ids[i] != 0 {
+                            set_classic_block(c.1, version, x, y, z, ids[i]);
                         }
                     }
                 }
@@ -100,6 +285,8 @@ impl MCGLevel for Level {
     }
 
     fn get_mcgalaxy_level_file_size(&self) -> usize {
+        let bounds = self.get_finite_bounds();
+
         2 // signature
         + 2 // width
         + 2 // length
@@ -111,17 +298,22 @@ impl MCGLevel for Level {
         + 1 // spawn_pitch
         + 1 // min_access_perm
         + 1 // min_build_perm
-        + (self.get_block_width() as usize * self.get_block_height() as usize * self.get_block_length() as usize) // block array
+        + (bounds.width as usize * bounds.height as usize * bounds.length as usize) // block array
     }
 
-    fn write_mcgalaxy_level(&self, version: McVersion) -> Vec<u8> {
+    fn write_mcgalaxy_level(&self, version: McVersion) -> Result<Vec<u8>, String> {
+        let bounds = self.get_finite_bounds();
+        MCG_LIMITS
+            .check(bounds.width, bounds.height, bounds.length)
+            .map_err(|e| format!("Level of {} for MCGalaxy", e))?;
+
         let mut c = Cursor::new(vec![0u8; self.get_mcgalaxy_level_file_size()]);
         c.write_u16::<LittleEndian>(1874u16)
             .expect("Unable to write signature!");
 
-        let width = self.get_block_width();
-        let length = self.get_block_length();
-        let height = self.get_block_height();
+        let width = bounds.width;
+        let length = bounds.length;
+        let height = bounds.height;
 
         c.write_i16::<LittleEndian>(width as i16)
             .expect("Unable to write level width!");
@@ -163,22 +355,141 @@ impl MCGLevel for Level {
         c.write_u8(min_build_perm)
             .expect("Unable to write minimum build permissions!");
 
-        let mut blocks = vec![0u8; width as usize * length as usize * height as usize];
-
-        let mx = self.get_min_block_x();
-        let mz = self.get_min_block_z();
+        let mut ids = vec![0u16; width as usize * length as usize * height as usize];
 
-        // Write blocks
-        blocks.par_iter_mut().enumerate().for_each(|(i, v)| {
+        ids.par_iter_mut().enumerate().for_each(|(i, v)| {
             let y = i / (length as usize * width as usize);
             let z = (i / width as usize) % length as usize;
             let x = i % width as usize;
 
-            *v = usize::try_from(get_version_block_id(version, &self.get_block(x as i32 + mx, y as i16, z as i32 + mz))).unwrap_or(0) as u8;
+            *v = get_classic_block(
+                self,
+                version,
+                x as i32 + bounds.x,
+                y as i16 + bounds.y,
+                z as i32 + bounds.z,
+            );
         });
 
+        // Write blocks
+        let blocks: Vec<u8> = ids
+            .iter()
+            .map(|id| match id >> 8 {
+                0 => *id as u8,
+                n => MCG_CUSTOM_BLOCK[(n as usize - 1).min(MCG_CUSTOM_BLOCK.len() - 1)],
+            })
+            .collect();
+
         c.write_all(blocks.as_slice()).expect("Block array");
 
-        c.into_inner()
+        // Write custom blocks
+        c.write_u8(MCG_CUSTOM_BLOCKS_SECTION)
+            .expect("Unable to write custom blocks section!");
+
+        let (cw, ch, cl) =
+            get_custom_chunk_counts(width as usize, height as usize, length as usize);
+
+        for cy in 0..ch {
+            for cz in 0..cl {
+                for cx in 0..cw {
+                    let mut chunk = vec![0u8; MCG_CUSTOM_CHUNK_SIZE.pow(3)];
+                    let mut has_custom = false;
+
+                    for (ci, ext) in chunk.iter_mut().enumerate() {
+                        let x = cx * MCG_CUSTOM_CHUNK_SIZE + ci % 16;
+                        let z = cz * MCG_CUSTOM_CHUNK_SIZE + (ci / 16) % 16;
+                        let y = cy * MCG_CUSTOM_CHUNK_SIZE + ci / 256;
+                        if x >= width as usize || y >= height as usize || z >= length as usize {
+                            continue;
+                        }
+
+                        let id = ids[(y * length as usize + z) * width as usize + x];
+                        if id > 0xFF {
+                            *ext = id as u8;
+                            has_custom = true;
+                        }
+                    }
+
+                    if has_custom {
+                        c.write_u8(1).expect("Unable to write custom block chunk flag!");
+                        c.write_all(&chunk)
+                            .expect("Unable to write custom block chunk!");
+                    } else {
+                        c.write_u8(0).expect("Unable to write custom block chunk flag!");
+                    }
+                }
+            }
+        }
+
+        // Write physics checks
+        if let Some(physics) = self
+            .custom_data
+            .get_value::<Vec<u8>, &str>(metadata::MCG_PHYSICS_DATA)
+        {
+            c.write_u8(MCG_PHYSICS_SECTION)
+                .expect("Unable to write physics section!");
+            c.write_i32::<LittleEndian>((physics.len() / 8) as i32)
+                .expect("Unable to write physics check count!");
+            c.write_all(&physics[..physics.len() / 8 * 8])
+                .expect("Unable to write physics checks!");
+        }
+
+        Ok(c.into_inner())
+    }
+
+    fn read_mcgalaxy_properties(&mut self, data: Vec<u8>) {
+        let properties = String::from_utf8_lossy(&data);
+
+        for line in properties.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line.split_once('=') {
+                Some((key, value)) => {
+                    let (key, value) = (key.trim(), value.trim());
+
+                    if !read_mcgalaxy_env_property(&mut self.environment, key, value) {
+                        self.custom_data
+                            .set_value(mcg_property_key(key), value.to_string());
+                    }
+                }
+                None => log::debug!("Skipping malformed property line: {}", line),
+            }
+        }
+
+        if let Some(offset) = self
+            .custom_data
+            .remove(&mcg_property_key("SidesOffset"))
+            .and_then(|v| String::try_from(v).ok())
+            .and_then(|v| v.parse::<i16>().ok())
+        {
+            self.environment.set_sides_offset(offset);
+        }
+    }
+
+    fn write_mcgalaxy_properties(&self) -> Vec<u8> {
+        let prefix = format!("{}.", metadata::MCG_PROPERTIES);
+
+        let mut properties: Vec<(String, String)> = self
+            .custom_data
+            .iter()
+            .filter_map(|(k, v)| match (k.strip_prefix(prefix.as_str()), v) {
+                (Some(key), Value::String(value)) if !MCG_ENV_PROPERTIES.contains(&key) => {
+                    Some((key.to_string(), value.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        properties.extend(write_mcgalaxy_env_properties(self));
+        properties.sort();
+
+        let mut out = String::from("#Level properties\n");
+        for (key, value) in properties {
+            out.push_str(&format!("{} = {}\n", key, value));
+        }
+
+        out.into_bytes()
     }
 }
@@ -2,15 +2,24 @@ use lodestone_common::util::McVersion;
 use lodestone_level::block::conversion::{get_internal_block_id, get_version_block_id};
 use lodestone_level::block::BlockId;
 use lodestone_level::level::chunk::{CHUNK_LENGTH, CHUNK_WIDTH};
+use lodestone_level::level::export::FiniteLimits;
 use lodestone_level::level::Level;
 use rayon::iter::IndexedParallelIterator;
 use rayon::iter::IntoParallelRefMutIterator;
 use rayon::iter::ParallelIterator;
 use lodestone_level::block::BlockId::NumericAndFlattened;
 
+/// .mine v1 levels are always 256x64x256
+pub const MINEV1_LIMITS: FiniteLimits = FiniteLimits {
+    max_width: 256,
+    max_height: 64,
+    max_length: 256,
+    max_volume: None,
+};
+
 pub trait MineV1Level {
     fn read_minev1(version: McVersion, data: Vec<u8>) -> Result<Level, String>;
-    fn write_minev1(&self, version: McVersion) -> Vec<u8>;
+    fn write_minev1(&self, version: McVersion) -> Result<Vec<u8>, String>;
     fn get_minev1_file_size() -> usize;
 }
 
@@ -54,18 +63,12 @@ impl MineV1Level for Level {
         Ok(level)
     }
 
-    fn write_minev1(&self, version: McVersion) -> Vec<u8> {
-        let width = self.get_block_width() as usize;
-        let length = self.get_block_length();
-        let height = self.get_block_height() as usize;
-
-        if width > 256 || length > 256 || height > 256 {
-            println!(
-                "WARN: Level bounds are too large for .mine v1's limited size of 256x64x256 (XYZ). Your level size is X: {}, Y: {}, Z: {}",
-                width, height, length
-            );
-            println!("The level will be truncated to fit within these strict bounds.");
-        }
+    fn write_minev1(&self, version: McVersion) -> Result<Vec<u8>, String> {
+        // Use export_finite with MINEV1_LIMITS beforehand to pick what gets cut off
+        let bounds = self.get_finite_bounds();
+        MINEV1_LIMITS
+            .check(bounds.width, bounds.height, bounds.length)
+            .map_err(|e| format!("Level of {} for .mine v1", e))?;
 
         let mut blocks = vec![0u8; Self::get_minev1_file_size()];
 
@@ -75,10 +78,10 @@ impl MineV1Level for Level {
             let z = (i / 256) % 256;
             let x = i % 256;
 
-            *v = usize::try_from(get_version_block_id(version, &self.get_block(x as i32, y as i16, z as i32))).unwrap_or(0) as u8;
+            *v = usize::try_from(get_version_block_id(version, &self.get_block(bounds.x + x as i32, bounds.y + y as i16, bounds.z + z as i32).get_classic_fallback())).unwrap_or(0) as u8;
         });
 
-        blocks
+        Ok(blocks)
     }
 
     fn get_minev1_file_size() -> usize {
@@ -5,16 +5,25 @@ use lodestone_common::util::McVersion;
 use lodestone_level::block::conversion::{get_internal_block_id, get_version_block_id};
 use lodestone_level::block::BlockId;
 use lodestone_level::level::chunk::{CHUNK_LENGTH, CHUNK_WIDTH};
+use lodestone_level::level::export::FiniteLimits;
 use lodestone_level::level::{metadata, Level};
 use rayon::prelude::*;
 use std::io::{Cursor, Read, Write};
 use std::time::SystemTime;
 use lodestone_level::block::BlockId::NumericAndFlattened;
 
+/// Sizes are stored as shorts, readers index the block array with an int
+pub const MINEV2_LIMITS: FiniteLimits = FiniteLimits {
+    max_width: i16::MAX as i32,
+    max_height: i16::MAX,
+    max_length: i16::MAX as i32,
+    max_volume: Some(i32::MAX as u64),
+};
+
 pub trait MineV2Level {
     fn new_minev2(width: i16, height: i16, length: i16, name: String, author: String) -> Level;
     fn read_minev2(version: McVersion, data: Vec<u8>) -> Result<Level, String>;
-    fn write_minev2(&mut self, version: McVersion) -> Vec<u8>;
+    fn write_minev2(&mut self, version: McVersion) -> Result<Vec<u8>, String>;
     fn get_minev2_file_size(&self) -> usize;
 }
 
@@ -121,7 +130,12 @@ impl MineV2Level for Level {
         Ok(level)
     }
 
-    fn write_minev2(&mut self, version: McVersion) -> Vec<u8> {
+    fn write_minev2(&mut self, version: McVersion) -> Result<Vec<u8>, String> {
+        let bounds = self.get_finite_bounds();
+        MINEV2_LIMITS
+            .check(bounds.width, bounds.height, bounds.length)
+            .map_err(|e| format!("Level of {} for .mine v2", e))?;
+
         let mut c = Cursor::new(vec![0u8; self.get_minev2_file_size()]);
 
         c.write_i32::<BigEndian>(0x271BB788)
@@ -142,12 +156,9 @@ impl MineV2Level for Level {
         )
         .expect("Creation timestamp");
 
-        let width = self.get_block_width() as usize;
-        let depth = self.get_block_length() as usize;
-        let height = self.get_block_height() as usize;
-
-        let mx = self.get_min_block_x();
-        let mz = self.get_min_block_z();
+        let width = bounds.width as usize;
+        let depth = bounds.length as usize;
+        let height = bounds.height as usize;
 
         c.write_i16::<BigEndian>(width as i16).expect("Width");
         c.write_i16::<BigEndian>(depth as i16).expect("Depth");
@@ -160,12 +171,12 @@ impl MineV2Level for Level {
             let z = (i / width) % depth;
             let x = i % width;
 
-            *v = usize::try_from(get_version_block_id(version, &self.get_block(x as i32, y as i16, z as i32))).unwrap_or(0) as u8;
+            *v = usize::try_from(get_version_block_id(version, &self.get_block(bounds.x + x as i32, bounds.y + y as i16, bounds.z + z as i32).get_classic_fallback())).unwrap_or(0) as u8;
         });
 
         c.write_all(blocks.as_slice()).expect("Block array");
 
-        c.into_inner()
+        Ok(c.into_inner())
     }
 
     fn get_minev2_file_size(&self) -> usize {
@@ -182,9 +193,10 @@ impl MineV2Level for Level {
         size += 2; // length
         size += 2; // height
 
-        size += (self.get_block_width() as usize)
-            * (self.get_block_height() as usize)
-            * (self.get_block_length() as usize);
+        let bounds = self.get_finite_bounds();
+        size += (bounds.width as usize)
+            * (bounds.height as usize)
+            * (bounds.length as usize);
 
         size
     }
@@ -1,4 +1,46 @@
 pub mod classic_world;
+pub mod fcm;
 pub mod mcgalaxy_lvl;
 pub mod mine_v1;
 pub mod mine_v2;
+
+use lodestone_common::util::McVersion;
+use lodestone_level::block::conversion::{get_internal_block_id, get_version_block_id};
+use lodestone_level::block::{Block, BlockId};
+use lodestone_level::level::chunk::Chunk;
+use lodestone_level::level::{metadata, Level};
+
+/// Sets a classic block ID inside of a chunk
+///
+/// IDs that have no internal block (server defined blocks) are kept as `Block::Custom`
+/// so that they can be written back out.
+pub fn set_classic_block(chunk: &mut Chunk, version: McVersion, x: i8, y: i16, z: i8, id: u16) {
+    match get_internal_block_id(version, &BlockId::Numeric(id)) {
+        Some(blk) => chunk.set_block(x, y, z, blk),
+        None => {
+            chunk.set_block(x, y, z, Block::Custom);
+            chunk.set_state(
+                x,
+                y,
+                z,
+                metadata::CUSTOM_BLOCK_ID.to_string(),
+                id.to_string(),
+            );
+        }
+    }
+}
+
+/// Gets the classic block ID at the given position, including the IDs of `Block::Custom` blocks
+pub fn get_classic_block(level: &Level, version: McVersion, x: i32, y: i16, z: i32) -> u16 {
+    let blk = level.get_block(x, y, z);
+
+    if blk == Block::Custom {
+        return level
+            .get_states(x, y, z)
+            .and_then(|s| s.get(metadata::CUSTOM_BLOCK_ID))
+            .and_then(|id| id.parse::<u16>().ok())
+            .unwrap_or(0);
+    }
+
+    usize::try_from(get_version_block_id(version, &blk)).unwrap_or(0) as u16
+}
@@ -1,8 +1,10 @@
 use lodestone_common::types::hashmap_ext::HashMapExt;
 use lodestone_common::util::McVersion;
 use lodestone_level::block::conversion::{get_internal_block_id, get_version_block_id};
-use lodestone_level::block::BlockId;
+use lodestone_level::block::{Block, BlockId};
 use lodestone_level::level::chunk::{CHUNK_LENGTH, CHUNK_WIDTH};
+use lodestone_level::level::environment::{Environment, Rgb};
+use lodestone_level::level::export::FiniteLimits;
 use lodestone_level::level::metadata;
 use lodestone_level::level::Level;
 use quartz_nbt::io::{self, Flavor};
@@ -11,10 +13,18 @@ use rayon::iter::IntoParallelRefMutIterator;
 use rayon::iter::ParallelIterator;
 use std::io::Cursor;
 
+/// Sizes are stored as shorts and the blocks as a single NBT byte array
+pub const INDEV_LIMITS: FiniteLimits = FiniteLimits {
+    max_width: i16::MAX as i32,
+    max_height: i16::MAX,
+    max_length: i16::MAX as i32,
+    max_volume: Some(i32::MAX as u64),
+};
+
 pub trait IndevLevel {
     fn new_indev(height: i16, name: String, author: String) -> Level;
     fn read_indev(version: McVersion, data: Vec<u8>) -> Result<Level, String>;
-    fn write_indev(&mut self, version: McVersion) -> Vec<u8>;
+    fn write_indev(&mut self, version: McVersion) -> Result<Vec<u8>, String>;
 }
 
 impl IndevLevel for Level {
@@ -139,42 +149,34 @@ impl IndevLevel for Level {
             .set_value(metadata::AUTHOR.to_string(), author.to_string());
         // Environment
         level.time = time as i64;
-        level
-            .custom_data
-            .set_value(metadata::SKY_BRIGHTNESS.to_string(), sky_brightness);
-        level
-            .custom_data
-            .set_value(metadata::SKY_COLOR.to_string(), sky_color);
-        level
-            .custom_data
-            .set_value(metadata::FOG_COLOR.to_string(), fog_color);
-        level
-            .custom_data
-            .set_value(metadata::CLOUD_COLOR.to_string(), cloud_color);
-        level
-            .custom_data
-            .set_value(metadata::CLOUD_HEIGHT.to_string(), cloud_height);
-        level.custom_data.set_value(
-            metadata::SURROUNDING_GROUND_TYPE.to_string(),
-            surrounding_ground_type,
-        );
-        level.custom_data.set_value(
-            metadata::SURROUNDING_GROUND_HEIGHT.to_string(),
-            surrounding_ground_height,
-        );
-        level.custom_data.set_value(
-            metadata::SURROUNDING_WATER_TYPE.to_string(),
-            surrounding_water_type,
-        );
-        level.custom_data.set_value(
-            metadata::SURROUNDING_WATER_HEIGHT.to_string(),
-            surrounding_water_height,
-        );
+        level.environment = Environment {
+            sky_color: Some(Rgb::from_packed(sky_color)),
+            fog_color: Some(Rgb::from_packed(fog_color)),
+            cloud_color: Some(Rgb::from_packed(cloud_color)),
+            sky_brightness: Some(sky_brightness as u8),
+            cloud_height: Some(cloud_height),
+            edge_block: get_internal_block_id(
+                version,
+                &BlockId::Numeric(surrounding_water_type as u8 as u16),
+            ),
+            edge_height: Some(surrounding_water_height),
+            side_block: get_internal_block_id(
+                version,
+                &BlockId::Numeric(surrounding_ground_type as u8 as u16),
+            ),
+            side_height: Some(surrounding_ground_height),
+            ..Default::default()
+        };
 
         Ok(level)
     }
 
-    fn write_indev(&mut self, version: McVersion) -> Vec<u8> {
+    fn write_indev(&mut self, version: McVersion) -> Result<Vec<u8>, String> {
+        let bounds = self.get_finite_bounds();
+        INDEV_LIMITS
+            .check(bounds.width, bounds.height, bounds.length)
+            .map_err(|e| format!("Level of {} for Indev", e))?;
+
         let mut out: Vec<u8> = Vec::new();
 
         let mut mclvl = NbtCompound::new();
@@ -201,64 +203,58 @@ impl IndevLevel for Level {
 
         // env
         env.insert("TimeOfDay".to_string(), self.time as i16);
+        let environment = &self.environment;
+        let block_id = |blk: Option<Block>, default: i8| {
+            blk.and_then(|b| usize::try_from(get_version_block_id(version, &b)).ok())
+                .map_or(default, |id| id as i8)
+        };
+
         env.insert(
             "SkyBrightness".to_string(),
-            self.custom_data
-                .get_value::<i8, _>(metadata::SKY_BRIGHTNESS)
-                .unwrap_or(15),
+            environment.sky_brightness.map_or(15, |b| b as i8),
         );
         env.insert(
             "SkyColor".to_string(),
-            self.custom_data
-                .get_value::<i32, _>(metadata::SKY_COLOR)
-                .unwrap_or(0x99CCFF),
+            environment.sky_color.map_or(0x99CCFF, |c| c.to_packed()),
         );
         env.insert(
             "FogColor".to_string(),
-            self.custom_data
-                .get_value::<i32, _>(metadata::FOG_COLOR)
-                .unwrap_or(0xFFFFFF),
+            environment.fog_color.map_or(0xFFFFFF, |c| c.to_packed()),
         );
         env.insert(
             "CloudColor".to_string(),
-            self.custom_data
-                .get_value::<i32, _>(metadata::CLOUD_COLOR)
-                .unwrap_or(0xFFFFFF),
+            environment.cloud_color.map_or(0xFFFFFF, |c| c.to_packed()),
         );
         env.insert(
             "CloudHeight".to_string(),
-            self.custom_data
-                .get_value::<i16, _>(metadata::CLOUD_HEIGHT)
+            environment
+                .cloud_height
                 .unwrap_or(self.get_max_block_y() / 2),
         );
         env.insert(
             "SurroundingGroundType".to_string(),
-            self.custom_data
-                .get_value::<i8, _>(metadata::SURROUNDING_GROUND_TYPE)
-                .unwrap_or(2),
+            block_id(environment.side_block, 2),
         );
         env.insert(
             "SurroundingGroundHeight".to_string(),
-            self.custom_data
-                .get_value::<i16, _>(metadata::SURROUNDING_GROUND_HEIGHT)
+            environment
+                .side_height
                 .unwrap_or(self.get_max_block_y() / 3),
         );
         env.insert(
             "SurroundingWaterType".to_string(),
-            self.custom_data
-                .get_value::<i8, _>(metadata::SURROUNDING_WATER_TYPE)
-                .unwrap_or(8),
+            block_id(environment.edge_block, 8),
         );
         env.insert(
             "SurroundingWaterHeight".to_string(),
-            self.custom_data
-                .get_value::<i16, _>(metadata::SURROUNDING_WATER_HEIGHT)
+            environment
+                .edge_height
                 .unwrap_or(self.get_max_block_y() / 2),
         );
 
-        let width = self.get_block_width();
-        let length = self.get_block_length();
-        let height = self.get_block_height();
+        let width = bounds.width;
+        let length = bounds.length;
+        let height = bounds.height;
 
         // map
         map.insert("Width".to_string(), width as i16);
@@ -280,15 +276,14 @@ impl IndevLevel for Level {
             Vec::with_capacity((width as usize) * (length as usize) * (height as usize));
         data.resize((width as usize) * (length as usize) * (height as usize), 0);
 
-        let mx = self.get_min_block_x();
-        let mz = self.get_min_block_z();
+        let (mx, my, mz) = (bounds.x, bounds.y, bounds.z);
 
         for y in 0..height {
             for z in 0..length {
                 for x in 0..width {
                     blocks[(y as usize) * (length as usize * width as usize)
                         + (z as usize) * (width as usize)
-                        + (x as usize)] = usize::try_from(get_version_block_id(version, &self.get_block(x + mx, y, z + mz))).unwrap_or(0) as i8;
+                        + (x as usize)] = usize::try_from(get_version_block_id(version, &self.get_block(x + mx, y + my, z + mz))).unwrap_or(0) as i8;
                 }
             }
         }
@@ -312,6 +307,6 @@ impl IndevLevel for Level {
         )
         .unwrap();
 
-        out
+        Ok(out)
     }
 }
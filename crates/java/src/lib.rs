@@ -5,4 +5,6 @@ pub mod alpha;
 pub mod anvil;
 pub mod classic;
 pub mod indev;
+pub mod map;
 pub mod mcregion;
+pub mod schematic;
@@ -0,0 +1,271 @@
+use lodestone_common::util::McVersion;
+use lodestone_level::block::palette::MapColor;
+use lodestone_level::level::render::MapImage;
+use lodestone_level::level::Level;
+use quartz_nbt::io::{self, Flavor};
+use quartz_nbt::{NbtCompound, NbtTag};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+/// Maps are always 128x128 pixels
+pub const MAP_SIZE: u32 = 128;
+/// Most zoomed out a map can be, 2^4 blocks per pixel
+pub const MAX_MAP_SCALE: i8 = 4;
+
+/// An in-game map item, stored as `data/map_<id>.dat`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapItem {
+    /// 2^scale blocks per pixel
+    pub scale: i8,
+    /// 0 for the overworld, -1 for the nether and 1 for the end
+    pub dimension: i8,
+    pub x_center: i32,
+    pub z_center: i32,
+    /// Whether player markers are shown
+    pub tracking_position: bool,
+    /// Map color IDs, the base color ID times 4 plus the shade
+    pub colors: Vec<u8>,
+}
+
+/// Gets the center of the map a player at `x`, `z` would get, maps snap to a grid the size of a map
+pub fn get_map_center(x: i32, z: i32, scale: i8) -> (i32, i32) {
+    let size = (MAP_SIZE as i32) << scale;
+    let snap = |v: i32| (v + 64).div_euclid(size) * size + size / 2 - 64;
+
+    (snap(x), snap(z))
+}
+
+fn get_dimension_name(dimension: i8) -> &'static str {
+    match dimension {
+        -1 => "minecraft:the_nether",
+        1 => "minecraft:the_end",
+        _ => "minecraft:overworld",
+    }
+}
+
+/// Gets the DataVersion of a 1.13+ target, so the game only upgrades what is actually older
+fn get_data_version(version: McVersion) -> i32 {
+    if version >= McVersion::Release1_16 {
+        2566
+    } else if version >= McVersion::Release1_14 {
+        1952
+    } else {
+        1519
+    }
+}
+
+impl MapItem {
+    /// Reads a `map_<id>.dat` file
+    pub fn read_map_item(data: Vec<u8>) -> Result<MapItem, String> {
+        let nbt = io::read_nbt(&mut Cursor::new(&data), Flavor::GzCompressed)
+            .map_err(|e| format!("Failed to read map NBT: {}", e))?
+            .0;
+        let root: &NbtCompound = nbt.get("data").map_err(|e| e.to_string())?;
+
+        // a byte before 1.13, an int until 1.16 and a name since then
+        let dimension = match root.inner().get("dimension") {
+            Some(NbtTag::Byte(d)) => *d,
+            Some(NbtTag::Int(d)) => *d as i8,
+            Some(NbtTag::String(d)) => match d.as_str() {
+                "minecraft:the_nether" => -1,
+                "minecraft:the_end" => 1,
+                _ => 0,
+            },
+            _ => 0,
+        };
+
+        let colors: &[u8] = root.get("colors").map_err(|e| e.to_string())?;
+        if colors.len() != (MAP_SIZE * MAP_SIZE) as usize {
+            return Err(format!(
+                "Map has {} colors instead of {}",
+                colors.len(),
+                MAP_SIZE * MAP_SIZE
+            ));
+        }
+
+        Ok(MapItem {
+            scale: root.get("scale").unwrap_or(0),
+            dimension,
+            x_center: root.get("xCenter").map_err(|e| e.to_string())?,
+            z_center: root.get("zCenter").map_err(|e| e.to_string())?,
+            tracking_position: root.get::<_, i8>("trackingPosition").unwrap_or(1) != 0,
+            colors: colors.to_vec(),
+        })
+    }
+
+    /// Writes a `map_<id>.dat` file
+    pub fn write_map_item(&self, version: McVersion) -> Vec<u8> {
+        let mut out = Vec::new();
+
+        let mut data = NbtCompound::new();
+        data.insert("scale", self.scale);
+        data.insert("xCenter", self.x_center);
+        data.insert("zCenter", self.z_center);
+        data.insert("trackingPosition", self.tracking_position as i8);
+        data.insert("colors", self.colors.clone());
+
+        let mut root = NbtCompound::new();
+        if version >= McVersion::Release1_16 {
+            data.insert("dimension", get_dimension_name(self.dimension));
+        } else if version >= McVersion::Release1_13 {
+            data.insert("dimension", self.dimension as i32);
+        } else {
+            data.insert("dimension", self.dimension);
+        }
+        if version >= McVersion::Release1_13 {
+            data.insert("unlimitedTracking", 0i8);
+            root.insert("DataVersion", get_data_version(version));
+        } else {
+            data.insert("width", MAP_SIZE as i16);
+            data.insert("height", MAP_SIZE as i16);
+        }
+        // Locked maps only came in with the cartography table
+        if version >= McVersion::Release1_14 {
+            data.insert("locked", 1i8);
+        }
+        root.insert("data", data);
+
+        io::write_nbt(&mut out, None, &root, Flavor::GzCompressed).expect("Write map compound");
+
+        out
+    }
+
+    /// Draws the map's colors, unexplored pixels are transparent
+    pub fn to_image(&self) -> MapImage {
+        let mut img = MapImage::new(MAP_SIZE, MAP_SIZE);
+
+        for (i, id) in self.colors.iter().enumerate() {
+            if let Some(color) = MapColor::from_id(id >> 2) {
+                let rgb = color.get_shaded_rgb(id & 3);
+                img.set_pixel(
+                    i as u32 % MAP_SIZE,
+                    i as u32 / MAP_SIZE,
+                    [rgb.r, rgb.g, rgb.b, 0xFF],
+                );
+            }
+        }
+
+        img
+    }
+}
+
+/// Writes maps into a world's `data` directory as `map_0.dat` onwards, along with `idcounts.dat`
+pub fn write_map_items(
+    data_dir: &Path,
+    maps: &[MapItem],
+    version: McVersion,
+) -> Result<(), String> {
+    if maps.is_empty() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(data_dir).map_err(|e| e.to_string())?;
+
+    for (id, map) in maps.iter().enumerate() {
+        fs::write(
+            data_dir.join(format!("map_{}.dat", id)),
+            map.write_map_item(version),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    // highest map ID in use, so new maps don't overwrite ours
+    let last = maps.len() - 1;
+    let mut out = Vec::new();
+    if version >= McVersion::Release1_13 {
+        let mut data = NbtCompound::new();
+        data.insert("map", last as i32);
+
+        let mut root = NbtCompound::new();
+        root.insert("data", data);
+        root.insert("DataVersion", get_data_version(version));
+        io::write_nbt(&mut out, None, &root, Flavor::GzCompressed)
+            .expect("Write idcounts compound");
+    } else {
+        let mut root = NbtCompound::new();
+        root.insert("map", last as i16);
+        io::write_nbt(&mut out, None, &root, Flavor::Uncompressed)
+            .expect("Write idcounts compound");
+    }
+
+    fs::write(data_dir.join("idcounts.dat"), out).map_err(|e| e.to_string())
+}
+
+/// Reads every `map_<id>.dat` in a world's `data` directory, sorted by ID
+pub fn read_map_items(data_dir: &Path) -> Result<Vec<(u32, MapItem)>, String> {
+    let mut maps = Vec::new();
+
+    for entry in fs::read_dir(data_dir).map_err(|e| e.to_string())? {
+        let p = entry.map_err(|e| e.to_string())?.path();
+        let Some(id) = p.file_name().and_then(|n| n.to_str()).and_then(|n| {
+            n.strip_prefix("map_")?
+                .strip_suffix(".dat")?
+                .parse::<u32>()
+                .ok()
+        }) else {
+            continue;
+        };
+
+        let data = fs::read(&p).map_err(|e| e.to_string())?;
+        match MapItem::read_map_item(data) {
+            Ok(map) => maps.push((id, map)),
+            Err(e) => log::warn!("Skipping map {}: {}", id, e),
+        }
+    }
+
+    maps.sort_by_key(|(id, _)| *id);
+    Ok(maps)
+}
+
+pub trait MapItemLevel {
+    /// Draws a map of the level centered on `x_center`, `z_center`, like the game would once it's fully explored
+    ///
+    /// Use [`get_map_center`] to snap the center to the same grid as maps made in-game.
+    fn create_map_item(&self, x_center: i32, z_center: i32, scale: i8, dimension: i8) -> MapItem;
+    /// Draws maps covering the level's finite bounds (the box around all chunks for infinite levels), row by row
+    /// from the north west corner, to put on a wall of item frames
+    fn create_wall_map_items(&self, scale: i8, dimension: i8) -> Vec<MapItem>;
+}
+
+impl MapItemLevel for Level {
+    fn create_map_item(&self, x_center: i32, z_center: i32, scale: i8, dimension: i8) -> MapItem {
+        let scale = scale.clamp(0, MAX_MAP_SCALE);
+        let blocks = 1u32 << scale;
+        let half = (MAP_SIZE * blocks / 2) as i32;
+
+        let colors = self
+            .get_map_colors(x_center - half, z_center - half, MAP_SIZE, MAP_SIZE, blocks)
+            .into_iter()
+            .map(|p| p.map_or(0, |(color, shade)| color.get_id() * 4 + shade))
+            .collect();
+
+        MapItem {
+            scale,
+            dimension,
+            x_center,
+            z_center,
+            tracking_position: true,
+            colors,
+        }
+    }
+
+    fn create_wall_map_items(&self, scale: i8, dimension: i8) -> Vec<MapItem> {
+        let bounds = self.get_finite_bounds();
+        let size = (MAP_SIZE as i32) << scale.clamp(0, MAX_MAP_SCALE);
+
+        let mut maps = Vec::new();
+        for z in 0..bounds.length.max(1).div_ceil(size) {
+            for x in 0..bounds.width.max(1).div_ceil(size) {
+                maps.push(self.create_map_item(
+                    bounds.x + x * size + size / 2,
+                    bounds.z + z * size + size / 2,
+                    scale,
+                    dimension,
+                ));
+            }
+        }
+
+        maps
+    }
+}
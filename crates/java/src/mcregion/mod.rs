@@ -4,6 +4,7 @@ use lodestone_common::types::hashmap_ext::HashMapExt;
 use lodestone_common::util::McVersion;
 use lodestone_level::block::conversion::get_internal_block_id;
 use lodestone_level::block::BlockId;
+use lodestone_level::entity::stash::{get_stashed_entities, stash_entities, EntityFormat};
 use lodestone_level::level::chunk::Chunk;
 use lodestone_level::level::region::ChunkLocation;
 use lodestone_level::level::region::Compression;
@@ -244,7 +245,6 @@ impl MCRChunk for Chunk {
         // let sky_light: &[u8] = level.get("SkyLight").expect("Chunk sky light");
         // let block_light: &[u8] = level.get("BlockLight").expect("Chunk block light");
         // let height_map: &[u8] = level.get("HeightMap").expect("Chunk heightmap");
-        // let entities: &NbtList = level.get("Entities").expect("Chunk entities");
         // let tile_entities: &NbtList = level.get("TileEntities").expect("Chunk tile entities");
         let has_populated: i8 = level.get("TerrainPopulated").expect("Chunk has populated");
 
@@ -254,6 +254,9 @@ impl MCRChunk for Chunk {
             .set_value(metadata::LAST_UPDATE.to_string(), last_update);
         c.custom_data
             .set_value(metadata::TERRAIN_POPULATED.to_string(), has_populated);
+        if let Ok(entities) = level.get::<_, &NbtList>("Entities") {
+            stash_entities(&mut c.custom_data, EntityFormat::Java, entities);
+        }
 
         for y in 0..128 {
             for z in 0..16 {
@@ -316,7 +319,10 @@ impl MCRChunk for Chunk {
                 .collect::<Vec<u8>>(),
         );
         c.insert("LastUpdate".to_string(), 0i64);
-        c.insert("Entities".to_string(), NbtList::new());
+        c.insert(
+            "Entities".to_string(),
+            get_stashed_entities(&self.custom_data, EntityFormat::Java),
+        );
         c.insert("TileEntities".to_string(), NbtList::new());
         c.insert("TerrainPopulated".to_string(), 1i8);
 
@@ -0,0 +1,458 @@
+use crate::schematic::{read_block_state_palette, write_block_state, DEFAULT_DATA_VERSION};
+use lodestone_common::types::hashmap_ext::HashMapExt;
+use lodestone_common::types::vec3i::Vec3i;
+use lodestone_common::util::McVersion;
+use lodestone_level::block::conversion::get_version_block_name;
+use lodestone_level::block::conversion::{format_block_state, get_named_block, set_named_block};
+use lodestone_level::block::Block;
+use lodestone_level::entity::block_entity::{BlockEntity, HasBlockEntity};
+use lodestone_level::level::{metadata, Level};
+use quartz_nbt::io::{self, Flavor};
+use quartz_nbt::{NbtCompound, NbtList, NbtTag};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Cursor;
+use std::time::SystemTime;
+
+/// Litematic version we write, used by Litematica for 1.13 up to 1.20
+pub const LITEMATIC_VERSION: i32 = 6;
+
+/// Per-region tags we don't model, kept as-is in [`metadata::LITEMATIC_REGION_DATA`]
+const LITEMATIC_REGION_TAGS: [&str; 3] = ["Entities", "PendingBlockTicks", "PendingFluidTicks"];
+
+pub trait LitematicLevel {
+    /// Reads every sub-region of a `.litematic` into one finite level
+    ///
+    /// The level starts at the lowest corner of all regions, space between regions is left as air.
+    fn read_litematic(version: McVersion, data: Vec<u8>) -> Result<Level, String>;
+    /// Reads every sub-region of a `.litematic` into its own finite level, keyed by region name
+    fn read_litematic_regions(
+        version: McVersion,
+        data: Vec<u8>,
+    ) -> Result<HashMap<String, Level>, String>;
+    /// Writes out the whole level as a `.litematic` with a single region
+    ///
+    /// Use [`Level::copy_region`] first to only export part of a level.
+    fn write_litematic(&self, version: McVersion, region_name: &str) -> Vec<u8>;
+}
+
+type Xyz = (i32, i32, i32);
+
+/// Reads an `{x, y, z}` compound (`Position`, `Size`, `EnclosingSize`)
+fn read_xyz(nbt: &NbtCompound) -> Option<Xyz> {
+    Some((nbt.get("x").ok()?, nbt.get("y").ok()?, nbt.get("z").ok()?))
+}
+
+fn write_xyz(x: i32, y: i32, z: i32) -> NbtCompound {
+    let mut nbt = NbtCompound::new();
+    nbt.insert("x", x);
+    nbt.insert("y", y);
+    nbt.insert("z", z);
+    nbt
+}
+
+/// Gets the lowest corner and the size of a region, sizes can be negative to grow from the position
+fn get_region_bounds(region: &NbtCompound) -> Option<(Xyz, Xyz)> {
+    let (px, py, pz) = read_xyz(region.get("Position").ok()?)?;
+    let (sx, sy, sz) = read_xyz(region.get("Size").ok()?)?;
+
+    let axis = |p: i32, s: i32| if s < 0 { (p + s + 1, -s) } else { (p, s) };
+    let (mx, w) = axis(px, sx);
+    let (my, h) = axis(py, sy);
+    let (mz, l) = axis(pz, sz);
+
+    Some(((mx, my, mz), (w, h, l)))
+}
+
+/// Gets the amount of bits every palette index takes up in `BlockStates`
+fn get_bits_per_entry(palette_len: usize) -> usize {
+    ((usize::BITS - palette_len.max(1).saturating_sub(1).leading_zeros()) as usize).max(2)
+}
+
+/// Gets a value from a tightly packed long array, unlike chunk sections values can span two longs
+fn get_packed(states: &[i64], bits: usize, i: usize) -> u64 {
+    let mask = (1u64 << bits) - 1;
+    let start = i * bits;
+    let (start_long, end_long) = (start >> 6, (start + bits - 1) >> 6);
+    let offset = start & 0x3F;
+
+    let low = *states.get(start_long).unwrap_or(&0) as u64 >> offset;
+    if start_long == end_long {
+        low & mask
+    } else {
+        let high = *states.get(end_long).unwrap_or(&0) as u64;
+        (low | (high << (64 - offset))) & mask
+    }
+}
+
+fn set_packed(states: &mut [i64], bits: usize, i: usize, v: u64) {
+    let mask = (1u64 << bits) - 1;
+    let start = i * bits;
+    let (start_long, end_long) = (start >> 6, (start + bits - 1) >> 6);
+    let offset = start & 0x3F;
+
+    let low = states[start_long] as u64;
+    states[start_long] = ((low & !(mask << offset)) | ((v & mask) << offset)) as i64;
+
+    if start_long != end_long {
+        let shift = 64 - offset;
+        let high = states[end_long] as u64;
+        states[end_long] = ((high & !(mask >> shift)) | ((v & mask) >> shift)) as i64;
+    }
+}
+
+/// Reads the blocks and tile entities of a region into the level, `offset` is where the region's lowest corner goes
+fn read_region(
+    level: &mut Level,
+    version: McVersion,
+    region: &NbtCompound,
+    size: Xyz,
+    offset: Xyz,
+) -> Result<(), String> {
+    let (width, height, length) = size;
+    let (ox, oy, oz) = offset;
+
+    let palette =
+        read_block_state_palette(region.get("BlockStatePalette").map_err(|e| e.to_string())?);
+    let states: &[i64] = region.get("BlockStates").map_err(|e| e.to_string())?;
+    let bits = get_bits_per_entry(palette.len());
+
+    for y in 0..height {
+        for z in 0..length {
+            for x in 0..width {
+                let i = ((y * length + z) * width + x) as usize;
+                let state = get_packed(states, bits, i) as usize;
+
+                let Some((name, properties)) = palette.get(state) else {
+                    return Err(format!(
+                        "Litematic block state {} isn't in the palette",
+                        state
+                    ));
+                };
+
+                set_named_block(
+                    level,
+                    version,
+                    x + ox,
+                    (y + oy) as i16,
+                    z + oz,
+                    name,
+                    properties,
+                );
+            }
+        }
+    }
+
+    if let Ok(tile_entities) = region.get::<_, &NbtList>("TileEntities") {
+        for tile_entity in tile_entities.iter() {
+            let NbtTag::Compound(tile_entity) = tile_entity else {
+                continue;
+            };
+
+            let Some((x, y, z)) = read_xyz(tile_entity) else {
+                continue;
+            };
+            let id: &str = tile_entity.get("id").unwrap_or("");
+
+            let mut data = tile_entity.clone();
+            for key in ["id", "x", "y", "z"] {
+                data.inner_mut().remove(key);
+            }
+
+            let pos = Vec3i::new(x + ox, y + oy, z + oz);
+            let block_entity = BlockEntity {
+                id: id.to_string(),
+                x: pos.x,
+                y: pos.y,
+                z: pos.z,
+                components: Default::default(),
+                keep_packed: false,
+                data: HasBlockEntity::NotFound(id.to_string(), data),
+            };
+
+            if let Some(c) = level.get_chunk_by_block_coords_mut(pos.x, pos.z) {
+                c.add_block_entity(pos, block_entity);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Keeps the unmodeled tags of the given regions in the level
+fn set_region_data<'a>(
+    level: &mut Level,
+    regions: impl Iterator<Item = (&'a str, &'a NbtCompound)>,
+) {
+    let mut root = NbtCompound::new();
+
+    for (name, region) in regions {
+        let mut data = NbtCompound::new();
+        for key in LITEMATIC_REGION_TAGS {
+            if let Some(tag) = region.inner().get(key) {
+                data.insert(key, tag.clone());
+            }
+        }
+
+        root.insert(name, data);
+    }
+
+    let mut raw = Vec::new();
+    io::write_nbt(&mut raw, None, &root, Flavor::Uncompressed).expect("Litematic region data");
+    level
+        .custom_data
+        .set_value(metadata::LITEMATIC_REGION_DATA.to_string(), raw);
+}
+
+fn read_litematic_metadata(level: &mut Level, nbt: &NbtCompound) {
+    if let Ok(data_version) = nbt.get::<_, i32>("MinecraftDataVersion") {
+        level
+            .custom_data
+            .set_value(metadata::DATA_VERSION.to_string(), data_version);
+    }
+
+    let Ok(meta) = nbt.get::<_, &NbtCompound>("Metadata") else {
+        return;
+    };
+
+    if let Ok(name) = meta.get::<_, &str>("Name") {
+        level.name = name.to_string();
+    }
+    if let Ok(author) = meta.get::<_, &str>("Author") {
+        level
+            .custom_data
+            .set_value(metadata::AUTHOR.to_string(), author.to_string());
+    }
+    if let Ok(description) = meta.get::<_, &str>("Description") {
+        level.custom_data.set_value(
+            metadata::LITEMATIC_DESCRIPTION.to_string(),
+            description.to_string(),
+        );
+    }
+    if let Ok(created) = meta.get::<_, i64>("TimeCreated") {
+        level
+            .custom_data
+            .set_value(metadata::CREATION_TIME.to_string(), created);
+    }
+    if let Ok(modified) = meta.get::<_, i64>("TimeModified") {
+        level
+            .custom_data
+            .set_value(metadata::LAST_MODIFIED.to_string(), modified);
+    }
+}
+
+impl LitematicLevel for Level {
+    fn read_litematic(version: McVersion, data: Vec<u8>) -> Result<Level, String> {
+        let nbt = io::read_nbt(&mut Cursor::new(&data), Flavor::GzCompressed)
+            .map_err(|e| format!("Failed to read litematic NBT: {}", e))?
+            .0;
+
+        let regions: &NbtCompound = nbt.get("Regions").map_err(|e| e.to_string())?;
+
+        let mut bounds = Vec::new();
+        for (name, region) in regions.inner() {
+            let NbtTag::Compound(region) = region else {
+                continue;
+            };
+
+            let (min, size) = get_region_bounds(region)
+                .ok_or(format!("Litematic region {} has no valid bounds", name))?;
+            bounds.push((name.as_str(), region, min, size));
+        }
+
+        let min = |f: fn(&Xyz) -> i32| bounds.iter().map(|b| f(&b.2)).min();
+        let max = |f: fn(&Xyz, &Xyz) -> i32| bounds.iter().map(|b| f(&b.2, &b.3)).max();
+
+        let (mx, my, mz) = (
+            min(|p| p.0).unwrap_or(0),
+            min(|p| p.1).unwrap_or(0),
+            min(|p| p.2).unwrap_or(0),
+        );
+        let (ex, ey, ez) = (
+            max(|p, s| p.0 + s.0).unwrap_or(0),
+            max(|p, s| p.1 + s.1).unwrap_or(0),
+            max(|p, s| p.2 + s.2).unwrap_or(0),
+        );
+
+        let mut level = Level::new();
+        level.create_finite(ex - mx, (ey - my) as i16, ez - mz);
+
+        for (_, region, min, size) in &bounds {
+            let offset = (min.0 - mx, min.1 - my, min.2 - mz);
+            read_region(&mut level, version, region, *size, offset)?;
+        }
+
+        set_region_data(&mut level, bounds.iter().map(|b| (b.0, b.1)));
+        read_litematic_metadata(&mut level, &nbt);
+
+        Ok(level)
+    }
+
+    fn read_litematic_regions(
+        version: McVersion,
+        data: Vec<u8>,
+    ) -> Result<HashMap<String, Level>, String> {
+        let nbt = io::read_nbt(&mut Cursor::new(&data), Flavor::GzCompressed)
+            .map_err(|e| format!("Failed to read litematic NBT: {}", e))?
+            .0;
+
+        let regions: &NbtCompound = nbt.get("Regions").map_err(|e| e.to_string())?;
+
+        let mut levels = HashMap::new();
+        for (name, region) in regions.inner() {
+            let NbtTag::Compound(region) = region else {
+                continue;
+            };
+
+            let (_, size) = get_region_bounds(region)
+                .ok_or(format!("Litematic region {} has no valid bounds", name))?;
+
+            let mut level = Level::new();
+            level.create_finite(size.0, size.1 as i16, size.2);
+            read_region(&mut level, version, region, size, (0, 0, 0))?;
+
+            set_region_data(&mut level, [(name.as_str(), region)].into_iter());
+            read_litematic_metadata(&mut level, &nbt);
+            level.name = name.clone();
+
+            levels.insert(name.clone(), level);
+        }
+
+        Ok(levels)
+    }
+
+    fn write_litematic(&self, version: McVersion, region_name: &str) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        let mut litematic = NbtCompound::new();
+
+        let bounds = self.get_finite_bounds();
+        let width = bounds.width;
+        let height = bounds.height as i32;
+        let length = bounds.length;
+
+        litematic.insert("Version", LITEMATIC_VERSION);
+        litematic.insert(
+            "MinecraftDataVersion",
+            self.custom_data
+                .get_value::<i32, _>(metadata::DATA_VERSION)
+                .unwrap_or(DEFAULT_DATA_VERSION),
+        );
+
+        let mut palette: Vec<NbtCompound> = Vec::new();
+        let mut indices: HashMap<String, u64> = HashMap::new();
+        let mut blocks: Vec<u64> = Vec::with_capacity((width * height * length) as usize);
+        let mut tile_entities = NbtList::new();
+        let mut total_blocks = 0;
+
+        // air is always the first state
+        let air = get_version_block_name(version, &Block::Air).unwrap_or("minecraft:air");
+        palette.push(write_block_state(air, &BTreeMap::new()));
+        indices.insert(air.to_string(), 0);
+
+        for y in 0..height {
+            for z in 0..length {
+                for x in 0..width {
+                    let (wx, wy, wz) = (x + bounds.x, y + bounds.y as i32, z + bounds.z);
+
+                    if self.get_block(wx, wy as i16, wz) != Block::Air {
+                        total_blocks += 1;
+                    }
+
+                    let (name, properties) = get_named_block(self, version, wx, wy as i16, wz);
+                    let state = *indices
+                        .entry(format_block_state(&name, &properties))
+                        .or_insert_with(|| {
+                            palette.push(write_block_state(&name, &properties));
+                            palette.len() as u64 - 1
+                        });
+                    blocks.push(state);
+
+                    if let Some(block_entity) = self
+                        .get_chunk_by_block_coords(wx, wz)
+                        .and_then(|c| c.block_entities.get(&Vec3i::new(wx, wy, wz)))
+                    {
+                        let mut nbt = match &block_entity.data {
+                            HasBlockEntity::NotFound(_, data) => data.clone(),
+                            HasBlockEntity::Found(_) => block_entity
+                                .to_nbt(McVersion::Alpha1_2_6)
+                                .unwrap_or_default(),
+                        };
+
+                        nbt.insert("id", &block_entity.id);
+                        nbt.insert("x", x);
+                        nbt.insert("y", y);
+                        nbt.insert("z", z);
+                        tile_entities.push(nbt);
+                    }
+                }
+            }
+        }
+
+        let bits = get_bits_per_entry(palette.len());
+        let mut states = vec![0i64; (blocks.len() * bits).div_ceil(64)];
+        for (i, state) in blocks.iter().enumerate() {
+            set_packed(&mut states, bits, i, *state);
+        }
+
+        let mut region = NbtCompound::new();
+        region.insert("Position", write_xyz(0, 0, 0));
+        region.insert("Size", write_xyz(width, height, length));
+        region.insert("BlockStatePalette", NbtList::from(palette));
+        region.insert("BlockStates", states);
+        region.insert("TileEntities", tile_entities);
+
+        let region_data = self
+            .custom_data
+            .get_value::<Vec<u8>, _>(metadata::LITEMATIC_REGION_DATA)
+            .and_then(|raw| io::read_nbt(&mut Cursor::new(raw), Flavor::Uncompressed).ok())
+            .and_then(|(root, _)| root.get::<_, &NbtCompound>(region_name).ok().cloned())
+            .unwrap_or_default();
+
+        for key in LITEMATIC_REGION_TAGS {
+            match region_data.inner().get(key) {
+                Some(tag) => region.insert(key, tag.clone()),
+                None => region.insert(key, NbtList::new()),
+            }
+        }
+
+        let mut regions = NbtCompound::new();
+        regions.insert(region_name, region);
+        litematic.insert("Regions", regions);
+
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("Time since unix epoch")
+            .as_millis() as i64;
+
+        let mut meta = NbtCompound::new();
+        meta.insert("Name", &self.name);
+        meta.insert(
+            "Author",
+            self.custom_data
+                .get_value::<String, _>(metadata::AUTHOR)
+                .unwrap_or_default(),
+        );
+        meta.insert(
+            "Description",
+            self.custom_data
+                .get_value::<String, _>(metadata::LITEMATIC_DESCRIPTION)
+                .unwrap_or_default(),
+        );
+        meta.insert("RegionCount", 1);
+        meta.insert("TotalBlocks", total_blocks);
+        meta.insert("TotalVolume", width * height * length);
+        meta.insert("EnclosingSize", write_xyz(width, height, length));
+        meta.insert(
+            "TimeCreated",
+            self.custom_data
+                .get_value::<i64, _>(metadata::CREATION_TIME)
+                .unwrap_or(now),
+        );
+        meta.insert("TimeModified", now);
+        litematic.insert("Metadata", meta);
+
+        io::write_nbt(&mut out, Some(""), &litematic, Flavor::GzCompressed)
+            .expect("Write litematic compound");
+
+        out
+    }
+}
@@ -0,0 +1,244 @@
+use crate::classic::set_classic_block;
+use lodestone_common::types::hashmap_ext::HashMapExt;
+use lodestone_common::types::vec3i::Vec3i;
+use lodestone_common::util::McVersion;
+use lodestone_level::block::conversion::{get_internal_block_id, get_version_block_id};
+use lodestone_level::block::{Block, BlockId};
+use lodestone_level::entity::block_entity::BlockEntity;
+use lodestone_level::entity::stash::{get_stashed_entities, stash_entities, EntityFormat};
+use lodestone_level::level::{metadata, Level};
+use quartz_nbt::io::{self, Flavor};
+use quartz_nbt::{NbtCompound, NbtList, NbtTag};
+use std::io::Cursor;
+
+pub trait SchematicLevel {
+    /// Reads an MCEdit/WorldEdit `.schematic` into a finite level
+    fn read_schematic(version: McVersion, data: Vec<u8>) -> Result<Level, String>;
+    /// Writes out the whole level as a `.schematic`
+    ///
+    /// Use [`Level::copy_region`] first to only export part of a level.
+    fn write_schematic(&self, version: McVersion) -> Vec<u8>;
+}
+
+/// Gets the block ID of a schematic block, `AddBlocks` holds the upper 4 bits of every ID
+fn get_schematic_id(blocks: &[u8], add_blocks: Option<&[u8]>, i: usize) -> u16 {
+    let add = match add_blocks.and_then(|a| a.get(i >> 1)) {
+        Some(a) if i & 1 == 0 => (*a & 0x0F) as u16,
+        Some(a) => ((*a & 0xF0) >> 4) as u16,
+        None => 0,
+    };
+
+    (add << 8) | blocks[i] as u16
+}
+
+impl SchematicLevel for Level {
+    fn read_schematic(version: McVersion, data: Vec<u8>) -> Result<Level, String> {
+        let nbt = io::read_nbt(&mut Cursor::new(&data), Flavor::GzCompressed)
+            .map_err(|e| format!("Failed to read schematic NBT: {}", e))?
+            .0;
+
+        let width: i16 = nbt.get("Width").map_err(|e| e.to_string())?;
+        let height: i16 = nbt.get("Height").map_err(|e| e.to_string())?;
+        let length: i16 = nbt.get("Length").map_err(|e| e.to_string())?;
+
+        let materials: &str = nbt.get("Materials").unwrap_or("Alpha");
+        if materials != "Alpha" {
+            return Err(format!("Unsupported schematic materials: {}", materials));
+        }
+
+        let blocks: &[u8] = nbt.get("Blocks").map_err(|e| e.to_string())?;
+        let block_data: &[u8] = nbt.get("Data").map_err(|e| e.to_string())?;
+        let add_blocks: Option<&[u8]> = nbt.get("AddBlocks").ok();
+
+        let size = width as usize * height as usize * length as usize;
+        if blocks.len() < size || block_data.len() < size {
+            return Err(format!(
+                "Schematic block arrays don't match its size ({}x{}x{})",
+                width, height, length
+            ));
+        }
+
+        let mut level = Level::new();
+        level.create_finite(width as i32, height, length as i32);
+
+        for y in 0..height {
+            for z in 0..length as i32 {
+                for x in 0..width as i32 {
+                    let i =
+                        (y as usize * length as usize + z as usize) * width as usize + x as usize;
+
+                    let id = get_schematic_id(blocks, add_blocks, i);
+                    if id == 0 {
+                        continue;
+                    }
+
+                    let data = (block_data[i] & 0x0F) as u16;
+                    match get_internal_block_id(version, &BlockId::NumericWithData(id, data)) {
+                        Some(blk) => level.set_block(x, y, z, blk),
+                        None => {
+                            if let Some(c) = level.get_chunk_by_block_coords_mut(x, z) {
+                                set_classic_block(
+                                    c,
+                                    version,
+                                    (x & 15) as i8,
+                                    y,
+                                    (z & 15) as i8,
+                                    id,
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Ok(tile_entities) = nbt.get::<_, &NbtList>("TileEntities") {
+            for tile_entity in tile_entities.iter() {
+                let NbtTag::Compound(tile_entity) = tile_entity else {
+                    continue;
+                };
+
+                if let Some(block_entity) =
+                    BlockEntity::from_nbt(McVersion::Alpha1_2_6, tile_entity)
+                {
+                    let pos = Vec3i::new(block_entity.x, block_entity.y, block_entity.z);
+
+                    if let Some(c) = level.get_chunk_by_block_coords_mut(pos.x, pos.z) {
+                        c.add_block_entity(pos, block_entity);
+                    }
+                }
+            }
+        }
+
+        if let Ok(entities) = nbt.get::<_, &NbtList>("Entities") {
+            stash_entities(&mut level.custom_data, EntityFormat::Schematic, entities);
+        }
+
+        for key in [
+            metadata::WE_ORIGIN_X,
+            metadata::WE_ORIGIN_Y,
+            metadata::WE_ORIGIN_Z,
+            metadata::WE_OFFSET_X,
+            metadata::WE_OFFSET_Y,
+            metadata::WE_OFFSET_Z,
+        ] {
+            if let Ok(v) = nbt.get::<_, i32>(key) {
+                level.custom_data.set_value(key.to_string(), v);
+            }
+        }
+
+        Ok(level)
+    }
+
+    fn write_schematic(&self, version: McVersion) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        let mut schematic = NbtCompound::new();
+
+        let bounds = self.get_finite_bounds();
+        let width = bounds.width as usize;
+        let height = bounds.height as usize;
+        let length = bounds.length as usize;
+
+        schematic.insert("Width", width as i16);
+        schematic.insert("Height", height as i16);
+        schematic.insert("Length", length as i16);
+        schematic.insert("Materials", "Alpha");
+
+        let size = width * height * length;
+        let mut blocks = vec![0u8; size];
+        let mut block_data = vec![0u8; size];
+        let mut add_blocks = vec![0u8; size.div_ceil(2)];
+
+        for y in 0..height {
+            for z in 0..length {
+                for x in 0..width {
+                    let i = (y * length + z) * width + x;
+                    let (wx, wy, wz) = (
+                        x as i32 + bounds.x,
+                        y as i16 + bounds.y,
+                        z as i32 + bounds.z,
+                    );
+
+                    let blk = self.get_block(wx, wy, wz);
+                    let (id, data) = match blk {
+                        Block::Custom => (
+                            self.get_states(wx, wy, wz)
+                                .and_then(|s| s.get(metadata::CUSTOM_BLOCK_ID))
+                                .and_then(|id| id.parse::<u16>().ok())
+                                .unwrap_or(0),
+                            0,
+                        ),
+                        blk => match get_version_block_id(version, &blk) {
+                            BlockId::NumericWithData(id, data) => (id, data),
+                            id => (usize::try_from(id).unwrap_or(0) as u16, 0),
+                        },
+                    };
+
+                    blocks[i] = id as u8;
+                    block_data[i] = data as u8 & 0x0F;
+
+                    let add = ((id >> 8) & 0x0F) as u8;
+                    if i & 1 == 0 {
+                        add_blocks[i >> 1] |= add;
+                    } else {
+                        add_blocks[i >> 1] |= add << 4;
+                    }
+                }
+            }
+        }
+
+        schematic.insert("Blocks", blocks);
+        schematic.insert("Data", block_data);
+
+        if add_blocks.iter().any(|a| *a != 0) {
+            schematic.insert("AddBlocks", add_blocks);
+        }
+
+        let mut tile_entities = NbtList::new();
+        for chunk in self.get_chunks().values() {
+            for block_entity in chunk.block_entities.values() {
+                if !bounds.contains(block_entity.x, block_entity.y, block_entity.z) {
+                    continue;
+                }
+
+                let mut block_entity = block_entity.clone();
+                block_entity.x -= bounds.x;
+                block_entity.y -= bounds.y as i32;
+                block_entity.z -= bounds.z;
+
+                if let Some(nbt) = block_entity.to_nbt(McVersion::Alpha1_2_6) {
+                    tile_entities.push(nbt);
+                }
+            }
+        }
+        schematic.insert("TileEntities", tile_entities);
+
+        schematic.insert(
+            "Entities",
+            get_stashed_entities(&self.custom_data, EntityFormat::Schematic),
+        );
+
+        for key in [
+            metadata::WE_ORIGIN_X,
+            metadata::WE_ORIGIN_Y,
+            metadata::WE_ORIGIN_Z,
+            metadata::WE_OFFSET_X,
+            metadata::WE_OFFSET_Y,
+            metadata::WE_OFFSET_Z,
+        ] {
+            if let Some(v) = self.custom_data.get_value::<i32, _>(key) {
+                schematic.insert(key, v);
+            }
+        }
+
+        io::write_nbt(
+            &mut out,
+            Some("Schematic"),
+            &schematic,
+            Flavor::GzCompressed,
+        )
+        .expect("Write schematic compound");
+
+        out
+    }
+}
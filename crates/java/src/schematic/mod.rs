@@ -0,0 +1,54 @@
+pub mod litematic;
+pub mod mcedit;
+pub mod sponge;
+pub mod structure;
+
+use quartz_nbt::{NbtCompound, NbtList, NbtTag};
+use std::collections::BTreeMap;
+
+/// Data version of 1.13, written when the level doesn't have one since it's the first version with flattened names
+pub const DEFAULT_DATA_VERSION: i32 = 1519;
+
+/// Reads a `{Name, Properties}` block state palette (structures, litematics)
+pub fn read_block_state_palette(palette: &NbtList) -> Vec<(String, BTreeMap<String, String>)> {
+    palette
+        .iter()
+        .map(|state| {
+            let NbtTag::Compound(state) = state else {
+                return ("minecraft:air".to_string(), BTreeMap::new());
+            };
+
+            let name: &str = state.get("Name").unwrap_or("minecraft:air");
+            let properties = state
+                .get::<_, &NbtCompound>("Properties")
+                .map(|p| {
+                    p.inner()
+                        .iter()
+                        .filter_map(|(k, v)| match v {
+                            NbtTag::String(v) => Some((k.clone(), v.clone())),
+                            _ => None,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            (name.to_string(), properties)
+        })
+        .collect()
+}
+
+/// Writes a block state as a `{Name, Properties}` palette entry
+pub fn write_block_state(name: &str, properties: &BTreeMap<String, String>) -> NbtCompound {
+    let mut state = NbtCompound::new();
+    state.insert("Name", name);
+
+    if !properties.is_empty() {
+        let mut props = NbtCompound::new();
+        for (k, v) in properties {
+            props.insert(k.clone(), v.as_str());
+        }
+        state.insert("Properties", props);
+    }
+
+    state
+}
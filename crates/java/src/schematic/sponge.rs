@@ -0,0 +1,375 @@
+use crate::schematic::DEFAULT_DATA_VERSION;
+use lodestone_common::io::{read_varint, write_varint};
+use lodestone_common::types::hashmap_ext::HashMapExt;
+use lodestone_common::types::vec3i::Vec3i;
+use lodestone_common::util::McVersion;
+use lodestone_level::block::conversion::{
+    format_block_state, get_named_block, parse_block_state, set_named_block,
+};
+use lodestone_level::entity::block_entity::{BlockEntity, HasBlockEntity};
+use lodestone_level::entity::stash::{get_stashed_entities, stash_entities, EntityFormat};
+use lodestone_level::level::{metadata, Level};
+use quartz_nbt::io::{self, Flavor};
+use quartz_nbt::{NbtCompound, NbtList, NbtTag};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Cursor;
+
+pub trait SpongeSchematicLevel {
+    /// Reads a Sponge `.schem` (version 1, 2 or 3) into a finite level
+    fn read_sponge_schematic(version: McVersion, data: Vec<u8>) -> Result<Level, String>;
+    /// Writes out the whole level as a Sponge `.schem`
+    ///
+    /// # Params
+    ///
+    /// * `version` - The version to get block names from
+    /// * `sponge_version` - The Sponge schematic version to write, either 2 or 3
+    fn write_sponge_schematic(&self, version: McVersion, sponge_version: i32) -> Vec<u8>;
+}
+
+/// Reads a Sponge block entity, the position and ID are taken out of the compound
+fn read_sponge_block_entity(sponge_version: i32, nbt: &NbtCompound) -> Option<BlockEntity> {
+    let pos: &[i32] = nbt.get("Pos").ok()?;
+    let id: &str = nbt.get("Id").ok()?;
+
+    let data = match sponge_version {
+        3 => nbt
+            .get::<_, &NbtCompound>("Data")
+            .cloned()
+            .unwrap_or_default(),
+        _ => {
+            let mut data = nbt.clone();
+            data.inner_mut().remove("Pos");
+            data.inner_mut().remove("Id");
+            data
+        }
+    };
+
+    Some(BlockEntity {
+        id: id.to_string(),
+        x: *pos.first()?,
+        y: *pos.get(1)?,
+        z: *pos.get(2)?,
+        components: Default::default(),
+        keep_packed: false,
+        data: HasBlockEntity::NotFound(id.to_string(), data),
+    })
+}
+
+fn write_sponge_block_entity(
+    sponge_version: i32,
+    block_entity: &BlockEntity,
+    pos: Vec3i,
+) -> NbtCompound {
+    let mut data = match &block_entity.data {
+        HasBlockEntity::NotFound(_, data) => data.clone(),
+        HasBlockEntity::Found(_) => {
+            let mut data = block_entity
+                .to_nbt(McVersion::Alpha1_2_6)
+                .unwrap_or_default();
+            for key in ["id", "x", "y", "z"] {
+                data.inner_mut().remove(key);
+            }
+            data
+        }
+    };
+
+    let mut nbt = match sponge_version {
+        3 => {
+            let mut nbt = NbtCompound::new();
+            nbt.insert("Data", data);
+            nbt
+        }
+        _ => {
+            data.inner_mut().remove("Data");
+            data
+        }
+    };
+
+    nbt.insert("Pos", vec![pos.x, pos.y, pos.z]);
+    nbt.insert("Id", &block_entity.id);
+    nbt
+}
+
+/// Puts a Sponge entity into the version 3 layout, where everything but the position and ID is under `Data`
+fn read_sponge_entity(sponge_version: i32, nbt: &NbtCompound) -> NbtCompound {
+    if sponge_version == 3 {
+        return nbt.clone();
+    }
+
+    let mut data = nbt.clone();
+    let mut entity = NbtCompound::new();
+    for key in ["Pos", "Id"] {
+        if let Some(tag) = data.inner_mut().remove(key) {
+            entity.insert(key, tag);
+        }
+    }
+    entity.insert("Data", data);
+    entity
+}
+
+/// Puts an entity from [`read_sponge_entity`] back into the layout of a Sponge version
+fn write_sponge_entity(sponge_version: i32, entity: &NbtCompound) -> NbtCompound {
+    if sponge_version == 3 {
+        return entity.clone();
+    }
+
+    let mut nbt = entity
+        .get::<_, &NbtCompound>("Data")
+        .cloned()
+        .unwrap_or_default();
+    for key in ["Pos", "Id"] {
+        if let Ok(tag) = entity.get::<_, &NbtTag>(key) {
+            nbt.insert(key, tag.clone());
+        }
+    }
+    nbt
+}
+
+impl SpongeSchematicLevel for Level {
+    fn read_sponge_schematic(version: McVersion, data: Vec<u8>) -> Result<Level, String> {
+        let nbt = io::read_nbt(&mut Cursor::new(&data), Flavor::GzCompressed)
+            .map_err(|e| format!("Failed to read Sponge schematic NBT: {}", e))?
+            .0;
+
+        // v3 puts everything inside of a "Schematic" compound
+        let schem = nbt.get::<_, &NbtCompound>("Schematic").unwrap_or(&nbt);
+
+        let sponge_version: i32 = schem.get("Version").map_err(|e| e.to_string())?;
+        let width = schem.get::<_, i16>("Width").map_err(|e| e.to_string())? as u16 as usize;
+        let height = schem.get::<_, i16>("Height").map_err(|e| e.to_string())? as u16 as usize;
+        let length = schem.get::<_, i16>("Length").map_err(|e| e.to_string())? as u16 as usize;
+
+        let (palette, block_data, block_entities) = match sponge_version {
+            1 | 2 => (
+                schem.get::<_, &NbtCompound>("Palette"),
+                schem.get::<_, &[u8]>("BlockData"),
+                schem
+                    .get::<_, &NbtList>("BlockEntities")
+                    .or_else(|_| schem.get::<_, &NbtList>("TileEntities")),
+            ),
+            3 => {
+                let blocks: &NbtCompound = schem.get("Blocks").map_err(|e| e.to_string())?;
+                (
+                    blocks.get::<_, &NbtCompound>("Palette"),
+                    blocks.get::<_, &[u8]>("Data"),
+                    blocks.get::<_, &NbtList>("BlockEntities"),
+                )
+            }
+            v => return Err(format!("Unsupported Sponge schematic version: {}", v)),
+        };
+
+        let palette = palette.map_err(|e| e.to_string())?;
+        let block_data = block_data.map_err(|e| e.to_string())?;
+
+        let mut states: HashMap<i32, (&str, BTreeMap<String, String>)> = HashMap::new();
+        for (state, index) in palette.inner() {
+            if let NbtTag::Int(index) = index {
+                states.insert(*index, parse_block_state(state));
+            }
+        }
+
+        let mut level = Level::new();
+        level.create_finite(width as i32, height as i16, length as i32);
+
+        let mut c = Cursor::new(block_data);
+        for i in 0..width * height * length {
+            let index = read_varint(&mut c)?;
+
+            let y = i / (width * length);
+            let z = (i / width) % length;
+            let x = i % width;
+
+            if let Some((name, properties)) = states.get(&index) {
+                set_named_block(
+                    &mut level, version, x as i32, y as i16, z as i32, name, properties,
+                );
+            }
+        }
+
+        if let Ok(block_entities) = block_entities {
+            for block_entity in block_entities.iter() {
+                let NbtTag::Compound(block_entity) = block_entity else {
+                    continue;
+                };
+
+                if let Some(block_entity) = read_sponge_block_entity(sponge_version, block_entity) {
+                    let pos = Vec3i::new(block_entity.x, block_entity.y, block_entity.z);
+
+                    if let Some(c) = level.get_chunk_by_block_coords_mut(pos.x, pos.z) {
+                        c.add_block_entity(pos, block_entity);
+                    }
+                }
+            }
+        }
+
+        if let Ok(entities) = schem.get::<_, &NbtList>("Entities") {
+            let entities: NbtList = entities
+                .iter()
+                .filter_map(|e| match e {
+                    NbtTag::Compound(e) => Some(read_sponge_entity(sponge_version, e)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .into();
+            stash_entities(&mut level.custom_data, EntityFormat::Sponge, &entities);
+        }
+
+        if let Ok(data_version) = schem.get::<_, i32>("DataVersion") {
+            level
+                .custom_data
+                .set_value(metadata::DATA_VERSION.to_string(), data_version);
+        }
+
+        if let Ok(offset) = schem.get::<_, &[i32]>("Offset") {
+            for (key, v) in [
+                metadata::WE_ORIGIN_X,
+                metadata::WE_ORIGIN_Y,
+                metadata::WE_ORIGIN_Z,
+            ]
+            .iter()
+            .zip(offset)
+            {
+                level.custom_data.set_value(key.to_string(), *v);
+            }
+        }
+
+        if let Ok(we) = schem.get::<_, &NbtCompound>("Metadata") {
+            for key in [
+                metadata::WE_OFFSET_X,
+                metadata::WE_OFFSET_Y,
+                metadata::WE_OFFSET_Z,
+            ] {
+                if let Ok(v) = we.get::<_, i32>(key) {
+                    level.custom_data.set_value(key.to_string(), v);
+                }
+            }
+        }
+
+        Ok(level)
+    }
+
+    fn write_sponge_schematic(&self, version: McVersion, sponge_version: i32) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        let mut schem = NbtCompound::new();
+
+        let bounds = self.get_finite_bounds();
+        let width = bounds.width as usize;
+        let height = bounds.height as usize;
+        let length = bounds.length as usize;
+
+        schem.insert("Version", sponge_version);
+        schem.insert(
+            "DataVersion",
+            self.custom_data
+                .get_value::<i32, _>(metadata::DATA_VERSION)
+                .unwrap_or(DEFAULT_DATA_VERSION),
+        );
+        schem.insert("Width", width as i16);
+        schem.insert("Height", height as i16);
+        schem.insert("Length", length as i16);
+
+        let offset: Vec<i32> = [
+            metadata::WE_ORIGIN_X,
+            metadata::WE_ORIGIN_Y,
+            metadata::WE_ORIGIN_Z,
+        ]
+        .iter()
+        .map(|k| self.custom_data.get_value::<i32, _>(k).unwrap_or(0))
+        .collect();
+        schem.insert("Offset", offset);
+
+        let mut we = NbtCompound::new();
+        for key in [
+            metadata::WE_OFFSET_X,
+            metadata::WE_OFFSET_Y,
+            metadata::WE_OFFSET_Z,
+        ] {
+            if let Some(v) = self.custom_data.get_value::<i32, _>(key) {
+                we.insert(key, v);
+            }
+        }
+        schem.insert("Metadata", we);
+
+        let mut palette: HashMap<String, i32> = HashMap::new();
+        let mut block_data: Vec<u8> = Vec::new();
+
+        for y in 0..height {
+            for z in 0..length {
+                for x in 0..width {
+                    let (name, properties) = get_named_block(
+                        self,
+                        version,
+                        x as i32 + bounds.x,
+                        y as i16 + bounds.y,
+                        z as i32 + bounds.z,
+                    );
+
+                    let next = palette.len() as i32;
+                    let index = *palette
+                        .entry(format_block_state(&name, &properties))
+                        .or_insert(next);
+
+                    write_varint(&mut block_data, index);
+                }
+            }
+        }
+
+        let mut palette_tag = NbtCompound::new();
+        for (state, index) in &palette {
+            palette_tag.insert(state.clone(), *index);
+        }
+
+        let mut block_entities = NbtList::new();
+        for chunk in self.get_chunks().values() {
+            for block_entity in chunk.block_entities.values() {
+                if !bounds.contains(block_entity.x, block_entity.y, block_entity.z) {
+                    continue;
+                }
+
+                let pos = Vec3i::new(
+                    block_entity.x - bounds.x,
+                    block_entity.y - bounds.y as i32,
+                    block_entity.z - bounds.z,
+                );
+                block_entities.push(write_sponge_block_entity(sponge_version, block_entity, pos));
+            }
+        }
+
+        let entities: NbtList = get_stashed_entities(&self.custom_data, EntityFormat::Sponge)
+            .iter()
+            .filter_map(|e| match e {
+                NbtTag::Compound(e) => Some(write_sponge_entity(sponge_version, e)),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .into();
+        schem.insert("Entities", entities);
+
+        match sponge_version {
+            3 => {
+                let mut blocks = NbtCompound::new();
+                blocks.insert("Palette", palette_tag);
+                blocks.insert("Data", block_data);
+                blocks.insert("BlockEntities", block_entities);
+                schem.insert("Blocks", blocks);
+
+                let mut root = NbtCompound::new();
+                root.insert("Schematic", schem);
+
+                io::write_nbt(&mut out, Some(""), &root, Flavor::GzCompressed)
+                    .expect("Write schematic compound");
+            }
+            _ => {
+                schem.insert("PaletteMax", palette.len() as i32);
+                schem.insert("Palette", palette_tag);
+                schem.insert("BlockData", block_data);
+                schem.insert("BlockEntities", block_entities);
+
+                io::write_nbt(&mut out, Some("Schematic"), &schem, Flavor::GzCompressed)
+                    .expect("Write schematic compound");
+            }
+        }
+
+        out
+    }
+}
@@ -0,0 +1,207 @@
+use crate::schematic::{read_block_state_palette, write_block_state, DEFAULT_DATA_VERSION};
+use lodestone_common::types::hashmap_ext::HashMapExt;
+use lodestone_common::types::vec3i::Vec3i;
+use lodestone_common::util::McVersion;
+use lodestone_level::block::conversion::{format_block_state, get_named_block, set_named_block};
+use lodestone_level::entity::block_entity::{BlockEntity, HasBlockEntity};
+use lodestone_level::entity::stash::{get_stashed_entities, stash_entities, EntityFormat};
+use lodestone_level::level::{metadata, Level};
+use quartz_nbt::io::{self, Flavor};
+use quartz_nbt::{NbtCompound, NbtList, NbtTag};
+use std::collections::HashMap;
+use std::io::Cursor;
+
+pub trait StructureLevel {
+    /// Reads a vanilla structure block template (`.nbt`) into a finite level
+    ///
+    /// Templates with several `palettes` (e.g. shipwrecks) only have their first palette read.
+    fn read_structure(version: McVersion, data: Vec<u8>) -> Result<Level, String>;
+    /// Writes out the whole level as a structure block template
+    ///
+    /// Use [`Level::copy_region`] first to only export part of a level.
+    fn write_structure(&self, version: McVersion) -> Vec<u8>;
+}
+
+/// Reads a list of 3 ints (`size`, `pos`)
+fn read_int_list(list: &NbtList) -> Option<(i32, i32, i32)> {
+    let mut ints = list.iter().map(|t| match t {
+        NbtTag::Int(i) => Some(*i),
+        _ => None,
+    });
+
+    Some((ints.next()??, ints.next()??, ints.next()??))
+}
+
+/// Reads the `nbt` of a block, the ID is taken out of the compound
+fn read_structure_block_entity(pos: Vec3i, nbt: &NbtCompound) -> Option<BlockEntity> {
+    let id: &str = nbt.get("id").ok()?;
+
+    let mut data = nbt.clone();
+    data.inner_mut().remove("id");
+
+    Some(BlockEntity {
+        id: id.to_string(),
+        x: pos.x,
+        y: pos.y,
+        z: pos.z,
+        components: Default::default(),
+        keep_packed: false,
+        data: HasBlockEntity::NotFound(id.to_string(), data),
+    })
+}
+
+fn write_structure_block_entity(block_entity: &BlockEntity) -> NbtCompound {
+    let mut nbt = match &block_entity.data {
+        HasBlockEntity::NotFound(_, data) => data.clone(),
+        HasBlockEntity::Found(_) => block_entity
+            .to_nbt(McVersion::Alpha1_2_6)
+            .unwrap_or_default(),
+    };
+
+    // the position comes from the block itself
+    for key in ["x", "y", "z"] {
+        nbt.inner_mut().remove(key);
+    }
+
+    nbt.insert("id", &block_entity.id);
+    nbt
+}
+
+impl StructureLevel for Level {
+    fn read_structure(version: McVersion, data: Vec<u8>) -> Result<Level, String> {
+        let nbt = io::read_nbt(&mut Cursor::new(&data), Flavor::GzCompressed)
+            .map_err(|e| format!("Failed to read structure NBT: {}", e))?
+            .0;
+
+        let (width, height, length) = nbt
+            .get::<_, &NbtList>("size")
+            .ok()
+            .and_then(read_int_list)
+            .ok_or("Structure has no valid size")?;
+
+        let palette = match nbt.get::<_, &NbtList>("palette") {
+            Ok(palette) => read_block_state_palette(palette),
+            Err(_) => match nbt
+                .get::<_, &NbtList>("palettes")
+                .map_err(|e| e.to_string())?
+                .iter()
+                .next()
+            {
+                Some(NbtTag::List(palette)) => read_block_state_palette(palette),
+                _ => Vec::new(),
+            },
+        };
+
+        let blocks: &NbtList = nbt.get("blocks").map_err(|e| e.to_string())?;
+
+        let mut level = Level::new();
+        level.create_finite(width, height as i16, length);
+
+        for block in blocks.iter() {
+            let NbtTag::Compound(block) = block else {
+                continue;
+            };
+
+            let Some((x, y, z)) = block.get::<_, &NbtList>("pos").ok().and_then(read_int_list)
+            else {
+                continue;
+            };
+
+            let state: i32 = block.get("state").map_err(|e| e.to_string())?;
+            let Some((name, properties)) = palette.get(state as usize) else {
+                return Err(format!(
+                    "Structure block state {} isn't in the palette",
+                    state
+                ));
+            };
+
+            set_named_block(&mut level, version, x, y as i16, z, name, properties);
+
+            if let Ok(nbt) = block.get::<_, &NbtCompound>("nbt") {
+                let pos = Vec3i::new(x, y, z);
+
+                if let Some(block_entity) = read_structure_block_entity(pos.clone(), nbt)
+                    && let Some(c) = level.get_chunk_by_block_coords_mut(x, z)
+                {
+                    c.add_block_entity(pos, block_entity);
+                }
+            }
+        }
+
+        if let Ok(entities) = nbt.get::<_, &NbtList>("entities") {
+            stash_entities(&mut level.custom_data, EntityFormat::Structure, entities);
+        }
+
+        if let Ok(data_version) = nbt.get::<_, i32>("DataVersion") {
+            level
+                .custom_data
+                .set_value(metadata::DATA_VERSION.to_string(), data_version);
+        }
+
+        Ok(level)
+    }
+
+    fn write_structure(&self, version: McVersion) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        let mut structure = NbtCompound::new();
+
+        let bounds = self.get_finite_bounds();
+        let width = bounds.width;
+        let height = bounds.height as i32;
+        let length = bounds.length;
+
+        structure.insert(
+            "DataVersion",
+            self.custom_data
+                .get_value::<i32, _>(metadata::DATA_VERSION)
+                .unwrap_or(DEFAULT_DATA_VERSION),
+        );
+        structure.insert("size", NbtList::from(vec![width, height, length]));
+
+        let mut palette: Vec<NbtCompound> = Vec::new();
+        let mut indices: HashMap<String, i32> = HashMap::new();
+        let mut blocks = NbtList::new();
+
+        for y in 0..height {
+            for z in 0..length {
+                for x in 0..width {
+                    let (wx, wy, wz) = (x + bounds.x, y + bounds.y as i32, z + bounds.z);
+                    let (name, properties) = get_named_block(self, version, wx, wy as i16, wz);
+
+                    let state = *indices
+                        .entry(format_block_state(&name, &properties))
+                        .or_insert_with(|| {
+                            palette.push(write_block_state(&name, &properties));
+                            palette.len() as i32 - 1
+                        });
+
+                    let mut block = NbtCompound::new();
+                    block.insert("pos", NbtList::from(vec![x, y, z]));
+                    block.insert("state", state);
+
+                    if let Some(block_entity) = self
+                        .get_chunk_by_block_coords(wx, wz)
+                        .and_then(|c| c.block_entities.get(&Vec3i::new(wx, wy, wz)))
+                    {
+                        block.insert("nbt", write_structure_block_entity(block_entity));
+                    }
+
+                    blocks.push(block);
+                }
+            }
+        }
+
+        structure.insert("palette", NbtList::from(palette));
+        structure.insert("blocks", blocks);
+
+        structure.insert(
+            "entities",
+            get_stashed_entities(&self.custom_data, EntityFormat::Structure),
+        );
+
+        io::write_nbt(&mut out, Some(""), &structure, Flavor::GzCompressed)
+            .expect("Write structure compound");
+
+        out
+    }
+}
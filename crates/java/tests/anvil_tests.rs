@@ -2,14 +2,23 @@ mod anvil_tests {
     use flate2::write::GzEncoder;
     use flate2::Compression;
     use lodestone_common::util::McVersion;
+    use lodestone_java::alpha::AlphaChunk;
     use lodestone_java::alpha::AlphaLevel;
     use lodestone_java::anvil::Anvil;
+    use lodestone_java::anvil::AnvilChunk;
     use lodestone_java::classic::classic_world::CWLevel;
     use lodestone_java::classic::mine_v2::MineV2Level;
-    use lodestone_level::level::Level;
+    use lodestone_java::map::{read_map_items, write_map_items, MapItem, MapItemLevel};
+    use lodestone_java::mcregion::MCRChunk;
+    use lodestone_level::block::Block;
+    use lodestone_level::entity::stash::{get_stashed_entities, stash_entities, EntityFormat};
+    use lodestone_level::level::chunk::Chunk;
+    use lodestone_level::level::{Coords, Level};
+    use quartz_nbt::io::{self, Flavor};
+    use quartz_nbt::{NbtCompound, NbtList};
     use std::fs;
     use std::fs::File;
-    use std::io::Write;
+    use std::io::{Cursor, Write};
     use std::path::Path;
 
     #[test]
@@ -41,7 +50,7 @@ mod anvil_tests {
         of.write_all(&map).unwrap();
         of.flush().unwrap();
 
-        let out = level.write_minev2(McVersion::Release1_2_1);
+        let out = level.write_minev2(McVersion::Release1_2_1).unwrap();
 
         println!("Compressing");
         let mut enc = GzEncoder::new(
@@ -187,4 +196,130 @@ mod anvil_tests {
         let output_dir = Path::new("../../internal_tests/anvil/dst/World2/");
         level.write_anvil_dir(output_dir);
     }
+
+    #[test]
+    fn map_items() {
+        let mut level = Level::new();
+        level.create_finite(128, 16, 128);
+        for x in 0..128 {
+            for z in 0..128 {
+                level.set_block(x, 5, z, Block::Grass);
+            }
+        }
+        level.set_block(10, 5, 10, Block::Water);
+
+        let map = level.create_map_item(64, 64, 0, 0);
+        // grass, normal shade
+        assert_eq!(map.colors[0], 5);
+        // shallow water, light shade
+        assert_eq!(map.colors[10 * 128 + 10], 12 * 4 + 2);
+        assert_eq!(
+            map.to_image().pixels,
+            level.render_map(0, 0, 128, 128).pixels
+        );
+
+        // 2 blocks per pixel, the level only covers the middle
+        let zoomed = level.create_map_item(64, 64, 1, 0);
+        assert_eq!(zoomed.colors[0], 0);
+        assert_ne!(zoomed.colors[32 * 128 + 32], 0);
+        assert_eq!(zoomed.to_image().get_pixel(0, 0)[3], 0);
+
+        for version in [
+            McVersion::Release1_2_1,
+            McVersion::Release1_13,
+            McVersion::Release1_14,
+            McVersion::Release1_16,
+        ] {
+            let read = MapItem::read_map_item(map.write_map_item(version)).unwrap();
+            assert_eq!(read, map);
+        }
+
+        let map_data = |version| {
+            let raw = map.write_map_item(version);
+            let (root, _) = io::read_nbt(&mut Cursor::new(raw), Flavor::GzCompressed).unwrap();
+            let data_version = root.get::<_, i32>("DataVersion").ok();
+            (
+                root.get::<_, &NbtCompound>("data").unwrap().clone(),
+                data_version,
+            )
+        };
+        let (data, data_version) = map_data(McVersion::Release1_2_1);
+        assert_eq!(data.get::<_, i8>("dimension").unwrap(), 0);
+        assert!(!data.contains_key("locked"));
+        assert_eq!(data_version, None);
+        let (data, data_version) = map_data(McVersion::Release1_13);
+        assert_eq!(data.get::<_, i32>("dimension").unwrap(), 0);
+        assert!(!data.contains_key("locked"));
+        assert_eq!(data_version, Some(1519));
+        let (data, data_version) = map_data(McVersion::Release1_14);
+        assert_eq!(data.get::<_, i32>("dimension").unwrap(), 0);
+        assert_eq!(data.get::<_, i8>("locked").unwrap(), 1);
+        assert_eq!(data_version, Some(1952));
+        let (data, data_version) = map_data(McVersion::Release1_16);
+        assert_eq!(
+            data.get::<_, &str>("dimension").unwrap(),
+            "minecraft:overworld"
+        );
+        assert_eq!(data_version, Some(2566));
+
+        let dir = std::env::temp_dir().join("lodestone_map_items");
+        let _ = fs::remove_dir_all(&dir);
+        let maps = level.create_wall_map_items(0, 0);
+        assert_eq!(maps.len(), 1);
+        write_map_items(&dir, &[map, zoomed], McVersion::Release1_2_1).unwrap();
+        assert!(dir.join("idcounts.dat").is_file());
+
+        let read = read_map_items(&dir).unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!((read[1].0, read[1].1.scale), (1, 1));
+
+        let _ = fs::remove_dir_all(&dir);
+        write_map_items(&dir, &[read[1].1.clone()], McVersion::Release1_16).unwrap();
+        let idcounts = fs::read(dir.join("idcounts.dat")).unwrap();
+        let (root, _) = io::read_nbt(&mut Cursor::new(idcounts), Flavor::GzCompressed).unwrap();
+        assert_eq!(root.get::<_, i32>("DataVersion").unwrap(), 2566);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn chunk_entities() {
+        let mut pig = NbtCompound::new();
+        pig.insert("id", "Pig");
+        pig.insert("Pos", NbtList::from(vec![5.5f64, 64.0, 6.5]));
+
+        let mut chunk = Chunk::new(128);
+        stash_entities(
+            &mut chunk.custom_data,
+            EntityFormat::Java,
+            &NbtList::from(vec![pig]),
+        );
+        let coords = Coords { x: 0, z: 0 };
+
+        let pig_of = |root: &NbtCompound| {
+            let level = root.get::<_, &NbtCompound>("Level").unwrap();
+            let entities = level.get::<_, &NbtList>("Entities").unwrap();
+            assert_eq!(entities.len(), 1);
+            let pig = entities.get::<&NbtCompound>(0).unwrap();
+            pig.get::<_, &str>("id").unwrap().to_string()
+        };
+
+        let out = chunk.write_alpha_chunk(McVersion::Alpha1_2_6, &coords);
+        let (root, _) = io::read_nbt(&mut Cursor::new(&out), Flavor::GzCompressed).unwrap();
+        assert_eq!(pig_of(&root), "Pig");
+
+        let out = MCRChunk::write_mcr(&chunk, McVersion::Release1_1, &coords);
+        let (root, _) = io::read_nbt(&mut Cursor::new(&out), Flavor::ZlibCompressed).unwrap();
+        assert_eq!(pig_of(&root), "Pig");
+
+        let out = chunk.write_anvil_chunk(&coords);
+        let (root, _) = io::read_nbt(&mut Cursor::new(&out), Flavor::ZlibCompressed).unwrap();
+        assert_eq!(pig_of(&root), "Pig");
+
+        // entities read from a chunk are written back out with it
+        let mut out = Vec::new();
+        io::write_nbt(&mut out, Some(""), &root, Flavor::Uncompressed).unwrap();
+        let (read, _) = Chunk::read_anvil_chunk(out).unwrap();
+        let entities = get_stashed_entities(&read.custom_data, EntityFormat::Java);
+        assert_eq!(entities.len(), 1);
+    }
 }
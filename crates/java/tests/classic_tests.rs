@@ -1,13 +1,32 @@
 mod classic_tests {
     use lodestone_common::util::McVersion;
     use lodestone_java::alpha::AlphaLevel;
-    use lodestone_java::classic::classic_world::CWLevel;
-    use lodestone_java::classic::mcgalaxy_lvl::MCGLevel;
+    use lodestone_java::classic::classic_world::{cpe_metadata_key, CWLevel};
+    use lodestone_java::classic::fcm::{fcm_metadata_key, FCMLevel, FCM_LIMITS};
+    use lodestone_java::classic::mcgalaxy_lvl::{mcg_property_key, MCGLevel};
+    use lodestone_java::classic::mine_v1::{MineV1Level, MINEV1_LIMITS};
+    use lodestone_java::classic::mine_v2::MineV2Level;
+    use lodestone_java::indev::IndevLevel;
     use lodestone_java::mcregion::Region;
+    use lodestone_common::types::hashmap_ext::HashMapExt;
+    use lodestone_common::types::vec3i::Vec3i;
+    use lodestone_level::entity::block_entity::{BlockEntity, HasBlockEntity};
+    use lodestone_level::block::Block;
+    use lodestone_level::level::environment::{Environment, Rgb, Weather};
+    use lodestone_level::level::export::{FiniteLimits, TruncationPolicy};
+    use lodestone_level::level::metadata;
+    use lodestone_level::level::chunk::Light;
+    use lodestone_level::level::edit::{BlockPattern, Mask};
+    use lodestone_level::level::query::Region as QueryRegion;
+    use lodestone_level::level::render::isometric::IsometricOptions;
+    use lodestone_level::level::render::slice::SlicePlane;
+    use lodestone_level::level::render::MapImage;
+    use lodestone_level::level::{Bounds, Coords};
     use lodestone_level::level::Level;
     use std::fs;
     use std::fs::{create_dir_all, File};
-    use std::io::Write;
+    use quartz_nbt::io::{self, Flavor};
+    use std::io::{Cursor, Read, Write};
     use std::path::Path;
 
     #[test]
@@ -110,4 +129,685 @@ mod classic_tests {
         of.write_all(&map).unwrap();
         of.flush().unwrap();
     }
+
+    #[test]
+    fn fcm_level() {
+        let file_name = "fcm_test";
+        let data = match fs::read(format!("../../internal_tests/classic/fcm/src/{file_name}.fcm")) {
+            Ok(d) => d,
+            Err(e) => {
+                eprintln!("uh oh {}", e);
+                return;
+            }
+        };
+        let level = Level::read_fcm(McVersion::Classic0_30, data).unwrap();
+
+        println!(
+            "World bounds (XYZ): {}x{}x{}",
+            level.get_block_width(),
+            level.get_block_height(),
+            level.get_block_length()
+        );
+
+        let out = level.write_fcm(McVersion::Classic0_30).unwrap();
+        let mut of = File::create(format!(
+            "../../internal_tests/classic/fcm/dst/{file_name}.fcm"
+        ))
+        .unwrap();
+        of.write_all(&out).unwrap();
+        of.flush().unwrap();
+    }
+
+    #[test]
+    fn fcm_round_trip() {
+        let mut level = Level::new_with_name("FCM".to_string());
+        level.create_finite(32, 16, 32);
+        level.set_block(0, 0, 0, Block::Stone);
+        level.set_block(31, 15, 31, Block::Glass);
+        level.set_block(17, 3, 5, Block::Sponge);
+        level.set_spawn_point(16, 8, 16);
+        level.custom_data.set_value(
+            fcm_metadata_key(metadata::FCM_ZONES, "spawn"),
+            "0 0 0 4 4 4".to_string(),
+        );
+
+        let out = level.write_fcm(McVersion::Classic0_30).unwrap();
+        let read = Level::read_fcm(McVersion::Classic0_30, out).unwrap();
+
+        assert_eq!(read.get_block(0, 0, 0), Block::Stone);
+        assert_eq!(read.get_block(31, 15, 31), Block::Glass);
+        assert_eq!(read.get_block(17, 3, 5), Block::Sponge);
+        assert_eq!(read.get_block(1, 0, 0), Block::Air);
+        assert_eq!(read.spawn.x, 16);
+        assert_eq!(read.spawn.y, 8);
+        assert_eq!(read.spawn.z, 16);
+        assert_eq!(
+            read.custom_data
+                .get_value::<String, _>(fcm_metadata_key(metadata::FCM_ZONES, "spawn")),
+            Some("0 0 0 4 4 4".to_string())
+        );
+    }
+
+    #[test]
+    fn finite_size_round_trip() {
+        let mut level = Level::new();
+        level.create_finite(100, 64, 100);
+        level.set_block(99, 63, 99, Block::Glass);
+
+        let check = |read: Level| {
+            let bounds = read.get_finite_bounds();
+            assert_eq!((bounds.width, bounds.height, bounds.length), (100, 64, 100));
+            assert_eq!(read.get_block(99, 63, 99), Block::Glass);
+        };
+
+        let v = McVersion::Classic0_30;
+        check(Level::read_minev2(v, level.write_minev2(v).unwrap()).unwrap());
+        check(Level::read_cw(v, level.write_cw(v).unwrap()).unwrap());
+        check(Level::read_mcgalaxy_level(v, level.write_mcgalaxy_level(v).unwrap()).unwrap());
+        check(Level::read_fcm(v, level.write_fcm(v).unwrap()).unwrap());
+        let indev = level.write_indev(McVersion::Indev).unwrap();
+        check(Level::read_indev(McVersion::Indev, indev).unwrap());
+    }
+
+    #[test]
+    fn export_to_minev1() {
+        let mut level = Level::new();
+        level.create_finite(300, 80, 40);
+        level.set_spawn_point(250, 10, 20);
+        level.set_block(260, 6, 6, Block::Glass);
+        level.set_block(10, 6, 6, Block::Stone);
+
+        assert!(level
+            .export_finite(None, &MINEV1_LIMITS, TruncationPolicy::Fail)
+            .is_err());
+        // writers don't truncate on their own
+        assert!(level.write_minev1(McVersion::Classic0_30).is_err());
+        assert!(FCM_LIMITS.check(i16::MAX as i32, 2, i16::MAX as i32).is_ok());
+        assert!(FCM_LIMITS.check(i16::MAX as i32, 3, i16::MAX as i32).is_err());
+
+        let (cropped, report) = level
+            .export_finite(None, &MINEV1_LIMITS, TruncationPolicy::Crop)
+            .unwrap();
+        assert!(report.cropped);
+        assert_eq!((report.exported.x, report.exported.y), (44, 0));
+        assert_eq!((report.width, report.height, report.length), (256, 64, 40));
+        assert_eq!(cropped.spawn.x, 206);
+
+        assert_eq!(cropped.get_block(216, 6, 6), Block::Glass);
+        let v = McVersion::Classic0_30;
+        let read = Level::read_minev1(v, cropped.write_minev1(v).unwrap()).unwrap();
+        assert_eq!(read.get_block(216, 6, 6), Block::Glass);
+
+        let (scaled, report) = level
+            .export_finite(None, &MINEV1_LIMITS, TruncationPolicy::Scale)
+            .unwrap();
+        assert_eq!(report.scale, 2);
+        assert_eq!((report.width, report.height, report.length), (150, 40, 20));
+        assert_eq!(scaled.get_block(130, 3, 3), Block::Glass);
+        assert_eq!(scaled.get_block(5, 3, 3), Block::Stone);
+
+        let no_space = FiniteLimits { max_width: 0, ..MINEV1_LIMITS };
+        assert!(level
+            .export_finite(None, &no_space, TruncationPolicy::Scale)
+            .is_err());
+        assert!(level
+            .export_finite(None, &no_space, TruncationPolicy::Crop)
+            .is_err());
+    }
+
+    #[test]
+    fn render_map_png() {
+        let mut level = Level::new();
+        level.create_finite(64, 32, 64);
+        for x in 0..64 {
+            for z in 0..64 {
+                level.set_block(x, 10, z, Block::Grass);
+            }
+        }
+        // 5 deep pool
+        for x in 10..15 {
+            for z in 10..15 {
+                for y in 6..11 {
+                    level.set_block(x, y, z, Block::Water);
+                }
+            }
+        }
+        level.set_block(20, 11, 20, Block::Stone);
+        level.set_block(21, 11, 20, Block::Stone);
+        level.set_block(40, 20, 40, Block::Glass);
+
+        let map = level.render_map(0, 0, 64, 64);
+        let flat = map.get_pixel(42, 40);
+        assert_eq!(flat, [109, 153, 48, 255]);
+        // glass is see-through
+        assert_eq!(map.get_pixel(40, 40), flat);
+        // lit on the north side, shaded on the south side
+        assert_eq!(map.get_pixel(21, 20), [112, 112, 112, 255]);
+        assert_eq!(map.get_pixel(21, 21), [89, 125, 39, 255]);
+        assert_eq!(map.get_pixel(12, 12), [55, 55, 220, 255]);
+
+        let png = map.to_png();
+        assert_eq!(&png[1..4], b"PNG");
+        let mut idat = Vec::new();
+        flate2::read::ZlibDecoder::new(&png[41..png.len() - 12])
+            .read_to_end(&mut idat)
+            .unwrap();
+        assert_eq!(idat.len(), 64 * (64 * 4 + 1));
+
+        // outside of the level
+        assert!(level.render_map(100, 100, 16, 16).is_empty());
+
+        // a zoomed out pixel half outside of the level is as deep as the water that is inside of it
+        for y in 0..11 {
+            level.set_block(63, y, 63, Block::Water);
+        }
+        assert_eq!(
+            level.get_map_colors(63, 63, 1, 1, 2),
+            level.get_map_colors(63, 63, 1, 1, 1)
+        );
+
+        assert!(level.render_map_tiles(0).is_empty());
+        let tiles = level.render_map_tiles(32);
+        assert_eq!(tiles.len(), 5);
+        let top: &MapImage = &tiles.iter().find(|t| t.zoom == 0).unwrap().image;
+        assert_eq!((top.width, top.height), (32, 32));
+        assert!(!top.is_empty());
+    }
+
+    #[test]
+    fn render_isometric_and_slice() {
+        let mut level = Level::new();
+        level.create_finite(16, 16, 16);
+        level.set_block(0, 0, 0, Block::Stone);
+        level.set_block(3, 2, 0, Block::Sand);
+
+        let one = Some(Bounds {
+            x: 0,
+            y: 0,
+            z: 0,
+            width: 1,
+            height: 1,
+            length: 1,
+        });
+        let mut options = IsometricOptions {
+            block_size: 4,
+            lighting: false,
+        };
+        let iso = level.render_isometric(one, &options);
+        assert_eq!((iso.width, iso.height), (8, 8));
+        assert_eq!(iso.get_pixel(0, 0)[3], 0);
+        assert_eq!(iso.get_pixel(4, 1), [112, 112, 112, 255]);
+        assert_eq!(iso.get_pixel(1, 5), [89, 89, 89, 255]);
+        assert_eq!(iso.get_pixel(6, 5), [67, 67, 67, 255]);
+
+        // no light above the block
+        options.lighting = true;
+        assert_eq!(level.render_isometric(one, &options).get_pixel(4, 1), [28, 28, 28, 255]);
+        level.set_light(Light::SKY, 0, 1, 0, 15);
+        assert_eq!(level.render_isometric(one, &options).get_pixel(4, 1), [112, 112, 112, 255]);
+
+        let slice = level.render_slice(SlicePlane::Xy { z: 0 }, None, 2);
+        assert_eq!((slice.width, slice.height), (32, 32));
+        assert_eq!(slice.get_pixel(7, 27), [247, 233, 163, 255]);
+        assert_eq!(slice.get_pixel(0, 31), [112, 112, 112, 255]);
+        assert_eq!(slice.get_pixel(0, 29)[3], 0);
+        assert_eq!(
+            level.render_slice(SlicePlane::Zy { x: 3 }, None, 1).get_pixel(0, 13),
+            [247, 233, 163, 255]
+        );
+    }
+
+    #[test]
+    fn level_stats() {
+        let mut level = Level::new();
+        level.create_finite(32, 32, 32);
+        for y in 0..16 {
+            for z in 0..32 {
+                for x in 0..32 {
+                    level.set_block(x, y, z, Block::Stone);
+                }
+            }
+        }
+        level.set_block(20, 20, 5, Block::Tnt);
+        for x in 0..3 {
+            level.set_block(x, 16, 0, Block::Water);
+        }
+
+        let stats = level.get_stats();
+        assert_eq!(stats.chunk_count, 4);
+        assert_eq!(stats.get_count(Block::Stone), 32 * 32 * 16);
+        assert_eq!(stats.get_count_at_y(Block::Stone, 3), 32 * 32);
+        assert_eq!(stats.get_count_at_y(Block::Water, 16), 3);
+        assert_eq!(stats.get_count(Block::Air), 0);
+        assert_eq!(stats.get_chunks_with(Block::Tnt), vec![Coords { x: 1, z: 0 }]);
+        assert_eq!(stats.chunks[0].total, 16 * 16 * 16 + 3);
+        assert_eq!(
+            stats.get_fraction(&[Block::Water]),
+            3.0 / (32.0 * 32.0 * 16.0 + 4.0)
+        );
+        assert_eq!(
+            stats.bounds,
+            Some(Bounds {
+                x: 0,
+                y: 0,
+                z: 0,
+                width: 32,
+                height: 21,
+                length: 32,
+            })
+        );
+    }
+
+    #[test]
+    fn mcg_custom_blocks_round_trip() {
+        let mut level = Level::new();
+        level.create_finite(32, 24, 32);
+        level.set_block(3, 2, 1, Block::Stone);
+        level.set_block(20, 17, 9, Block::Custom);
+        level.set_data(
+            20,
+            17,
+            9,
+            metadata::CUSTOM_BLOCK_ID.to_string(),
+            "300".to_string(),
+        );
+        level
+            .custom_data
+            .set_value(metadata::MCG_PHYSICS_DATA.to_string(), vec![1u8, 0, 0, 0, 2, 0, 0, 0]);
+
+        let out = level.write_mcgalaxy_level(McVersion::Classic0_30).unwrap();
+        let read = Level::read_mcgalaxy_level(McVersion::Classic0_30, out).unwrap();
+
+        assert_eq!(read.get_block(3, 2, 1), Block::Stone);
+        assert_eq!(read.get_block(20, 17, 9), Block::Custom);
+        assert_eq!(
+            read.get_states(20, 17, 9)
+                .and_then(|s| s.get(metadata::CUSTOM_BLOCK_ID).cloned()),
+            Some("300".to_string())
+        );
+        assert_eq!(
+            read.custom_data
+                .get_value::<Vec<u8>, _>(metadata::MCG_PHYSICS_DATA),
+            Some(vec![1u8, 0, 0, 0, 2, 0, 0, 0])
+        );
+    }
+
+    #[test]
+    fn mcg_properties() {
+        let mut level = Level::new();
+        level.read_mcgalaxy_properties(
+            b"#Level properties\nMOTD = Welcome!\nPhysics = 0\n".to_vec(),
+        );
+
+        assert_eq!(
+            level
+                .custom_data
+                .get_value::<String, _>(mcg_property_key("MOTD")),
+            Some("Welcome!".to_string())
+        );
+
+        let mut read = Level::new();
+        read.read_mcgalaxy_properties(level.write_mcgalaxy_properties());
+        assert_eq!(
+            read.custom_data
+                .get_value::<String, _>(mcg_property_key("Physics")),
+            Some("0".to_string())
+        );
+    }
+
+    #[test]
+    fn cw_cpe_round_trip() {
+        let mut level = Level::new_with_name("CPE".to_string());
+        level.create_finite(16, 16, 16);
+        level.set_block(1, 1, 1, Block::GoldBlock);
+        level.set_block(2, 3, 4, Block::Custom);
+        level.set_data(
+            2,
+            3,
+            4,
+            metadata::CUSTOM_BLOCK_ID.to_string(),
+            "700".to_string(),
+        );
+        level
+            .custom_data
+            .set_value(cpe_metadata_key(metadata::CPE_CLICK_DISTANCE, "Distance"), 160i16);
+        level.custom_data.set_value(
+            cpe_metadata_key(metadata::CPE_BLOCK_DEFINITIONS, "Block700.Name"),
+            "Marble".to_string(),
+        );
+        level.custom_data.set_value(
+            cpe_metadata_key(metadata::CPE_BLOCK_DEFINITIONS, "Block700.Speed"),
+            1.5f32,
+        );
+
+        let out = level.write_cw(McVersion::Classic0_30).unwrap();
+        let read = Level::read_cw(McVersion::Classic0_30, out.clone()).unwrap();
+
+        // a BlockArray2 that doesn't cover every block is an error rather than a panic
+        let (mut nbt, name) = io::read_nbt(&mut Cursor::new(out), Flavor::GzCompressed).unwrap();
+        nbt.insert("BlockArray2", vec![0u8; 16]);
+        let mut short = Vec::new();
+        io::write_nbt(&mut short, Some(&name), &nbt, Flavor::GzCompressed).unwrap();
+        assert!(Level::read_cw(McVersion::Classic0_30, short).is_err());
+
+        assert_eq!(read.get_block(1, 1, 1), Block::GoldBlock);
+        assert_eq!(read.get_block(2, 3, 4), Block::Custom);
+        assert_eq!(
+            read.get_states(2, 3, 4)
+                .and_then(|s| s.get(metadata::CUSTOM_BLOCK_ID).cloned()),
+            Some("700".to_string())
+        );
+        assert_eq!(
+            read.custom_data
+                .get_value::<i16, _>(cpe_metadata_key(metadata::CPE_CLICK_DISTANCE, "Distance")),
+            Some(160)
+        );
+        assert_eq!(
+            read.custom_data.get_value::<String, _>(cpe_metadata_key(
+                metadata::CPE_BLOCK_DEFINITIONS,
+                "Block700.Name"
+            )),
+            Some("Marble".to_string())
+        );
+        assert_eq!(
+            read.custom_data.get_value::<f32, _>(cpe_metadata_key(
+                metadata::CPE_BLOCK_DEFINITIONS,
+                "Block700.Speed"
+            )),
+            Some(1.5)
+        );
+    }
+
+    #[test]
+    fn cpe_custom_blocks() {
+        let mut level = Level::new_with_name("CustomBlocks".to_string());
+        level.create_finite(16, 16, 16);
+        level.set_block(0, 0, 0, Block::CobblestoneSlab);
+        level.set_block(1, 0, 0, Block::StoneBrick);
+        level.set_block(2, 0, 0, Block::Sand);
+
+        let cw = level.write_cw(McVersion::Classic0_30).unwrap();
+        let read = Level::read_cw(McVersion::Classic0_30, cw).unwrap();
+
+        assert_eq!(read.get_block(0, 0, 0), Block::CobblestoneSlab);
+        assert_eq!(read.get_block(1, 0, 0), Block::StoneBrick);
+        assert_eq!(read.get_block(2, 0, 0), Block::Sand);
+
+        // vanilla formats get the fallback blocks instead
+        let mine = level.write_minev2(McVersion::Classic0_30).unwrap();
+        let read = Level::read_minev2(McVersion::Classic0_30, mine).unwrap();
+
+        assert_eq!(read.get_block(0, 0, 0), Block::SmoothStoneSlab);
+        assert_eq!(read.get_block(1, 0, 0), Block::Stone);
+        assert_eq!(read.get_block(2, 0, 0), Block::Sand);
+    }
+
+    #[test]
+    fn environment_round_trip() {
+        let mut level = Level::new_with_name("Environment".to_string());
+        level.create_finite(16, 32, 16);
+        level.environment = Environment {
+            sky_color: Some(Rgb::new(0x12, 0x34, 0x56)),
+            fog_color: Some(Rgb::from_packed(0xFF8800)),
+            edge_block: Some(Block::Lava),
+            edge_height: Some(10),
+            side_height: Some(8),
+            weather: Some(Weather::Snowing),
+            ..Default::default()
+        };
+
+        let cw = level.write_cw(McVersion::Classic0_30).unwrap();
+        let cw = Level::read_cw(McVersion::Classic0_30, cw).unwrap();
+        assert_eq!(cw.environment.sky_color, level.environment.sky_color);
+        assert_eq!(cw.environment.fog_color, level.environment.fog_color);
+        assert_eq!(cw.environment.edge_block, Some(Block::Lava));
+        assert_eq!(cw.environment.side_height, Some(8));
+        assert_eq!(cw.environment.weather, Some(Weather::Snowing));
+
+        let mut mcg = Level::new();
+        mcg.read_mcgalaxy_properties(level.write_mcgalaxy_properties());
+        assert_eq!(mcg.environment, level.environment);
+
+        let fcm = level.write_fcm(McVersion::Classic0_30).unwrap();
+        let fcm = Level::read_fcm(McVersion::Classic0_30, fcm).unwrap();
+        assert_eq!(fcm.environment.sky_color, level.environment.sky_color);
+        assert_eq!(fcm.environment.edge_height, Some(10));
+    }
+
+    #[test]
+    fn block_queries() {
+        let mut level = Level::new();
+        level.create_finite(32, 32, 32);
+        for z in 0..32 {
+            for x in 0..32 {
+                level.set_block(x, 0, z, Block::Bedrock);
+            }
+        }
+        level.set_block(3, 5, 3, Block::GoldBlock);
+        level.set_block(20, 5, 20, Block::GoldBlock);
+        level.set_block(20, 6, 20, Block::Tnt);
+
+        let column: Vec<_> = level
+            .iter_region(QueryRegion::Column { x: 20, z: 20 })
+            .collect();
+        assert_eq!(column.len(), 3);
+        assert_eq!(column[2].block, Block::Tnt);
+        assert_eq!(column[2].pos, Vec3i::new(20, 6, 20));
+
+        let all = level.find_blocks(
+            QueryRegion::Box(level.get_finite_bounds()),
+            &[Block::GoldBlock, Block::Tnt],
+        );
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].pos, Vec3i::new(3, 5, 3));
+
+        let sphere = QueryRegion::Sphere {
+            x: 20,
+            y: 4,
+            z: 20,
+            radius: 2,
+        };
+        let near = level.find_blocks(sphere, &[Block::GoldBlock, Block::Tnt]);
+        assert_eq!(near.len(), 2);
+        assert_eq!(level.iter_region(sphere).count(), 2);
+
+        let high = level
+            .find_blocks_matching(QueryRegion::Box(level.get_finite_bounds()), |b| b.pos.y > 0);
+        assert_eq!(high.len(), 3);
+
+        if let Some(c) = level.get_chunk_by_block_coords_mut(20, 20) {
+            c.add_block_entity(
+                Vec3i::new(20, 5, 20),
+                BlockEntity {
+                    id: "minecraft:chest".to_string(),
+                    x: 20,
+                    y: 5,
+                    z: 20,
+                    components: Default::default(),
+                    keep_packed: false,
+                    data: HasBlockEntity::NotFound(
+                        "minecraft:chest".to_string(),
+                        Default::default(),
+                    ),
+                },
+            );
+        }
+        assert_eq!(level.find_block_entities(Some("Chest"), None).len(), 1);
+        assert_eq!(level.find_block_entities(Some("chest"), Some(sphere)).len(), 1);
+        assert!(level
+            .find_block_entities(None, Some(QueryRegion::Column { x: 3, z: 3 }))
+            .is_empty());
+    }
+
+    #[test]
+    fn bulk_edits() {
+        let mut level = Level::new();
+        level.create_finite(32, 32, 32);
+        level.set_block(0, 0, 0, Block::Stone);
+        level.set_light(Light::SKY, 0, 1, 0, 15);
+
+        let floor = Bounds {
+            x: 0,
+            y: 0,
+            z: 0,
+            width: 32,
+            height: 1,
+            length: 32,
+        };
+        assert_eq!(level.set_box(floor, &Block::Stone.into()), 32 * 32 - 1);
+        assert_eq!(level.get_block(31, 0, 31), Block::Stone);
+        let chunk = level.get_chunk_by_block_coords(20, 20).unwrap();
+        assert_eq!(chunk.get_height(4, 4), 1);
+
+        // only the chunk that already had light gets relit
+        level.set_block(0, 5, 0, Block::Stone);
+        level.set_box(floor, &Block::Dirt.into());
+        assert_eq!(level.get_light(Light::SKY, 0, 6, 0), 15);
+        assert_eq!(level.get_light(Light::SKY, 0, 3, 0), 0);
+
+        let lit = BlockPattern::new(Block::Custom).with_state("lit", "true");
+        level.set_block(1, 1, 1, Block::Custom);
+        level.set_data(1, 1, 1, "lit".to_string(), "false".to_string());
+        level.set_block(2, 1, 1, Block::Custom);
+        let everywhere = level.get_finite_bounds();
+        let unlit = Mask::Blocks(vec![
+            BlockPattern::new(Block::Custom).with_state("lit", "false")
+        ]);
+        assert_eq!(level.replace_blocks(everywhere, &unlit, &Block::Tnt.into()), 1);
+        assert_eq!(level.get_block(1, 1, 1), Block::Tnt);
+        assert_eq!(level.get_block(2, 1, 1), Block::Custom);
+        level.get_chunk_by_block_coords_mut(2, 1).unwrap().add_block_entity(
+            Vec3i::new(2, 1, 1),
+            BlockEntity {
+                id: "minecraft:furnace".to_string(),
+                x: 2,
+                y: 1,
+                z: 1,
+                components: Default::default(),
+                keep_packed: false,
+                data: HasBlockEntity::NotFound(
+                    "minecraft:furnace".to_string(),
+                    Default::default(),
+                ),
+            },
+        );
+        let custom = Mask::Blocks(vec![Block::Custom.into()]);
+        assert_eq!(level.replace_blocks(everywhere, &custom, &lit), 1);
+        assert_eq!(
+            level.get_states(2, 1, 1).and_then(|s| s.get("lit")).map(String::as_str),
+            Some("true")
+        );
+        // only changing the state keeps the block entity, replacing the block doesn't
+        let has_furnace =
+            |level: &Level| level.find_block_entities(Some("minecraft:furnace"), None).len();
+        assert_eq!(has_furnace(&level), 1);
+        level.replace_blocks(everywhere, &custom, &Block::Stone.into());
+        assert_eq!(has_furnace(&level), 0);
+        level.set_block(2, 1, 1, Block::Custom);
+        level.set_data(2, 1, 1, "lit".to_string(), "true".to_string());
+
+        let room = Bounds {
+            x: 10,
+            y: 1,
+            z: 10,
+            width: 5,
+            height: 4,
+            length: 5,
+        };
+        assert_eq!(level.set_walls(room, &Block::Glass.into()), 16 * 4);
+        assert_eq!(level.get_block(12, 2, 12), Block::Air);
+        assert_eq!(level.fill_box(room, &Block::Water.into()), 9 * 4);
+        assert_eq!(level.get_block(10, 2, 10), Block::Glass);
+        level.set_hollow_box(room, &Block::OakPlanks.into());
+        assert_eq!(level.get_block(12, 1, 12), Block::OakPlanks);
+        assert_eq!(level.get_block(12, 2, 12), Block::Air);
+        assert_eq!(level.get_block(10, 2, 10), Block::OakPlanks);
+
+        let changed = level.copy_box(room, Vec3i::new(10, 0, 0), true);
+        assert_eq!(changed, 5 * 5 * 4 - 3 * 3 * 2);
+        assert_eq!(level.get_block(20, 1, 10), Block::OakPlanks);
+        assert_eq!(level.get_block(22, 2, 12), Block::Air);
+        assert_eq!(
+            level.get_chunk_by_block_coords(22, 12).unwrap().get_height(6, 12),
+            5
+        );
+    }
+
+    #[test]
+    fn edits_near_coordinate_limits() {
+        let mut level = Level::new();
+        level.create_finite(32, 32, 32);
+
+        let huge = Bounds {
+            x: 0,
+            y: 0,
+            z: 0,
+            width: i32::MAX,
+            height: i16::MAX,
+            length: i32::MAX,
+        };
+        assert_eq!(level.set_walls(huge, &Block::Stone.into()), (32 + 31) * 32);
+        assert_eq!(level.get_block(31, 31, 0), Block::Stone);
+        assert_eq!(level.get_block(31, 31, 31), Block::Air);
+
+        let far = Bounds {
+            x: i32::MAX - 1,
+            y: i16::MAX - 1,
+            z: i32::MIN,
+            ..huge
+        };
+        assert_eq!(level.set_hollow_box(far, &Block::Stone.into()), 0);
+        // the destination is clamped instead of overflowing, and nothing is there to paste into
+        let small = Bounds {
+            width: 4,
+            height: 4,
+            length: 4,
+            ..huge
+        };
+        let offset = Vec3i::new(i32::MAX, i32::MAX, i32::MIN);
+        assert_eq!(level.copy_box(small, offset, false), 0);
+
+        let sphere = QueryRegion::Sphere {
+            x: i32::MAX,
+            y: 0,
+            z: i32::MIN,
+            radius: i32::MAX,
+        };
+        assert_eq!(level.iter_region(sphere).count(), 0);
+        assert_eq!(
+            level.iter_region(QueryRegion::Box(huge)).count(),
+            (32 + 31) * 32
+        );
+    }
+
+    #[test]
+    fn sky_light_opacity() {
+        let mut level = Level::new();
+        level.create_finite(16, 16, 16);
+        // only chunks that already have light get relit
+        level.set_block(0, 0, 0, Block::Stone);
+        level.set_light(Light::SKY, 0, 15, 0, 15);
+
+        for (x, block) in [
+            (1, Block::Rose),
+            (2, Block::OakLeaves),
+            (3, Block::Water),
+            (4, Block::Stone),
+        ] {
+            let at = Bounds {
+                x,
+                y: 10,
+                z: 0,
+                width: 1,
+                height: 1,
+                length: 1,
+            };
+            level.set_box(at, &block.into());
+        }
+
+        assert_eq!(level.get_light(Light::SKY, 1, 9, 0), 15);
+        assert_eq!(level.get_light(Light::SKY, 2, 9, 0), 14);
+        assert_eq!(level.get_light(Light::SKY, 3, 9, 0), 12);
+        assert_eq!(level.get_light(Light::SKY, 4, 9, 0), 0);
+    }
 }
@@ -1,7 +1,17 @@
 mod indev_tests {
+    use lodestone_common::types::hashmap_ext::HashMapExt;
+    use lodestone_common::types::vec3i::Vec3i;
     use lodestone_common::util::McVersion;
     use lodestone_java::indev::IndevLevel;
+    use lodestone_level::block::Block;
+    use lodestone_level::entity::block_entity::{BlockEntity, HasBlockEntity};
+    use lodestone_level::level::diff::{DiffOptions, DiffSide};
+    use lodestone_level::level::metadata;
+    use lodestone_level::level::render::isometric::IsometricOptions;
+    use lodestone_level::level::render::slice::SlicePlane;
+    use lodestone_level::level::surroundings::Surroundings;
     use lodestone_level::level::Level;
+    use quartz_nbt::NbtCompound;
     use std::fs;
     use std::fs::File;
     use std::io::Write;
@@ -41,4 +51,146 @@ mod indev_tests {
         of.write_all(&map).unwrap();
         of.flush().unwrap();
     }
+
+    #[test]
+    fn indev_level_renders() {
+        let fname = "IndevTest";
+
+        let data = match fs::read(format!("../../internal_tests/indev/src/{}.mclevel", fname)) {
+            Ok(d) => d,
+            Err(e) => {
+                eprintln!("uh oh {}", e);
+                return;
+            }
+        };
+
+        let level = Level::read_indev(McVersion::Infdev20100630, data)
+            .expect("Could not read Indev level from file!");
+
+        println!("Writing isometric render");
+        let iso = level.render_isometric(None, &IsometricOptions::default());
+        fs::write(
+            format!("../../internal_tests/map/{}-iso.png", fname),
+            iso.to_png(),
+        )
+        .unwrap();
+
+        println!("Writing slice render");
+        let slice = level.render_slice(
+            SlicePlane::Xy {
+                z: level.get_block_length() / 2,
+            },
+            None,
+            2,
+        );
+        fs::write(
+            format!("../../internal_tests/map/{}-slice.png", fname),
+            slice.to_png(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn indev_surroundings() {
+        let mut level = Level::new();
+        level.create_finite(20, 64, 20);
+        level.set_block(19, 10, 19, Block::Glass);
+        level.environment.side_block = Some(Block::Grass);
+        level.environment.side_height = Some(30);
+        level.environment.edge_height = Some(32);
+
+        let read = Level::read_indev(
+            McVersion::Indev,
+            level.write_indev(McVersion::Indev).unwrap(),
+        )
+        .unwrap();
+        let mut surroundings = Surroundings::from_environment(&read);
+        surroundings.radius = 1;
+
+        let mut level = read;
+        level.add_surroundings(&surroundings);
+
+        // 2x2 chunks plus one on each side
+        assert_eq!(level.get_chunk_count(), 16);
+        assert_eq!(level.get_block(19, 10, 19), Block::Glass);
+        // padding columns of the level's own chunks get filled too
+        for x in [-16, 25, 47] {
+            assert_eq!(level.get_block(x, 0, 5), Block::Bedrock);
+            assert_eq!(level.get_block(x, 28, 5), Block::Dirt);
+            assert_eq!(level.get_block(x, 29, 5), Block::Grass);
+            assert_eq!(level.get_block(x, 31, 5), Block::Water);
+            assert_eq!(level.get_block(x, 32, 5), Block::Air);
+        }
+        assert_eq!(level.get_block(5, 10, 5), Block::Air);
+        // stored the way the anvil writer reads it back
+        assert!(level.get_chunks().values().all(|c| {
+            c.custom_data
+                .get_value::<i8, &str>(metadata::TERRAIN_POPULATED)
+                == Some(1)
+        }));
+    }
+
+    #[test]
+    fn indev_round_trip_diff() {
+        let mut level = Level::new();
+        level.create_finite(32, 32, 32);
+        level.set_block(1, 2, 3, Block::Stone);
+        level.set_block(30, 20, 17, Block::Glass);
+
+        let mut read = Level::read_indev(
+            McVersion::Indev,
+            level.write_indev(McVersion::Indev).unwrap(),
+        )
+        .unwrap();
+
+        let options = DiffOptions {
+            compare_metadata: false,
+            ..Default::default()
+        };
+        let diff = level.diff(&read, &options);
+        assert!(diff.is_empty(), "{:?}", diff.to_list());
+
+        read.set_block(30, 20, 17, Block::Air);
+        read.set_block(20, 5, 5, Block::GoldBlock);
+
+        let diff = level.diff(&read, &options);
+        assert_eq!(diff.blocks.len(), 2);
+        assert_eq!(diff.block_counts[&(Block::Glass, Block::Air)], 1);
+        assert_eq!(diff.chunk_counts.len(), 2);
+
+        let changes = diff.to_level(DiffSide::To);
+        assert_eq!(changes.get_block(20, 5, 5), Block::GoldBlock);
+        assert_eq!(changes.get_block(30, 20, 17), Block::Air);
+        assert_eq!(
+            diff.to_level(DiffSide::From).get_block(30, 20, 17),
+            Block::Glass
+        );
+
+        // block entities that only differ in their data are changes too
+        let add_chest = |level: &mut Level, name: &str| {
+            let mut chest = NbtCompound::new();
+            chest.insert("CustomName", name);
+            level
+                .get_chunk_by_block_coords_mut(4, 4)
+                .unwrap()
+                .add_block_entity(
+                    Vec3i::new(4, 4, 4),
+                    BlockEntity {
+                        id: "Chest".to_string(),
+                        x: 4,
+                        y: 4,
+                        z: 4,
+                        components: Default::default(),
+                        keep_packed: false,
+                        data: HasBlockEntity::NotFound("Chest".to_string(), chest),
+                    },
+                );
+        };
+        let mut other = level.clone();
+        add_chest(&mut level, "loot");
+        add_chest(&mut other, "loot");
+        assert!(level.diff(&other, &options).block_entities.is_empty());
+        add_chest(&mut other, "more loot");
+        assert_eq!(level.diff(&other, &options).block_entities.len(), 1);
+    }
 }
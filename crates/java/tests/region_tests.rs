@@ -8,10 +8,11 @@ mod region_tests {
     use lodestone_java::anvil::Anvil;
     use lodestone_java::classic::classic_world::CWLevel;
     use lodestone_java::classic::mcgalaxy_lvl::MCGLevel;
-    use lodestone_java::classic::mine_v1::MineV1Level;
+    use lodestone_java::classic::mine_v1::{MineV1Level, MINEV1_LIMITS};
     use lodestone_java::classic::mine_v2::MineV2Level;
     use lodestone_java::indev::IndevLevel;
     use lodestone_java::mcregion::Region;
+    use lodestone_level::level::export::TruncationPolicy;
     use lodestone_level::level::Level;
     use std::fs;
     use std::fs::{create_dir_all, exists, remove_dir_all, File};
@@ -49,7 +50,10 @@ mod region_tests {
 
         println!("Writing Mine V1 world");
         let mv1_start = Instant::now();
-        let mv1 = level.write_minev1(Classic0_0_12a);
+        let (level, _) = level
+            .export_finite(None, &MINEV1_LIMITS, TruncationPolicy::Crop)
+            .unwrap();
+        let mv1 = level.write_minev1(Classic0_0_12a).unwrap();
         write_world(mv1, "RegionTest.mine", "minev1");
         let mv1_end = mv1_start.elapsed();
         println!("Mine V1: {:?}", mv1_end);
@@ -63,7 +67,7 @@ mod region_tests {
         println!("Writing Mine V2 world");
         let mv2_start = Instant::now();
 
-        let mv2: Vec<u8> = level.write_minev2(McVersion::Classic0_0_14a);
+        let mv2: Vec<u8> = level.write_minev2(McVersion::Classic0_0_14a).unwrap();
         write_world(mv2, "RegionTest.mine", "minev2");
         let mv2_end = mv2_start.elapsed();
 
@@ -78,7 +82,7 @@ mod region_tests {
         println!("Writing ClassicWorld world");
         let cw_start = Instant::now();
 
-        let cw: Vec<u8> = level.write_cw(McVersion::Classic0_30);
+        let cw: Vec<u8> = level.write_cw(McVersion::Classic0_30).unwrap();
         write_world_dc(cw, "RegionTest.cw", "cw");
         let cw_end = cw_start.elapsed();
 
@@ -92,7 +96,7 @@ mod region_tests {
 
         println!("Writing MCG world");
         let mcg_start = Instant::now();
-        let mcg = level.write_mcgalaxy_level(McVersion::Classic0_30);
+        let mcg = level.write_mcgalaxy_level(McVersion::Classic0_30).unwrap();
         write_world_dc(mcg, "RegionTest.lvl", "lvl");
 
         let mcg_end = mcg_start.elapsed();
@@ -107,7 +111,7 @@ mod region_tests {
 
         println!("Writing Indev world");
         let indev_start = Instant::now();
-        let indev = level.write_indev(McVersion::Infdev20100630);
+        let indev = level.write_indev(McVersion::Infdev20100630).unwrap();
         write_world_dc(indev, "RegionTest.mclevel", "indev");
 
         let indev_end = indev_start.elapsed();
@@ -0,0 +1,338 @@
+mod schematic_tests {
+    use lodestone_common::types::vec3i::Vec3i;
+    use lodestone_common::util::McVersion;
+    use lodestone_java::schematic::litematic::LitematicLevel;
+    use lodestone_java::schematic::mcedit::SchematicLevel;
+    use lodestone_java::schematic::sponge::SpongeSchematicLevel;
+    use lodestone_java::schematic::structure::StructureLevel;
+    use lodestone_level::block::Block;
+    use lodestone_level::entity::block_entity::{BlockEntity, HasBlockEntity};
+    use lodestone_level::level::metadata;
+    use lodestone_level::level::{Bounds, Level};
+    use quartz_nbt::io::{self, Flavor};
+    use quartz_nbt::{NbtCompound, NbtList};
+    use std::fs;
+    use std::io::Cursor;
+
+    fn region_bounds(width: i32, height: i16, length: i32) -> Bounds {
+        Bounds {
+            x: 0,
+            y: 0,
+            z: 0,
+            width,
+            height,
+            length,
+        }
+    }
+
+    #[test]
+    fn schematic_level() {
+        let data = match fs::read("../../internal_tests/schematic/src/test.schematic") {
+            Ok(d) => d,
+            Err(e) => {
+                eprintln!("uh oh {}", e);
+                return;
+            }
+        };
+
+        let level = Level::read_schematic(McVersion::Release1_2_1, data)
+            .expect("Could not read schematic!");
+
+        println!(
+            "Schematic bounds (XYZ): {}x{}x{}",
+            level.get_block_width(),
+            level.get_block_height(),
+            level.get_block_length()
+        );
+    }
+
+    #[test]
+    fn schematic_region_round_trip() {
+        let mut level = Level::new();
+        level.create_finite(32, 16, 32);
+        level.set_block(20, 2, 20, Block::Stone);
+        level.set_block(21, 3, 22, Block::CobblestoneSlab);
+        level.set_block(22, 4, 21, Block::Custom);
+        level.set_data(
+            22,
+            4,
+            21,
+            metadata::CUSTOM_BLOCK_ID.to_string(),
+            "1234".to_string(),
+        );
+
+        let region = level.copy_region(20, 2, 20, 4, 4, 4);
+        let out = region.write_schematic(McVersion::Release1_2_1);
+        let read = Level::read_schematic(McVersion::Release1_2_1, out).unwrap();
+
+        assert_eq!(read.get_finite_bounds(), region_bounds(4, 4, 4));
+        assert_eq!(read.get_block(0, 0, 0), Block::Stone);
+        assert_eq!(read.get_block(1, 1, 2), Block::CobblestoneSlab);
+        assert_eq!(read.get_block(2, 2, 1), Block::Custom);
+        assert_eq!(
+            read.get_states(2, 2, 1)
+                .and_then(|s| s.get(metadata::CUSTOM_BLOCK_ID).cloned()),
+            Some("1234".to_string())
+        );
+    }
+
+    #[test]
+    fn schematic_wool_colors() {
+        let mut schematic = NbtCompound::new();
+        schematic.insert("Width", 3i16);
+        schematic.insert("Height", 1i16);
+        schematic.insert("Length", 1i16);
+        schematic.insert("Materials", "Alpha");
+        schematic.insert("Blocks", vec![35u8, 35, 35]);
+        schematic.insert("Data", vec![14u8, 0, 11]);
+        let mut data = Vec::new();
+        io::write_nbt(
+            &mut data,
+            Some("Schematic"),
+            &schematic,
+            Flavor::GzCompressed,
+        )
+        .unwrap();
+
+        let level = Level::read_schematic(McVersion::Release1_2_1, data).unwrap();
+        assert_eq!(level.get_block(0, 0, 0), Block::RedWool);
+        assert_eq!(level.get_block(1, 0, 0), Block::WhiteWool);
+        assert_eq!(level.get_block(2, 0, 0), Block::BlueWool);
+
+        let out = level.write_schematic(McVersion::Release1_2_1);
+        let read = Level::read_schematic(McVersion::Release1_2_1, out).unwrap();
+        assert_eq!(read.get_block(0, 0, 0), Block::RedWool);
+        assert_eq!(read.get_block(2, 0, 0), Block::BlueWool);
+    }
+
+    #[test]
+    fn sponge_round_trip() {
+        let mut level = Level::new();
+        level.create_finite(16, 8, 16);
+        level.set_block(1, 0, 1, Block::Stone);
+        level.set_block(2, 1, 3, Block::OrangeWool);
+        level.set_block(3, 2, 1, Block::Custom);
+        level.set_data(
+            3,
+            2,
+            1,
+            metadata::CUSTOM_BLOCK_NAME.to_string(),
+            "modded:thing".to_string(),
+        );
+        level.set_data(3, 2, 1, "facing".to_string(), "north".to_string());
+
+        let mut sign = NbtCompound::new();
+        sign.insert("Text1", "hello");
+        if let Some(c) = level.get_chunk_by_block_coords_mut(4, 4) {
+            c.add_block_entity(
+                Vec3i::new(4, 1, 4),
+                BlockEntity {
+                    id: "minecraft:sign".to_string(),
+                    x: 4,
+                    y: 1,
+                    z: 4,
+                    components: Default::default(),
+                    keep_packed: false,
+                    data: HasBlockEntity::NotFound("minecraft:sign".to_string(), sign),
+                },
+            );
+        }
+
+        for sponge_version in [2, 3] {
+            let out = level.write_sponge_schematic(McVersion::Release1_13, sponge_version);
+            let read = Level::read_sponge_schematic(McVersion::Release1_13, out).unwrap();
+
+            assert_eq!(read.get_finite_bounds(), region_bounds(16, 8, 16));
+            assert_eq!(read.get_block(1, 0, 1), Block::Stone);
+            assert_eq!(read.get_block(2, 1, 3), Block::OrangeWool);
+            assert_eq!(read.get_block(3, 2, 1), Block::Custom);
+
+            let states = read.get_states(3, 2, 1).cloned().unwrap_or_default();
+            assert_eq!(
+                states.get(metadata::CUSTOM_BLOCK_NAME).map(String::as_str),
+                Some("modded:thing")
+            );
+            assert_eq!(states.get("facing").map(String::as_str), Some("north"));
+
+            let chunk = read.get_chunk_by_block_coords(4, 4).unwrap();
+            let sign = chunk.block_entities.get(&Vec3i::new(4, 1, 4)).unwrap();
+            assert_eq!(sign.id, "minecraft:sign");
+            match &sign.data {
+                HasBlockEntity::NotFound(_, data) => {
+                    assert_eq!(data.get::<_, &str>("Text1").unwrap(), "hello")
+                }
+                _ => panic!("Sign data was parsed"),
+            }
+        }
+    }
+
+    #[test]
+    fn sponge_region_round_trip() {
+        let mut level = Level::new();
+        level.create_finite(32, 16, 32);
+        level.set_block(20, 2, 20, Block::Stone);
+        level.set_block(23, 5, 23, Block::OrangeWool);
+
+        let region = level.copy_region(20, 2, 20, 4, 4, 4);
+        let out = region.write_sponge_schematic(McVersion::Release1_13, 3);
+        let read = Level::read_sponge_schematic(McVersion::Release1_13, out).unwrap();
+
+        assert_eq!(read.get_finite_bounds(), region_bounds(4, 4, 4));
+        assert_eq!(read.get_block(0, 0, 0), Block::Stone);
+        assert_eq!(read.get_block(3, 3, 3), Block::OrangeWool);
+    }
+
+    #[test]
+    fn sponge_entities() {
+        let mut level = Level::new();
+        level.create_finite(4, 4, 4);
+
+        let out = level.write_sponge_schematic(McVersion::Release1_13, 2);
+        let (mut schem, _) = io::read_nbt(&mut Cursor::new(out), Flavor::GzCompressed).unwrap();
+
+        let mut pig = NbtCompound::new();
+        pig.insert("Pos", NbtList::from(vec![1.5f64, 0.0, 1.5]));
+        pig.insert("Id", "minecraft:pig");
+        pig.insert("Health", 10i16);
+        schem.insert("Entities", NbtList::from(vec![pig]));
+
+        let mut out = Vec::new();
+        io::write_nbt(&mut out, Some("Schematic"), &schem, Flavor::GzCompressed).unwrap();
+        let level = Level::read_sponge_schematic(McVersion::Release1_13, out).unwrap();
+
+        // version 2 keeps everything next to Pos and Id, version 3 moves it into Data
+        for sponge_version in [2, 3] {
+            let out = level.write_sponge_schematic(McVersion::Release1_13, sponge_version);
+            let (root, _) = io::read_nbt(&mut Cursor::new(out), Flavor::GzCompressed).unwrap();
+            let schem = match sponge_version {
+                3 => root.get::<_, &NbtCompound>("Schematic").unwrap().clone(),
+                _ => root,
+            };
+
+            let entities = schem.get::<_, &NbtList>("Entities").unwrap();
+            assert_eq!(entities.len(), 1);
+            let pig = entities.get::<&NbtCompound>(0).unwrap();
+            assert_eq!(pig.get::<_, &str>("Id").unwrap(), "minecraft:pig");
+
+            let data = match sponge_version {
+                3 => pig.get::<_, &NbtCompound>("Data").unwrap(),
+                _ => pig,
+            };
+            assert_eq!(data.get::<_, i16>("Health").unwrap(), 10);
+        }
+
+        // MCEdit entities are laid out differently, so Sponge ones aren't written to it
+        let out = level.write_schematic(McVersion::Release1_2_1);
+        let (schematic, _) = io::read_nbt(&mut Cursor::new(out), Flavor::GzCompressed).unwrap();
+        assert!(schematic.get::<_, &NbtList>("Entities").unwrap().is_empty());
+    }
+
+    #[test]
+    fn structure_round_trip() {
+        let mut level = Level::new();
+        level.create_finite(32, 8, 32);
+        level.set_block(17, 0, 17, Block::Stone);
+        level.set_block(18, 2, 19, Block::Custom);
+        level.set_data(
+            18,
+            2,
+            19,
+            metadata::CUSTOM_BLOCK_NAME.to_string(),
+            "minecraft:oak_stairs".to_string(),
+        );
+        level.set_data(18, 2, 19, "half".to_string(), "top".to_string());
+
+        let mut chest = NbtCompound::new();
+        chest.insert("CustomName", "loot");
+        if let Some(c) = level.get_chunk_by_block_coords_mut(19, 17) {
+            c.add_block_entity(
+                Vec3i::new(19, 1, 17),
+                BlockEntity {
+                    id: "minecraft:chest".to_string(),
+                    x: 19,
+                    y: 1,
+                    z: 17,
+                    components: Default::default(),
+                    keep_packed: false,
+                    data: HasBlockEntity::NotFound("minecraft:chest".to_string(), chest),
+                },
+            );
+        }
+
+        let region = level.copy_region(16, 0, 16, 4, 4, 4);
+        let out = region.write_structure(McVersion::Release1_13);
+        let read = Level::read_structure(McVersion::Release1_13, out).unwrap();
+
+        assert_eq!(read.get_finite_bounds(), region_bounds(4, 4, 4));
+        assert_eq!(read.get_block(1, 0, 1), Block::Stone);
+        assert_eq!(read.get_block(2, 2, 3), Block::Custom);
+        assert_eq!(
+            read.get_states(2, 2, 3)
+                .and_then(|s| s.get("half").cloned()),
+            Some("top".to_string())
+        );
+
+        let chunk = read.get_chunk_by_block_coords(3, 1).unwrap();
+        let chest = chunk.block_entities.get(&Vec3i::new(3, 1, 1)).unwrap();
+        assert_eq!(chest.id, "minecraft:chest");
+    }
+
+    #[test]
+    fn litematic_regions() {
+        let mut house = Level::new();
+        house.create_finite(4, 4, 4);
+        house.set_block(1, 0, 1, Block::Stone);
+        house.set_block(2, 3, 2, Block::OrangeWool);
+        house.set_block(3, 1, 0, Block::Dirt);
+        house.set_block(0, 2, 3, Block::Glass);
+
+        let mut tower = Level::new();
+        tower.create_finite(2, 8, 2);
+        tower.set_block(0, 7, 0, Block::GoldBlock);
+
+        let read_nbt = |data: Vec<u8>| {
+            io::read_nbt(&mut Cursor::new(data), Flavor::GzCompressed)
+                .unwrap()
+                .0
+        };
+
+        // put both regions in one file, with the tower to the east of the house
+        let mut litematic = read_nbt(house.write_litematic(McVersion::Release1_13, "house"));
+        let tower_nbt = read_nbt(tower.write_litematic(McVersion::Release1_13, "tower"));
+
+        let mut tower_region = tower_nbt
+            .get::<_, &NbtCompound>("Regions")
+            .unwrap()
+            .get::<_, &NbtCompound>("tower")
+            .unwrap()
+            .clone();
+        let mut position = NbtCompound::new();
+        position.insert("x", 20);
+        position.insert("y", 0);
+        position.insert("z", 0);
+        tower_region.insert("Position", position);
+
+        litematic
+            .get_mut::<_, &mut NbtCompound>("Regions")
+            .unwrap()
+            .insert("tower", tower_region);
+
+        let mut data = Vec::new();
+        io::write_nbt(&mut data, Some(""), &litematic, Flavor::GzCompressed).unwrap();
+
+        let regions = Level::read_litematic_regions(McVersion::Release1_13, data.clone()).unwrap();
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions["house"].get_finite_bounds(), region_bounds(4, 4, 4));
+        assert_eq!(regions["tower"].get_finite_bounds(), region_bounds(2, 8, 2));
+        assert_eq!(regions["house"].get_block(1, 0, 1), Block::Stone);
+        assert_eq!(regions["house"].get_block(2, 3, 2), Block::OrangeWool);
+        assert_eq!(regions["house"].get_block(3, 1, 0), Block::Dirt);
+        assert_eq!(regions["house"].get_block(0, 2, 3), Block::Glass);
+        assert_eq!(regions["tower"].get_block(0, 7, 0), Block::GoldBlock);
+
+        let level = Level::read_litematic(McVersion::Release1_13, data).unwrap();
+        assert_eq!(level.get_finite_bounds(), region_bounds(22, 8, 4));
+        assert_eq!(level.get_block(1, 0, 1), Block::Stone);
+        assert_eq!(level.get_block(20, 7, 0), Block::GoldBlock);
+    }
+}
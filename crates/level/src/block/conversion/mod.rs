@@ -1,6 +1,8 @@
 use crate::block::{Block, BlockId};
 use lodestone_common::util::McVersion;
-use crate::block::registry::BLOCK_REGISTRY;
+use crate::block::registry::{get_id_in_version, BLOCK_REGISTRY};
+use crate::level::{metadata, Level};
+use std::collections::BTreeMap;
 
 /// Adds a block to the block registry
 /// When one wants to convert a block id to the internal id, they will provide an id and a version to the converter method
@@ -39,15 +41,29 @@ pub fn get_version_block_id(v: McVersion, id: &Block) -> BlockId {
     BLOCK_REGISTRY
         .blocks
         .get(id)
-        .and_then(|ids| {
-            ids.range(..=v)
-                .rev()
-                .next()
-                .map(|(_, blk_id)| blk_id.clone())
-        })
+        .and_then(|ids| get_id_in_version(ids, v).cloned())
         .unwrap_or(BlockId::default())
 }
 
+/// Gets the internal block from a flattened block name (e.g. `minecraft:stone`)
+#[inline(always)]
+pub fn get_internal_block_id_by_name(v: McVersion, name: &str) -> Option<Block> {
+    BLOCK_REGISTRY
+        .names
+        .get(&v)
+        .and_then(|blocks| blocks.get(name))
+        .copied()
+}
+
+/// Gets the flattened block name (e.g. `minecraft:stone`) of a block, if it has one in the given version
+#[inline(always)]
+pub fn get_version_block_name(v: McVersion, id: &Block) -> Option<&'static str> {
+    match get_version_block_id(v, id) {
+        BlockId::Flattened(name) | BlockId::NumericAndFlattened(_, name) => Some(name),
+        _ => None,
+    }
+}
+
 #[inline(always)]
 pub fn convert_blocks_to_internal_format(
     version: McVersion,
@@ -67,3 +83,95 @@ pub fn convert_blocks_from_internal_format(
         .map(|v| get_version_block_id(version, v))
         .collect()
 }
+
+/// Splits a block state string (e.g. `minecraft:oak_stairs[facing=north,half=top]`) into its name and properties
+pub fn parse_block_state(state: &str) -> (&str, BTreeMap<String, String>) {
+    let Some((name, properties)) = state.split_once('[') else {
+        return (state, BTreeMap::new());
+    };
+
+    let properties = properties
+        .trim_end_matches(']')
+        .split(',')
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect();
+
+    (name, properties)
+}
+
+/// Joins a block name and its properties back into a block state string
+pub fn format_block_state(name: &str, properties: &BTreeMap<String, String>) -> String {
+    if properties.is_empty() {
+        return name.to_string();
+    }
+
+    let properties: Vec<String> = properties
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect();
+
+    format!("{}[{}]", name, properties.join(","))
+}
+
+/// Sets a block by its flattened name, blocks we don't know about are kept as `Block::Custom`
+pub fn set_named_block(
+    level: &mut Level,
+    version: McVersion,
+    x: i32,
+    y: i16,
+    z: i32,
+    name: &str,
+    properties: &BTreeMap<String, String>,
+) {
+    match get_internal_block_id_by_name(version, name) {
+        Some(Block::Air) => return,
+        Some(blk) => level.set_block(x, y, z, blk),
+        None => {
+            level.set_block(x, y, z, Block::Custom);
+            level.set_data(
+                x,
+                y,
+                z,
+                metadata::CUSTOM_BLOCK_NAME.to_string(),
+                name.to_string(),
+            );
+        }
+    }
+
+    for (k, v) in properties {
+        level.set_data(x, y, z, k.clone(), v.clone());
+    }
+}
+
+/// Gets the flattened name and properties of a block
+pub fn get_named_block(
+    level: &Level,
+    version: McVersion,
+    x: i32,
+    y: i16,
+    z: i32,
+) -> (String, BTreeMap<String, String>) {
+    let blk = level.get_block(x, y, z);
+    let mut properties = level.get_states(x, y, z).cloned().unwrap_or_default();
+
+    let name = match blk {
+        Block::Custom => properties.get(metadata::CUSTOM_BLOCK_NAME).cloned(),
+        blk => get_version_block_name(version, &blk)
+            // flowing liquids don't have their own block in Java since the flattening
+            .or_else(|| match blk {
+                Block::FlowingWater => get_version_block_name(version, &Block::Water),
+                Block::FlowingLava => get_version_block_name(version, &Block::Lava),
+                _ => None,
+            })
+            .map(str::to_string),
+    };
+
+    properties.remove(metadata::CUSTOM_BLOCK_ID);
+    properties.remove(metadata::CUSTOM_BLOCK_NAME);
+
+    match name {
+        Some(name) => (name, properties),
+        None => ("minecraft:air".to_string(), BTreeMap::new()),
+    }
+}
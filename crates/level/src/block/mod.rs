@@ -6,6 +6,7 @@ use crate::add_block_conv;
 use crate::block::BlockId::{Flattened, Numeric, NumericAndFlattened, NumericWithData};
 use lodestone_common::util::McVersion;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -46,10 +47,12 @@ pub struct BlockRegistry {
     pub blocks: HashMap<Block, BTreeMap<McVersion, BlockId>>,
     /// List of versions and every block inside each version
     pub versions: HashMap<McVersion, HashMap<BlockId, Block>>,
+    /// List of versions and every block inside each version by its flattened name (e.g. `minecraft:stone`)
+    pub names: HashMap<McVersion, HashMap<&'static str, Block>>,
 }
 
 /// Internal Block IDs
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(u16)]
 pub enum Block {
     Air = 0,
@@ -103,4 +106,88 @@ pub enum Block {
     Bookshelf = 47,
     MossyCobblestone = 48,
     Obsidian = 49,
+    // CPE CustomBlocks, only exist on classic servers that support the extension
+    CobblestoneSlab = 50,
+    Rope = 51,
+    Sandstone = 52,
+    Snow = 53, // thin layer of snow
+    Fire = 54,
+    LightPinkWool = 55,
+    ForestGreenWool = 56,
+    BrownWool = 57,
+    DeepBlueWool = 58,
+    TurquoiseWool = 59,
+    Ice = 60,
+    CeramicTile = 61,
+    Magma = 62,
+    Pillar = 63,
+    Crate = 64,
+    StoneBrick = 65,
+    // Pocket Edition only
+    NetherReactorCore = 66,
+    ActiveNetherReactorCore = 67, // data value 1, while the reactor is running
+    FinishedNetherReactorCore = 68, // data value 2, after the reactor is done
+    GlowingObsidian = 69,
+    Stonecutter = 70,
+    InfoUpdate = 71, // "update!" block
+    InfoUpdate2 = 72, // "ate!upd" block
+    // Server defined (MCGalaxy/CPE block definitions) or modded blocks which have no internal equivalent.
+    // The numeric ID is kept in the block's state under metadata::CUSTOM_BLOCK_ID, the name under metadata::CUSTOM_BLOCK_NAME
+    Custom = 0xFFFF,
+}
+
+impl Block {
+    /// Gets the closest block that exists in vanilla Classic 0.30
+    ///
+    /// Used when writing to formats that can't hold CPE CustomBlocks (e.g. .mine), any other block is returned as-is.
+    pub fn get_classic_fallback(&self) -> Block {
+        match self {
+            Block::CobblestoneSlab => Block::SmoothStoneSlab,
+            Block::Rope => Block::BrownMushroom,
+            Block::Sandstone => Block::Sand,
+            Block::Snow => Block::Air,
+            Block::Fire => Block::FlowingLava,
+            Block::LightPinkWool => Block::PinkWool,
+            Block::ForestGreenWool => Block::GreenWool,
+            Block::BrownWool => Block::Dirt,
+            Block::DeepBlueWool => Block::BlueWool,
+            Block::TurquoiseWool => Block::CyanWool,
+            Block::Ice => Block::Glass,
+            Block::CeramicTile => Block::IronBlock,
+            Block::Magma => Block::Obsidian,
+            Block::Pillar => Block::WhiteWool,
+            Block::Crate => Block::OakPlanks,
+            Block::StoneBrick => Block::Stone,
+            Block::NetherReactorCore
+            | Block::ActiveNetherReactorCore
+            | Block::FinishedNetherReactorCore => Block::IronBlock,
+            Block::GlowingObsidian => Block::Obsidian,
+            Block::Stonecutter => Block::Cobblestone,
+            Block::InfoUpdate | Block::InfoUpdate2 => Block::Air,
+            // server defined, we don't know what it looks like
+            Block::Custom => Block::Air,
+            b => *b,
+        }
+    }
+
+    /// Gets how much light the block takes away when light goes through it, from 0 (air) to 15 (fully opaque)
+    ///
+    /// Same as Java Edition, e.g. plants and glass let all of it through, leaves take 1 and water takes 3.
+    pub fn get_light_opacity(&self) -> u8 {
+        match self {
+            Block::Air
+            | Block::Glass
+            | Block::OakSapling
+            | Block::Dandelion
+            | Block::Rose
+            | Block::BrownMushroom
+            | Block::RedMushroom
+            | Block::Rope
+            | Block::Snow
+            | Block::Fire => 0,
+            Block::OakLeaves => 1,
+            Block::FlowingWater | Block::Water | Block::Ice => 3,
+            _ => 15,
+        }
+    }
 }
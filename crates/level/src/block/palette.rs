@@ -1,5 +1,7 @@
-// TODO: material system like actual map
-// then internal block map with Material field.
+use crate::block::Block;
+use crate::level::environment::Rgb;
+
+// Used by Level::generate_bitmap, see MapColor for the colors used by the map renderer
 pub const CLASSIC_PALETTE: [[f32; 3]; 51] = [
     [0.0, 0.0, 0.0],       // Air
     [112.0, 112.0, 112.0], // Stone
@@ -53,3 +55,280 @@ pub const CLASSIC_PALETTE: [[f32; 3]; 51] = [
     [25.0, 25.0, 25.0],    // Obsidian
     [0.0, 0.0, 0.0],       // Nonexistent
 ];
+
+/// Brightness of each of the 4 shades of a map color, the shade is the lowest 2 bits of a map color ID
+pub const MAP_SHADES: [u32; 4] = [180, 220, 255, 135];
+
+/// Map colors of materials, the same ones as in-game maps
+///
+/// The discriminant is the base color ID used by map items, 0 is transparent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum MapColor {
+    Grass = 1,
+    Sand = 2,
+    Wool = 3,
+    Fire = 4,
+    Ice = 5,
+    Metal = 6,
+    Plant = 7,
+    Snow = 8,
+    Clay = 9,
+    Dirt = 10,
+    Stone = 11,
+    Water = 12,
+    Wood = 13,
+    Quartz = 14,
+    Orange = 15,
+    Magenta = 16,
+    LightBlue = 17,
+    Yellow = 18,
+    Lime = 19,
+    Pink = 20,
+    Gray = 21,
+    LightGray = 22,
+    Cyan = 23,
+    Purple = 24,
+    Blue = 25,
+    Brown = 26,
+    Green = 27,
+    Red = 28,
+    Black = 29,
+    Gold = 30,
+    Diamond = 31,
+    Lapis = 32,
+    Emerald = 33,
+    Podzol = 34,
+    Nether = 35,
+    WhiteTerracotta = 36,
+    OrangeTerracotta = 37,
+    MagentaTerracotta = 38,
+    LightBlueTerracotta = 39,
+    YellowTerracotta = 40,
+    LimeTerracotta = 41,
+    PinkTerracotta = 42,
+    GrayTerracotta = 43,
+    LightGrayTerracotta = 44,
+    CyanTerracotta = 45,
+    PurpleTerracotta = 46,
+    BlueTerracotta = 47,
+    BrownTerracotta = 48,
+    GreenTerracotta = 49,
+    RedTerracotta = 50,
+    BlackTerracotta = 51,
+    CrimsonNylium = 52,
+    CrimsonStem = 53,
+    CrimsonHyphae = 54,
+    WarpedNylium = 55,
+    WarpedStem = 56,
+    WarpedHyphae = 57,
+    WarpedWartBlock = 58,
+    Deepslate = 59,
+    RawIron = 60,
+    GlowLichen = 61,
+}
+
+const MAP_COLORS: [MapColor; 61] = [
+    MapColor::Grass,
+    MapColor::Sand,
+    MapColor::Wool,
+    MapColor::Fire,
+    MapColor::Ice,
+    MapColor::Metal,
+    MapColor::Plant,
+    MapColor::Snow,
+    MapColor::Clay,
+    MapColor::Dirt,
+    MapColor::Stone,
+    MapColor::Water,
+    MapColor::Wood,
+    MapColor::Quartz,
+    MapColor::Orange,
+    MapColor::Magenta,
+    MapColor::LightBlue,
+    MapColor::Yellow,
+    MapColor::Lime,
+    MapColor::Pink,
+    MapColor::Gray,
+    MapColor::LightGray,
+    MapColor::Cyan,
+    MapColor::Purple,
+    MapColor::Blue,
+    MapColor::Brown,
+    MapColor::Green,
+    MapColor::Red,
+    MapColor::Black,
+    MapColor::Gold,
+    MapColor::Diamond,
+    MapColor::Lapis,
+    MapColor::Emerald,
+    MapColor::Podzol,
+    MapColor::Nether,
+    MapColor::WhiteTerracotta,
+    MapColor::OrangeTerracotta,
+    MapColor::MagentaTerracotta,
+    MapColor::LightBlueTerracotta,
+    MapColor::YellowTerracotta,
+    MapColor::LimeTerracotta,
+    MapColor::PinkTerracotta,
+    MapColor::GrayTerracotta,
+    MapColor::LightGrayTerracotta,
+    MapColor::CyanTerracotta,
+    MapColor::PurpleTerracotta,
+    MapColor::BlueTerracotta,
+    MapColor::BrownTerracotta,
+    MapColor::GreenTerracotta,
+    MapColor::RedTerracotta,
+    MapColor::BlackTerracotta,
+    MapColor::CrimsonNylium,
+    MapColor::CrimsonStem,
+    MapColor::CrimsonHyphae,
+    MapColor::WarpedNylium,
+    MapColor::WarpedStem,
+    MapColor::WarpedHyphae,
+    MapColor::WarpedWartBlock,
+    MapColor::Deepslate,
+    MapColor::RawIron,
+    MapColor::GlowLichen,
+];
+
+impl MapColor {
+    /// Gets a base color from its ID
+    pub fn from_id(id: u8) -> Option<MapColor> {
+        MAP_COLORS.get((id as usize).checked_sub(1)?).copied()
+    }
+
+    pub fn get_id(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn get_rgb(&self) -> Rgb {
+        match self {
+            MapColor::Grass => Rgb::new(127, 178, 56),
+            MapColor::Sand => Rgb::new(247, 233, 163),
+            MapColor::Wool => Rgb::new(199, 199, 199),
+            MapColor::Fire => Rgb::new(255, 0, 0),
+            MapColor::Ice => Rgb::new(160, 160, 255),
+            MapColor::Metal => Rgb::new(167, 167, 167),
+            MapColor::Plant => Rgb::new(0, 124, 0),
+            MapColor::Snow => Rgb::new(255, 255, 255),
+            MapColor::Clay => Rgb::new(164, 168, 184),
+            MapColor::Dirt => Rgb::new(151, 109, 77),
+            MapColor::Stone => Rgb::new(112, 112, 112),
+            MapColor::Water => Rgb::new(64, 64, 255),
+            MapColor::Wood => Rgb::new(143, 119, 72),
+            MapColor::Quartz => Rgb::new(255, 252, 245),
+            MapColor::Orange => Rgb::new(216, 127, 51),
+            MapColor::Magenta => Rgb::new(178, 76, 216),
+            MapColor::LightBlue => Rgb::new(102, 153, 216),
+            MapColor::Yellow => Rgb::new(229, 229, 51),
+            MapColor::Lime => Rgb::new(127, 204, 25),
+            MapColor::Pink => Rgb::new(242, 127, 165),
+            MapColor::Gray => Rgb::new(76, 76, 76),
+            MapColor::LightGray => Rgb::new(153, 153, 153),
+            MapColor::Cyan => Rgb::new(76, 127, 153),
+            MapColor::Purple => Rgb::new(127, 63, 178),
+            MapColor::Blue => Rgb::new(51, 76, 178),
+            MapColor::Brown => Rgb::new(102, 76, 51),
+            MapColor::Green => Rgb::new(102, 127, 51),
+            MapColor::Red => Rgb::new(153, 51, 51),
+            MapColor::Black => Rgb::new(25, 25, 25),
+            MapColor::Gold => Rgb::new(250, 238, 77),
+            MapColor::Diamond => Rgb::new(92, 219, 213),
+            MapColor::Lapis => Rgb::new(74, 128, 255),
+            MapColor::Emerald => Rgb::new(0, 217, 58),
+            MapColor::Podzol => Rgb::new(129, 86, 49),
+            MapColor::Nether => Rgb::new(112, 2, 0),
+            MapColor::WhiteTerracotta => Rgb::new(209, 177, 161),
+            MapColor::OrangeTerracotta => Rgb::new(159, 82, 36),
+            MapColor::MagentaTerracotta => Rgb::new(149, 87, 108),
+            MapColor::LightBlueTerracotta => Rgb::new(112, 108, 138),
+            MapColor::YellowTerracotta => Rgb::new(186, 133, 36),
+            MapColor::LimeTerracotta => Rgb::new(103, 117, 53),
+            MapColor::PinkTerracotta => Rgb::new(160, 77, 78),
+            MapColor::GrayTerracotta => Rgb::new(57, 41, 35),
+            MapColor::LightGrayTerracotta => Rgb::new(135, 107, 98),
+            MapColor::CyanTerracotta => Rgb::new(87, 92, 92),
+            MapColor::PurpleTerracotta => Rgb::new(122, 73, 88),
+            MapColor::BlueTerracotta => Rgb::new(76, 62, 92),
+            MapColor::BrownTerracotta => Rgb::new(76, 50, 35),
+            MapColor::GreenTerracotta => Rgb::new(76, 82, 42),
+            MapColor::RedTerracotta => Rgb::new(142, 60, 46),
+            MapColor::BlackTerracotta => Rgb::new(37, 22, 16),
+            MapColor::CrimsonNylium => Rgb::new(189, 48, 49),
+            MapColor::CrimsonStem => Rgb::new(148, 63, 97),
+            MapColor::CrimsonHyphae => Rgb::new(92, 25, 29),
+            MapColor::WarpedNylium => Rgb::new(22, 126, 134),
+            MapColor::WarpedStem => Rgb::new(58, 142, 140),
+            MapColor::WarpedHyphae => Rgb::new(86, 44, 62),
+            MapColor::WarpedWartBlock => Rgb::new(20, 180, 133),
+            MapColor::Deepslate => Rgb::new(100, 100, 100),
+            MapColor::RawIron => Rgb::new(216, 175, 147),
+            MapColor::GlowLichen => Rgb::new(127, 167, 150),
+        }
+    }
+
+    /// Gets the color with one of the [`MAP_SHADES`] applied
+    pub fn get_shaded_rgb(&self, shade: u8) -> Rgb {
+        let rgb = self.get_rgb();
+        let s = MAP_SHADES[shade as usize & 3];
+        Rgb::new(
+            (rgb.r as u32 * s / 255) as u8,
+            (rgb.g as u32 * s / 255) as u8,
+            (rgb.b as u32 * s / 255) as u8,
+        )
+    }
+}
+
+impl Block {
+    /// Gets the color the block shows up as on a map
+    ///
+    /// Blocks you can see through (air, glass) have none, so whatever is under them gets drawn instead.
+    pub fn get_map_color(&self) -> Option<MapColor> {
+        Some(match self {
+            Block::Air | Block::Glass | Block::InfoUpdate | Block::InfoUpdate2 => return None,
+            Block::Grass => MapColor::Grass,
+            Block::Dirt => MapColor::Dirt,
+            Block::Sand | Block::Sandstone => MapColor::Sand,
+            Block::FlowingWater | Block::Water => MapColor::Water,
+            Block::FlowingLava | Block::Lava | Block::Tnt | Block::Fire => MapColor::Fire,
+            Block::OakPlanks | Block::OakLog | Block::Bookshelf | Block::Crate | Block::Rope => {
+                MapColor::Wood
+            }
+            Block::OakSapling
+            | Block::OakLeaves
+            | Block::Dandelion
+            | Block::Rose
+            | Block::BrownMushroom
+            | Block::RedMushroom => MapColor::Plant,
+            Block::Sponge | Block::YellowWool => MapColor::Yellow,
+            Block::RedWool => MapColor::Red,
+            Block::OrangeWool => MapColor::Orange,
+            Block::LimeWool | Block::SpringGreenWool => MapColor::Lime,
+            Block::GreenWool | Block::ForestGreenWool => MapColor::Green,
+            Block::LightBlueWool | Block::TurquoiseWool => MapColor::LightBlue,
+            Block::CyanWool => MapColor::Cyan,
+            Block::BlueWool | Block::DeepBlueWool => MapColor::Blue,
+            Block::PurpleWool | Block::VioletWool => MapColor::Purple,
+            Block::MagentaWool => MapColor::Magenta,
+            Block::PinkWool | Block::LightPinkWool => MapColor::Pink,
+            Block::DarkGrayWool => MapColor::Gray,
+            Block::GrayWool => MapColor::LightGray,
+            Block::WhiteWool => MapColor::Wool,
+            Block::BrownWool => MapColor::Brown,
+            Block::GoldBlock => MapColor::Gold,
+            Block::IronBlock
+            | Block::NetherReactorCore
+            | Block::ActiveNetherReactorCore
+            | Block::FinishedNetherReactorCore => MapColor::Metal,
+            Block::Bricks => MapColor::Red,
+            Block::Obsidian => MapColor::Black,
+            Block::Snow => MapColor::Snow,
+            Block::Ice => MapColor::Ice,
+            Block::CeramicTile | Block::Pillar => MapColor::Quartz,
+            Block::Magma | Block::GlowingObsidian => MapColor::Nether,
+            // stone, ores, slabs and anything we don't know the look of
+            _ => MapColor::Stone,
+        })
+    }
+}
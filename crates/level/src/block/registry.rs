@@ -4,204 +4,582 @@ use std::collections::HashMap;
 use once_cell::sync::Lazy;
 use lodestone_common::util::McVersion;
 use crate::add_block_conv;
-use crate::block::BlockId::{Numeric, NumericAndFlattened};
-use crate::block::{Block, BlockRegistry};
+use crate::block::BlockId::{Flattened, Numeric, NumericAndFlattened, NumericWithData};
+use crate::block::{Block, BlockId, BlockRegistry};
+
+/// Gets the ID a block has in a version, from the newest entry at or before that version in the same edition
+///
+/// Pocket Edition versions sort after every Java version, so they skip the Java 1.13+ entries. PE blocks without a PE
+/// entry use their pre-flattening Java ID instead, which PE 0.x shares.
+pub fn get_id_in_version(ids: &BTreeMap<McVersion, BlockId>, v: McVersion) -> Option<&BlockId> {
+    if v < McVersion::MCPEAlpha0_1_0 {
+        return ids.range(..=v).next_back().map(|(_, id)| id);
+    }
+
+    ids.range(McVersion::MCPEAlpha0_1_0..=v)
+        .next_back()
+        .or_else(|| ids.range(..McVersion::Release1_13).next_back())
+        .map(|(_, id)| id)
+}
 
 pub static BLOCK_REGISTRY: Lazy<BlockRegistry> = Lazy::new(|| {
     let mut reg = BlockRegistry {
         blocks: HashMap::new(),
         versions: HashMap::new(),
+        names: HashMap::new(),
     };
 
     // TODO: rock is stored using same id as air, do we want to change air ID?
     add_block_conv!(reg, Block::Air, [
         McVersion::PreClassic20090515: Numeric(0u16),
-        McVersion::Release1_13: NumericAndFlattened(0u16, "minecraft:air")
+        McVersion::Release1_13: NumericAndFlattened(0u16, "minecraft:air"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:air")
     ]);
 
     add_block_conv!(reg, Block::Stone, [
         McVersion::PreClassic132211: Numeric(0u16),
         McVersion::PreClassic20090515: Numeric(1u16),
-        McVersion::Release1_13: NumericAndFlattened(1u16, "minecraft:stone")
+        McVersion::Release1_13: NumericAndFlattened(1u16, "minecraft:stone"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:stone[stone_type=stone]")
     ]);
 
     add_block_conv!(reg, Block::Grass, [
         McVersion::PreClassic20090515: Numeric(2u16),
-        McVersion::Release1_13: NumericAndFlattened(2u16, "minecraft:grass_block")
+        McVersion::Release1_13: NumericAndFlattened(2u16, "minecraft:grass_block"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:grass")
     ]);
 
     add_block_conv!(reg, Block::Dirt, [
         McVersion::PreClassic20090515: Numeric(3u16),
-        McVersion::Release1_13: NumericAndFlattened(3u16, "minecraft:dirt")
+        McVersion::Release1_13: NumericAndFlattened(3u16, "minecraft:dirt"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:dirt[dirt_type=normal]")
     ]);
 
     add_block_conv!(reg, Block::Cobblestone, [
         McVersion::PreClassic20090515: Numeric(4u16),
-        McVersion::Release1_13: NumericAndFlattened(4u16, "minecraft:cobblestone")
+        McVersion::Release1_13: NumericAndFlattened(4u16, "minecraft:cobblestone"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:cobblestone")
     ]);
 
     add_block_conv!(reg, Block::OakPlanks, [
         McVersion::PreClassic20090515: Numeric(5u16),
+        McVersion::Release1_13: NumericAndFlattened(5u16, "minecraft:oak_planks"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:planks[wood_type=oak]")
     ]);
 
     add_block_conv!(reg, Block::OakSapling, [
        McVersion::PreClassic161348: Numeric(6u16),
+       McVersion::Release1_13: NumericAndFlattened(6u16, "minecraft:oak_sapling"),
+       McVersion::Bedrock1_18_10: Flattened("minecraft:sapling[age_bit=false,sapling_type=oak]")
     ]);
 
     add_block_conv!(reg, Block::Bedrock, [
        McVersion::Classic0_0_12a: Numeric(7u16),
+       McVersion::Release1_13: NumericAndFlattened(7u16, "minecraft:bedrock"),
+       McVersion::Bedrock1_18_10: Flattened("minecraft:bedrock[infiniburn_bit=false]")
     ]);
 
     add_block_conv!(reg, Block::FlowingWater, [
         McVersion::Classic0_0_12a: Numeric(8u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:flowing_water[liquid_depth=0]")
     ]);
 
     add_block_conv!(reg, Block::Water, [
         McVersion::Classic0_0_12a: Numeric(9u16),
+        McVersion::Release1_13: NumericAndFlattened(9u16, "minecraft:water"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:water[liquid_depth=0]")
     ]);
 
     add_block_conv!(reg, Block::FlowingLava, [
         McVersion::Classic0_0_12a: Numeric(10u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:flowing_lava[liquid_depth=0]")
     ]);
 
     add_block_conv!(reg, Block::Lava, [
         McVersion::Classic0_0_12a: Numeric(11u16),
+        McVersion::Release1_13: NumericAndFlattened(11u16, "minecraft:lava"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:lava[liquid_depth=0]")
     ]);
 
     add_block_conv!(reg, Block::Sand, [
         McVersion::Classic0_0_14a: Numeric(12u16),
+        McVersion::Release1_13: NumericAndFlattened(12u16, "minecraft:sand"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:sand[sand_type=normal]")
     ]);
 
     add_block_conv!(reg, Block::Gravel, [
         McVersion::Classic0_0_14a: Numeric(13u16),
+        McVersion::Release1_13: NumericAndFlattened(13u16, "minecraft:gravel"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:gravel")
     ]);
 
     add_block_conv!(reg, Block::GoldOre, [
        McVersion::Classic0_0_14a: Numeric(14u16),
+       McVersion::Release1_13: NumericAndFlattened(14u16, "minecraft:gold_ore"),
+       McVersion::Bedrock1_18_10: Flattened("minecraft:gold_ore")
     ]);
 
     add_block_conv!(reg, Block::IronOre, [
         McVersion::Classic0_0_14a: Numeric(15u16),
+        McVersion::Release1_13: NumericAndFlattened(15u16, "minecraft:iron_ore"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:iron_ore")
     ]);
 
     add_block_conv!(reg, Block::CoalOre, [
         McVersion::Classic0_0_14a: Numeric(16u16),
+        McVersion::Release1_13: NumericAndFlattened(16u16, "minecraft:coal_ore"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:coal_ore")
     ]);
 
     add_block_conv!(reg, Block::OakLog, [
         McVersion::Classic0_0_14a: Numeric(17u16),
+        McVersion::Release1_13: NumericAndFlattened(17u16, "minecraft:oak_log"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:log[old_log_type=oak,pillar_axis=y]")
     ]);
 
     add_block_conv!(reg, Block::OakLeaves, [
         McVersion::Classic0_0_14a: Numeric(18u16),
+        McVersion::Release1_13: NumericAndFlattened(18u16, "minecraft:oak_leaves"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:leaves[old_leaf_type=oak,persistent_bit=false,update_bit=false]")
     ]);
 
     add_block_conv!(reg, Block::Sponge, [
         McVersion::Classic0_0_19a: Numeric(19u16),
+        McVersion::Release1_13: NumericAndFlattened(19u16, "minecraft:sponge"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:sponge[sponge_type=dry]")
     ]);
 
     add_block_conv!(reg, Block::Glass, [
         McVersion::Classic0_0_19a: Numeric(20u16),
+        McVersion::Release1_13: NumericAndFlattened(20u16, "minecraft:glass"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:glass")
     ]);
 
     add_block_conv!(reg, Block::RedWool, [
-        McVersion::Classic0_0_20a: Numeric(21u16)
+        McVersion::Classic0_0_20a: Numeric(21u16),
+        McVersion::Release1_1: NumericWithData(35u16, 14u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:red_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 14u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=red]")
     ]);
 
     add_block_conv!(reg, Block::OrangeWool, [
         McVersion::Classic0_0_20a: Numeric(22u16),
+        McVersion::Release1_1: NumericWithData(35u16, 1u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:orange_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 1u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=orange]")
     ]);
 
     add_block_conv!(reg, Block::YellowWool, [
         McVersion::Classic0_0_20a: Numeric(23u16),
+        McVersion::Release1_1: NumericWithData(35u16, 4u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:yellow_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 4u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=yellow]")
     ]);
 
     add_block_conv!(reg, Block::LimeWool, [
         McVersion::Classic0_0_20a: Numeric(24u16),
+        McVersion::Release1_1: NumericWithData(35u16, 5u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:lime_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 5u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=lime]")
     ]);
 
     add_block_conv!(reg, Block::GreenWool, [
         McVersion::Classic0_0_20a: Numeric(25u16),
+        McVersion::Release1_1: NumericWithData(35u16, 13u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:green_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 13u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=green]")
     ]);
 
     add_block_conv!(reg, Block::SpringGreenWool, [
         McVersion::Classic0_0_20a: Numeric(26u16),
+        McVersion::Release1_1: NumericWithData(35u16, 5u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:lime_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 5u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=lime]")
     ]);
 
     // Cyan in Classic
     add_block_conv!(reg, Block::LightBlueWool, [
         McVersion::Classic0_0_20a: Numeric(27u16),
+        McVersion::Release1_1: NumericWithData(35u16, 3u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:light_blue_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 3u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=light_blue]")
     ]);
 
     // Capri in Classic
     add_block_conv!(reg, Block::CyanWool, [
         McVersion::Classic0_0_20a: Numeric(28u16),
+        McVersion::Release1_1: NumericWithData(35u16, 9u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:cyan_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 9u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=cyan]")
     ]);
 
     add_block_conv!(reg, Block::BlueWool, [
         McVersion::Classic0_0_20a: Numeric(29u16),
+        McVersion::Release1_1: NumericWithData(35u16, 11u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:blue_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 11u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=blue]")
     ]);
 
     add_block_conv!(reg, Block::VioletWool, [
         McVersion::Classic0_0_20a: Numeric(30u16),
+        McVersion::Release1_1: NumericWithData(35u16, 10u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:purple_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 10u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=purple]")
     ]);
 
     add_block_conv!(reg, Block::PurpleWool, [
         McVersion::Classic0_0_20a: Numeric(31u16),
+        McVersion::Release1_1: NumericWithData(35u16, 10u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:purple_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 10u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=purple]")
     ]);
 
     add_block_conv!(reg, Block::MagentaWool, [
         McVersion::Classic0_0_20a: Numeric(32u16),
+        McVersion::Release1_1: NumericWithData(35u16, 2u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:magenta_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 2u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=magenta]")
     ]);
 
     add_block_conv!(reg, Block::PinkWool, [
         McVersion::Classic0_0_20a: Numeric(33u16),
+        McVersion::Release1_1: NumericWithData(35u16, 6u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:pink_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 6u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=pink]")
     ]);
 
     add_block_conv!(reg, Block::DarkGrayWool, [
         McVersion::Classic0_0_20a: Numeric(34u16),
+        McVersion::Release1_1: NumericWithData(35u16, 7u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:gray_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 7u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=gray]")
     ]);
 
     add_block_conv!(reg, Block::GrayWool, [
         McVersion::Classic0_0_20a: Numeric(35u16),
+        McVersion::Release1_1: NumericWithData(35u16, 8u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:light_gray_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 8u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=silver]")
     ]);
 
     add_block_conv!(reg, Block::WhiteWool, [
         McVersion::Classic0_0_20a: Numeric(36u16),
+        McVersion::Release1_1: NumericWithData(35u16, 0u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:white_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 0u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=white]")
     ]);
 
     add_block_conv!(reg, Block::Dandelion, [
         McVersion::Classic0_0_20a: Numeric(37u16),
+        McVersion::Release1_13: NumericAndFlattened(37u16, "minecraft:dandelion"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:yellow_flower")
     ]);
 
-    add_block_conv!(reg, Block::RedMushroom, [
+    add_block_conv!(reg, Block::Rose, [
         McVersion::Classic0_0_20a: Numeric(38u16),
+        McVersion::Release1_13: NumericAndFlattened(38u16, "minecraft:poppy"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:red_flower[flower_type=poppy]")
     ]);
 
-    add_block_conv!(reg, Block::Rose, [
+    add_block_conv!(reg, Block::BrownMushroom, [
         McVersion::Classic0_0_20a: Numeric(39u16),
+        McVersion::Release1_13: NumericAndFlattened(39u16, "minecraft:brown_mushroom"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:brown_mushroom")
     ]);
 
-    add_block_conv!(reg, Block::BrownMushroom, [
+    add_block_conv!(reg, Block::RedMushroom, [
         McVersion::Classic0_0_20a: Numeric(40u16),
+        McVersion::Release1_13: NumericAndFlattened(40u16, "minecraft:red_mushroom"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:red_mushroom")
     ]);
 
-    add_block_conv!(reg, Block::RedMushroom, [
+    add_block_conv!(reg, Block::GoldBlock, [
         McVersion::Classic0_0_20a: Numeric(41u16),
+        McVersion::Release1_13: NumericAndFlattened(41u16, "minecraft:gold_block"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:gold_block")
     ]);
 
-    add_block_conv!(reg, Block::GoldBlock, [
+    add_block_conv!(reg, Block::IronBlock, [
         McVersion::Classic0_0_20a: Numeric(42u16),
+        McVersion::Release1_13: NumericAndFlattened(42u16, "minecraft:iron_block"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:iron_block")
+    ]);
+
+    add_block_conv!(reg, Block::DoubleSmoothStoneSlab, [
+        McVersion::Classic0_0_20a: Numeric(43u16),
+        McVersion::Release1_13: NumericAndFlattened(43u16, "minecraft:smooth_stone"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:double_stone_slab[stone_slab_type=smooth_stone,top_slot_bit=false]")
+    ]);
+
+    add_block_conv!(reg, Block::SmoothStoneSlab, [
+        McVersion::Classic0_0_20a: Numeric(44u16),
+        McVersion::Release1_13: NumericAndFlattened(44u16, "minecraft:stone_slab"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:stone_slab[stone_slab_type=smooth_stone,top_slot_bit=false]")
+    ]);
+
+    add_block_conv!(reg, Block::Bricks, [
+        McVersion::Classic0_0_20a: Numeric(45u16),
+        McVersion::Release1_13: NumericAndFlattened(45u16, "minecraft:bricks"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:brick_block")
+    ]);
+
+    add_block_conv!(reg, Block::Tnt, [
+        McVersion::Classic0_0_20a: Numeric(46u16),
+        McVersion::Release1_13: NumericAndFlattened(46u16, "minecraft:tnt"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:tnt[allow_underwater_bit=false,explode_bit=false]")
+    ]);
+
+    add_block_conv!(reg, Block::Bookshelf, [
+        McVersion::Classic0_0_20a: Numeric(47u16),
+        McVersion::Release1_13: NumericAndFlattened(47u16, "minecraft:bookshelf"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:bookshelf")
+    ]);
+
+    add_block_conv!(reg, Block::MossyCobblestone, [
+        McVersion::Classic0_0_20a: Numeric(48u16),
+        McVersion::Release1_13: NumericAndFlattened(48u16, "minecraft:mossy_cobblestone"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:mossy_cobblestone")
+    ]);
+
+    add_block_conv!(reg, Block::Obsidian, [
+        McVersion::Classic0_0_20a: Numeric(49u16),
+        McVersion::Release1_13: NumericAndFlattened(49u16, "minecraft:obsidian"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:obsidian")
+    ]);
+
+    // CPE CustomBlocks
+    // Alpha has no equivalent for most of these, so they use the same block as the classic fallback there.
+    add_block_conv!(reg, Block::CobblestoneSlab, [
+        McVersion::Classic0_30: Numeric(50u16),
+        McVersion::Alpha1_2_6: Numeric(44u16),
+        McVersion::Release1_1: NumericWithData(44u16, 3u16),
+        McVersion::Release1_13: NumericAndFlattened(44u16, "minecraft:cobblestone_slab"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(44u16, 3u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:stone_slab[stone_slab_type=cobblestone,top_slot_bit=false]")
+    ]);
+
+    add_block_conv!(reg, Block::Rope, [
+        McVersion::Classic0_30: Numeric(51u16),
+        McVersion::Alpha1_2_6: Numeric(65u16), // ladder
+        McVersion::Release1_13: NumericAndFlattened(65u16, "minecraft:ladder"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:ladder[facing_direction=0]")
+    ]);
+
+    add_block_conv!(reg, Block::Sandstone, [
+        McVersion::Classic0_30: Numeric(52u16),
+        McVersion::Alpha1_2_6: Numeric(12u16),
+        McVersion::Release1_1: Numeric(24u16),
+        McVersion::Release1_13: NumericAndFlattened(24u16, "minecraft:sandstone"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:sandstone[sand_stone_type=default]")
+    ]);
+
+    add_block_conv!(reg, Block::Snow, [
+        McVersion::Classic0_30: Numeric(53u16),
+        McVersion::Alpha1_2_6: Numeric(78u16),
+        McVersion::Release1_13: NumericAndFlattened(78u16, "minecraft:snow"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:snow_layer[covered_bit=false,height=0]")
+    ]);
+
+    add_block_conv!(reg, Block::Fire, [
+        McVersion::Classic0_30: Numeric(54u16),
+        McVersion::Alpha1_2_6: Numeric(51u16),
+        McVersion::Release1_13: NumericAndFlattened(51u16, "minecraft:fire"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:fire[age=0]")
+    ]);
+
+    add_block_conv!(reg, Block::LightPinkWool, [
+        McVersion::Classic0_30: Numeric(55u16),
+        McVersion::Alpha1_2_6: Numeric(35u16),
+        McVersion::Release1_1: NumericWithData(35u16, 6u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:pink_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 6u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=pink]")
+    ]);
+
+    add_block_conv!(reg, Block::ForestGreenWool, [
+        McVersion::Classic0_30: Numeric(56u16),
+        McVersion::Alpha1_2_6: Numeric(35u16),
+        McVersion::Release1_1: NumericWithData(35u16, 13u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:green_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 13u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=green]")
     ]);
 
-    for (block, ids) in &reg.blocks {
-        for (&version, id) in ids {
-            for v in McVersion::iter().filter(|v| *v >= version) {
-                reg.versions
-                    .entry(v)
-                    .or_default()
-                    .entry(id.clone())
-                    .or_insert(*block);
+    add_block_conv!(reg, Block::BrownWool, [
+        McVersion::Classic0_30: Numeric(57u16),
+        McVersion::Alpha1_2_6: Numeric(35u16),
+        McVersion::Release1_1: NumericWithData(35u16, 12u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:brown_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 12u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=brown]")
+    ]);
+
+    add_block_conv!(reg, Block::DeepBlueWool, [
+        McVersion::Classic0_30: Numeric(58u16),
+        McVersion::Alpha1_2_6: Numeric(35u16),
+        McVersion::Release1_1: NumericWithData(35u16, 11u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:blue_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 11u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=blue]")
+    ]);
+
+    add_block_conv!(reg, Block::TurquoiseWool, [
+        McVersion::Classic0_30: Numeric(59u16),
+        McVersion::Alpha1_2_6: Numeric(35u16),
+        McVersion::Release1_1: NumericWithData(35u16, 9u16),
+        McVersion::Release1_13: NumericAndFlattened(35u16, "minecraft:cyan_wool"),
+        McVersion::MCPEAlpha0_1_0: NumericWithData(35u16, 9u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:wool[color=cyan]")
+    ]);
+
+    add_block_conv!(reg, Block::Ice, [
+        McVersion::Classic0_30: Numeric(60u16),
+        McVersion::Alpha1_2_6: Numeric(79u16),
+        McVersion::Release1_13: NumericAndFlattened(79u16, "minecraft:ice"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:ice")
+    ]);
+
+    add_block_conv!(reg, Block::CeramicTile, [
+        McVersion::Classic0_30: Numeric(61u16),
+        McVersion::Alpha1_2_6: Numeric(42u16),
+        McVersion::Release1_13: NumericAndFlattened(155u16, "minecraft:chiseled_quartz_block"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:quartz_block[chisel_type=chiseled,pillar_axis=y]")
+    ]);
+
+    add_block_conv!(reg, Block::Magma, [
+        McVersion::Classic0_30: Numeric(62u16),
+        McVersion::Alpha1_2_6: Numeric(49u16),
+        McVersion::Release1_13: NumericAndFlattened(213u16, "minecraft:magma_block"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:magma")
+    ]);
+
+    add_block_conv!(reg, Block::Pillar, [
+        McVersion::Classic0_30: Numeric(63u16),
+        McVersion::Alpha1_2_6: Numeric(35u16),
+        McVersion::Release1_13: NumericAndFlattened(155u16, "minecraft:quartz_pillar"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:quartz_block[chisel_type=lines,pillar_axis=y]")
+    ]);
+
+    // No modern equivalent
+    add_block_conv!(reg, Block::Crate, [
+        McVersion::Classic0_30: Numeric(64u16),
+        McVersion::Alpha1_2_6: Numeric(5u16),
+    ]);
+
+    add_block_conv!(reg, Block::StoneBrick, [
+        McVersion::Classic0_30: Numeric(65u16),
+        McVersion::Alpha1_2_6: Numeric(1u16),
+        McVersion::Release1_1: Numeric(98u16),
+        McVersion::Release1_13: NumericAndFlattened(98u16, "minecraft:stone_bricks"),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:stonebrick[stone_brick_type=default]")
+    ]);
+
+    // Pocket Edition only blocks
+    // Older PE versions and Java use the same kind of fallback as the CPE blocks above.
+    add_block_conv!(reg, Block::NetherReactorCore, [
+        McVersion::Alpha1_2_6: Numeric(42u16),
+        McVersion::Release1_13: NumericAndFlattened(42u16, "minecraft:iron_block"),
+        McVersion::MCPEAlpha0_5_0: Numeric(247u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:netherreactor")
+    ]);
+
+    add_block_conv!(reg, Block::ActiveNetherReactorCore, [
+        McVersion::Alpha1_2_6: Numeric(42u16),
+        McVersion::Release1_13: NumericAndFlattened(42u16, "minecraft:iron_block"),
+        McVersion::MCPEAlpha0_5_0: NumericWithData(247u16, 1u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:netherreactor")
+    ]);
+
+    add_block_conv!(reg, Block::FinishedNetherReactorCore, [
+        McVersion::Alpha1_2_6: Numeric(42u16),
+        McVersion::Release1_13: NumericAndFlattened(42u16, "minecraft:iron_block"),
+        McVersion::MCPEAlpha0_5_0: NumericWithData(247u16, 2u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:netherreactor")
+    ]);
+
+    add_block_conv!(reg, Block::GlowingObsidian, [
+        McVersion::Alpha1_2_6: Numeric(49u16),
+        McVersion::Release1_13: NumericAndFlattened(49u16, "minecraft:obsidian"),
+        McVersion::MCPEAlpha0_5_0: Numeric(246u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:glowingobsidian")
+    ]);
+
+    add_block_conv!(reg, Block::Stonecutter, [
+        McVersion::Alpha1_2_6: Numeric(58u16), // crafting table
+        McVersion::Release1_13: NumericAndFlattened(58u16, "minecraft:crafting_table"),
+        McVersion::MCPEAlpha0_3_0: Numeric(245u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:stonecutter")
+    ]);
+
+    // Placeholders the game shows for ids it doesn't know
+    add_block_conv!(reg, Block::InfoUpdate, [
+        McVersion::MCPEAlpha0_1_0: Numeric(248u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:info_update")
+    ]);
+
+    add_block_conv!(reg, Block::InfoUpdate2, [
+        McVersion::MCPEAlpha0_1_0: Numeric(249u16),
+        McVersion::Bedrock1_18_10: Flattened("minecraft:info_update2")
+    ]);
+
+    // Only index the id a block actually has in each version, otherwise ids that were
+    // reused later on (e.g. rd-132211 stone being 0) would collide with the newer block.
+    // Blocks are indexed in order so that when 2 blocks share an id (e.g. CPE blocks falling back to vanilla ones)
+    // the vanilla block is the one that gets read.
+    let mut blocks: Vec<(&Block, &BTreeMap<McVersion, BlockId>)> = reg.blocks.iter().collect();
+    blocks.sort_by_key(|(b, _)| **b as u16);
+
+    let mut data_fallbacks = Vec::new();
+    for (block, ids) in blocks {
+        for v in McVersion::iter() {
+            if let Some(id) = get_id_in_version(ids, v) {
+                let entries = reg.versions.entry(v).or_default();
+                entries.entry(id.clone()).or_insert(*block);
+
+                // Numeric lookups still work in versions that also have a flattened name
+                if let NumericAndFlattened(num, _) = id {
+                    entries.entry(Numeric(*num)).or_insert(*block);
+                }
+                // Formats that don't read data values get the block with data 0 (e.g. 35 is white wool)
+                if let NumericWithData(num, 0) = id {
+                    data_fallbacks.push((v, *num, *block));
+                }
+            }
+        }
+    }
+    // Added last so that they never shadow a block that has the plain numeric ID
+    for (v, num, block) in data_fallbacks {
+        reg.versions.entry(v).or_default().entry(Numeric(num)).or_insert(block);
+    }
+
+    // Flattened names can be looked up without a numeric ID, same rules as above apply
+    for v in McVersion::iter() {
+        let Some(ids) = reg.versions.get(&v) else {
+            continue;
+        };
+
+        let mut blocks: Vec<(&BlockId, &Block)> = ids.iter().collect();
+        blocks.sort_by_key(|(_, b)| **b as u16);
+
+        for (id, block) in blocks {
+            if let NumericAndFlattened(_, name) | Flattened(name) = id {
+                reg.names.entry(v).or_default().entry(*name).or_insert(*block);
             }
         }
     }
@@ -7,7 +7,7 @@ use quartz_nbt::NbtCompound;
 // Should contain everything the modern Tile Entity has
 // if it doesn't, then converted tile entities will be missing data.
 // We can put this in any format we like, as long as it can be converted back, then it's fine.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum BlockEntityType {
     Furnace {
         items: Vec<Item>,
@@ -49,29 +49,29 @@ pub enum BlockEntityType {
     },
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum HasBlockEntity {
     Found(BlockEntityType),
     NotFound(String, NbtCompound),
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct LootTable {
     pub name: String,
     pub seed: i64,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct LockableContainer {
     pub lock: bool,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct RecipesUsed {
     pub id: u16,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Item {
     pub slot: u8,
     pub id: u16,
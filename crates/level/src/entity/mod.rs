@@ -1,2 +1,3 @@
 pub mod block_entity;
 pub mod parsing;
+pub mod stash;
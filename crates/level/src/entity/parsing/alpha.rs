@@ -53,7 +53,11 @@ impl BlockEntityHandler for AlphaBlockEntityParser {
     }
 
     fn write(version: McVersion, block_entity: BlockEntity) -> NbtCompound {
-        let mut nbt = NbtCompound::new();
+        // Block entities we don't parse yet are written back the way they were read
+        let mut nbt = match &block_entity.data {
+            HasBlockEntity::NotFound(_, data) => data.clone(),
+            HasBlockEntity::Found(_) => NbtCompound::new(),
+        };
         nbt.insert("id", &block_entity.id);
         nbt.insert("x", block_entity.x);
         nbt.insert("y", block_entity.y);
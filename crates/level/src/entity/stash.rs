@@ -0,0 +1,79 @@
+use crate::level::metadata;
+use lodestone_common::types::hashmap_ext::{HashMapExt, Value};
+use quartz_nbt::io::{self, Flavor};
+use quartz_nbt::{NbtCompound, NbtList};
+use std::collections::HashMap;
+use std::io::Cursor;
+
+/// The format a list of stashed entities was read from
+///
+/// Every format lays out its entities differently, so a writer only takes the entities of its own format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntityFormat {
+    /// Java chunk entities, with a string `id`
+    Java,
+    /// MCEdit `.schematic` entities
+    Schematic,
+    /// Sponge `.schem` entities, in the version 3 layout (`{Pos, Id, Data}`)
+    Sponge,
+    /// Structure template entities (`{pos, blockPos, nbt}`)
+    Structure,
+    /// Bedrock `.mcstructure` entities
+    McStructure,
+    /// PE Alpha `entities.dat` entities, with a numeric `id`
+    PocketAlpha,
+    /// Bedrock LevelDB chunk entities
+    BedrockChunk,
+}
+
+impl EntityFormat {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            EntityFormat::Java => "Java",
+            EntityFormat::Schematic => "Schematic",
+            EntityFormat::Sponge => "Sponge",
+            EntityFormat::Structure => "Structure",
+            EntityFormat::McStructure => "McStructure",
+            EntityFormat::PocketAlpha => "PocketAlpha",
+            EntityFormat::BedrockChunk => "BedrockChunk",
+        }
+    }
+}
+
+/// Gets the key that stashed entities of a format are stored under inside `custom_data`
+pub fn entity_stash_key(format: EntityFormat) -> String {
+    format!("{}.{}", metadata::RAW_ENTITIES, format.get_name())
+}
+
+/// Keeps entities as-is (as raw uncompressed NBT) until we have a proper entity model
+///
+/// # Params
+///
+/// * `custom_data` - The custom data of the level or chunk the entities are in
+/// * `format` - The format the entities were read from
+/// * `entities` - The entities, nothing is stored if there aren't any
+pub fn stash_entities(
+    custom_data: &mut HashMap<String, Value>,
+    format: EntityFormat,
+    entities: &NbtList,
+) {
+    if entities.is_empty() {
+        return;
+    }
+
+    let mut root = NbtCompound::new();
+    root.insert(metadata::ENTITIES, entities.clone());
+
+    let mut raw = Vec::new();
+    io::write_nbt(&mut raw, None, &root, Flavor::Uncompressed).expect("Stashed entities");
+    custom_data.set_value(entity_stash_key(format), raw);
+}
+
+/// Gets the entities stashed by [`stash_entities`], empty if there are none of the given format
+pub fn get_stashed_entities(custom_data: &HashMap<String, Value>, format: EntityFormat) -> NbtList {
+    custom_data
+        .get_value::<Vec<u8>, _>(entity_stash_key(format))
+        .and_then(|raw| io::read_nbt(&mut Cursor::new(raw), Flavor::Uncompressed).ok())
+        .and_then(|(root, _)| root.get::<_, &NbtList>(metadata::ENTITIES).ok().cloned())
+        .unwrap_or_default()
+}
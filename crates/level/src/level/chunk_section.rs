@@ -163,6 +163,24 @@ impl ChunkSection {
         }
     }
 
+    /// Gets the block the whole section is made of, if it's only made of one
+    ///
+    /// Only looks at the palette, so it doesn't go through the section's blocks.
+    pub fn get_uniform_block(&self) -> Option<Block> {
+        if self.blocks.unique_values() != 1 {
+            return None;
+        }
+
+        self.blocks.iter_palette_entries().next().map(|e| e.value)
+    }
+
+    /// Checks if the section has any of the given blocks, by looking through its palette
+    pub fn contains_any(&self, blocks: &[Block]) -> bool {
+        self.blocks
+            .iter_palette_entries()
+            .any(|e| blocks.contains(&e.value))
+    }
+
     pub fn get_all_blocks_converted(&self, version: McVersion) -> Vec<BlockId> {
         let blocks: Vec<Block> = self
             .blocks
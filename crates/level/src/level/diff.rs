@@ -0,0 +1,317 @@
+use crate::block::Block;
+use crate::level::chunk::{Chunk, CHUNK_LENGTH, CHUNK_WIDTH};
+use crate::level::{Coords, Level};
+use lodestone_common::types::hashmap_ext::Value;
+use lodestone_common::types::vec3i::Vec3i;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// What gets compared between two levels
+#[derive(Debug, Clone, Copy)]
+pub struct DiffOptions {
+    /// Maps blocks before comparing them, e.g. `|b| b.get_classic_fallback()` to ignore blocks a format can't hold
+    pub block_mapping: Option<fn(Block) -> Block>,
+    pub compare_states: bool,
+    pub compare_block_entities: bool,
+    /// Compares the `custom_data` of the level and its chunks
+    pub compare_metadata: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            block_mapping: None,
+            compare_states: true,
+            compare_block_entities: true,
+            compare_metadata: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockChange {
+    pub pos: Vec3i,
+    pub from: Block,
+    pub to: Block,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateChange {
+    pub pos: Vec3i,
+    pub from: Option<BTreeMap<String, String>>,
+    pub to: Option<BTreeMap<String, String>>,
+}
+
+/// A block entity that was added, removed or changed, by its id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockEntityChange {
+    pub pos: Vec3i,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// A `custom_data` key that's missing on one side or has a different value
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataChange {
+    /// `None` for the level's own `custom_data`
+    pub chunk: Option<Coords>,
+    pub key: String,
+    pub from: Option<Value>,
+    pub to: Option<Value>,
+}
+
+/// Which side of a diff to take blocks from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffSide {
+    From,
+    To,
+}
+
+/// Differences between two levels, from the first one to the second one
+#[derive(Debug, Clone, Default)]
+pub struct LevelDiff {
+    pub blocks: Vec<BlockChange>,
+    pub states: Vec<StateChange>,
+    pub block_entities: Vec<BlockEntityChange>,
+    pub metadata: Vec<MetadataChange>,
+
+    /// How many times a block was changed into another one
+    pub block_counts: HashMap<(Block, Block), usize>,
+    /// How many blocks were changed in each chunk
+    pub chunk_counts: HashMap<Coords, usize>,
+}
+
+fn compare_metadata(
+    chunk: Option<Coords>,
+    from: &HashMap<String, Value>,
+    to: &HashMap<String, Value>,
+    out: &mut Vec<MetadataChange>,
+) {
+    let keys: BTreeSet<&String> = from.keys().chain(to.keys()).collect();
+    for key in keys {
+        let (a, b) = (from.get(key), to.get(key));
+        if a != b {
+            out.push(MetadataChange {
+                chunk: chunk.clone(),
+                key: key.clone(),
+                from: a.cloned(),
+                to: b.cloned(),
+            });
+        }
+    }
+}
+
+impl LevelDiff {
+    /// Checks if the levels were the same
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+            && self.states.is_empty()
+            && self.block_entities.is_empty()
+            && self.metadata.is_empty()
+    }
+
+    /// Lists every difference, one per line
+    pub fn to_list(&self) -> Vec<String> {
+        let mut list = Vec::with_capacity(
+            self.blocks.len() + self.states.len() + self.block_entities.len() + self.metadata.len(),
+        );
+
+        for c in &self.blocks {
+            list.push(format!(
+                "block {} {} {}: {:?} -> {:?}",
+                c.pos.x, c.pos.y, c.pos.z, c.from, c.to
+            ));
+        }
+
+        for c in &self.states {
+            list.push(format!(
+                "state {} {} {}: {:?} -> {:?}",
+                c.pos.x, c.pos.y, c.pos.z, c.from, c.to
+            ));
+        }
+
+        for c in &self.block_entities {
+            list.push(format!(
+                "block entity {} {} {}: {:?} -> {:?}",
+                c.pos.x, c.pos.y, c.pos.z, c.from, c.to
+            ));
+        }
+
+        for c in &self.metadata {
+            match &c.chunk {
+                Some(coords) => list.push(format!(
+                    "chunk {} {} data {}: {:?} -> {:?}",
+                    coords.x, coords.z, c.key, c.from, c.to
+                )),
+                None => list.push(format!("level data {}: {:?} -> {:?}", c.key, c.from, c.to)),
+            }
+        }
+
+        list
+    }
+
+    /// Creates a level holding only the changed blocks, taken from one side of the diff
+    ///
+    /// Blocks that are air on that side are left out, use the other side to see what was removed.
+    pub fn to_level(&self, side: DiffSide) -> Level {
+        let mut level = Level::new();
+        let height = self.get_height();
+
+        for c in &self.blocks {
+            let blk = match side {
+                DiffSide::From => c.from,
+                DiffSide::To => c.to,
+            };
+
+            if blk == Block::Air {
+                continue;
+            }
+
+            let coords = Coords {
+                x: c.pos.x.div_euclid(CHUNK_WIDTH as i32),
+                z: c.pos.z.div_euclid(CHUNK_LENGTH as i32),
+            };
+
+            if level.get_chunk(&coords).is_none() {
+                level.add_chunk(coords, Chunk::new(height));
+            }
+
+            level.set_block(c.pos.x, c.pos.y as i16, c.pos.z, blk);
+        }
+
+        level
+    }
+
+    fn get_height(&self) -> i16 {
+        self.blocks
+            .iter()
+            .map(|c| c.pos.y as i16 + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl Level {
+    /// Compares this level to another one chunk by chunk
+    ///
+    /// Chunks that only one of the levels has are compared against air.
+    pub fn diff(&self, other: &Level, options: &DiffOptions) -> LevelDiff {
+        let mut diff = LevelDiff::default();
+        let map = |b: Block| options.block_mapping.map_or(b, |f| f(b));
+
+        let mut coords: Vec<&Coords> = self
+            .get_chunks()
+            .keys()
+            .chain(
+                other
+                    .get_chunks()
+                    .keys()
+                    .filter(|c| self.get_chunk(c).is_none()),
+            )
+            .collect();
+        coords.sort_by_key(|c| (c.x, c.z));
+
+        for coords in coords {
+            let (a, b) = (self.get_chunk(coords), other.get_chunk(coords));
+            let height = a.map_or(0, |c| c.height).max(b.map_or(0, |c| c.height));
+            let empty = Chunk::new(height);
+            let (a, b) = (a.unwrap_or(&empty), b.unwrap_or(&empty));
+
+            let (bx, bz) = (
+                coords.x * CHUNK_WIDTH as i32,
+                coords.z * CHUNK_LENGTH as i32,
+            );
+            let mut changed = 0;
+
+            for y in 0..height {
+                // neither chunk has anything here
+                if a.get_chunk_section(y).is_none() && b.get_chunk_section(y).is_none() {
+                    continue;
+                }
+
+                for z in 0..CHUNK_LENGTH {
+                    for x in 0..CHUNK_WIDTH {
+                        let pos = Vec3i::new(bx + x as i32, y as i32, bz + z as i32);
+                        let (from, to) = (map(a.get_block(x, y, z)), map(b.get_block(x, y, z)));
+
+                        if from != to {
+                            *diff.block_counts.entry((from, to)).or_default() += 1;
+                            changed += 1;
+                            diff.blocks.push(BlockChange {
+                                pos: pos.clone(),
+                                from,
+                                to,
+                            });
+                        }
+
+                        if options.compare_states {
+                            // an empty state is the same as not having one
+                            let from = a.get_state(x, y, z).filter(|s| !s.is_empty());
+                            let to = b.get_state(x, y, z).filter(|s| !s.is_empty());
+
+                            if from != to {
+                                diff.states.push(StateChange {
+                                    pos,
+                                    from: from.cloned(),
+                                    to: to.cloned(),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+
+            if changed != 0 {
+                diff.chunk_counts.insert(coords.clone(), changed);
+            }
+
+            if options.compare_block_entities {
+                let positions: BTreeSet<&Vec3i> = a
+                    .block_entities
+                    .keys()
+                    .chain(b.block_entities.keys())
+                    .collect();
+
+                for pos in positions {
+                    let (from, to) = (a.block_entities.get(pos), b.block_entities.get(pos));
+                    let same = match (from, to) {
+                        (Some(from), Some(to)) => {
+                            from.id == to.id
+                                && from.keep_packed == to.keep_packed
+                                && from.components == to.components
+                                && from.data == to.data
+                        }
+                        _ => false,
+                    };
+
+                    if !same {
+                        diff.block_entities.push(BlockEntityChange {
+                            pos: pos.clone(),
+                            from: from.map(|e| e.id.clone()),
+                            to: to.map(|e| e.id.clone()),
+                        });
+                    }
+                }
+            }
+
+            if options.compare_metadata {
+                compare_metadata(
+                    Some(coords.clone()),
+                    &a.custom_data,
+                    &b.custom_data,
+                    &mut diff.metadata,
+                );
+            }
+        }
+
+        if options.compare_metadata {
+            compare_metadata(
+                None,
+                &self.custom_data,
+                &other.custom_data,
+                &mut diff.metadata,
+            );
+        }
+
+        diff
+    }
+}
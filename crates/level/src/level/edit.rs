@@ -0,0 +1,292 @@
+use crate::block::Block;
+use crate::level::chunk::{Chunk, Light, CHUNK_LENGTH, CHUNK_SECTION_HEIGHT, CHUNK_WIDTH};
+use crate::level::chunk_section::ChunkSection;
+use crate::level::{Bounds, Coords, Level};
+use lodestone_common::types::vec3i::Vec3i;
+use std::collections::BTreeMap;
+
+/// A block along with its state
+///
+/// When placing, the state replaces whatever state was there before. When matching, only the keys in the
+/// pattern are compared, so a pattern without any state matches the block in every state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockPattern {
+    pub block: Block,
+    pub state: BTreeMap<String, String>,
+}
+
+impl BlockPattern {
+    pub fn new(block: Block) -> BlockPattern {
+        BlockPattern {
+            block,
+            state: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_state(mut self, key: &str, value: &str) -> BlockPattern {
+        self.state.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn matches(&self, block: Block, state: &BTreeMap<String, String>) -> bool {
+        block == self.block && self.state.iter().all(|(k, v)| state.get(k) == Some(v))
+    }
+}
+
+impl From<Block> for BlockPattern {
+    fn from(block: Block) -> Self {
+        BlockPattern::new(block)
+    }
+}
+
+/// Which blocks an edit is allowed to change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mask {
+    /// Every block, including air
+    Any,
+    /// Every block that isn't air
+    Existing,
+    /// Blocks matching any of the patterns
+    Blocks(Vec<BlockPattern>),
+    Not(Box<Mask>),
+}
+
+impl Mask {
+    pub fn matches(&self, block: Block, state: &BTreeMap<String, String>) -> bool {
+        match self {
+            Mask::Any => true,
+            Mask::Existing => block != Block::Air,
+            Mask::Blocks(patterns) => patterns.iter().any(|p| p.matches(block, state)),
+            Mask::Not(mask) => !mask.matches(block, state),
+        }
+    }
+}
+
+/// Sky light only goes straight down in here, losing the light opacity of every block it goes through
+fn recalc_sky_light(chunk: &mut Chunk) {
+    let top = chunk
+        .height
+        .min(chunk.chunk_sections.len() as i16 * CHUNK_SECTION_HEIGHT as i16);
+
+    for z in 0..CHUNK_LENGTH {
+        for x in 0..CHUNK_WIDTH {
+            let mut light = 15u8;
+            for y in (0..top).rev() {
+                light = light.saturating_sub(chunk.get_block(x, y, z).get_light_opacity());
+                chunk.set_light(Light::SKY, x, y, z, light);
+            }
+        }
+    }
+}
+
+fn has_light(chunk: &Chunk) -> bool {
+    chunk
+        .chunk_sections
+        .iter()
+        .any(|s| s.sky_light.iter().any(|l| *l != 0))
+}
+
+fn is_on_edge(bounds: &Bounds, pos: &Vec3i, with_floor_and_ceiling: bool) -> bool {
+    let ys = bounds.get_y_range();
+
+    pos.x == bounds.x
+        || pos.x as i64 == bounds.x as i64 + bounds.width as i64 - 1
+        || pos.z == bounds.z
+        || pos.z as i64 == bounds.z as i64 + bounds.length as i64 - 1
+        || (with_floor_and_ceiling && (pos.y == ys.start || pos.y == ys.end - 1))
+}
+
+impl Level {
+    /// Goes through every position of a box inside of the level's chunks, placing whatever `edit` returns there
+    ///
+    /// Positions outside of the level's chunks are skipped, no chunks are created. Writes to the chunk sections
+    /// directly, the heightmap (and sky light, if the chunk has any) of each chunk that changed is recalculated once
+    /// afterwards instead of for every block. Block entities are removed when their block is replaced by another
+    /// block, changing only the state keeps them. Returns how many blocks changed.
+    fn edit_box<F>(&mut self, bounds: Bounds, mut edit: F) -> usize
+    where
+        F: FnMut(&Vec3i, Block, &BTreeMap<String, String>) -> Option<BlockPattern>,
+    {
+        let (w, l) = (CHUNK_WIDTH as i32, CHUNK_LENGTH as i32);
+        let mut coords: Vec<Coords> = self
+            .get_chunks()
+            .keys()
+            .filter(|c| bounds.overlaps_chunk(c))
+            .cloned()
+            .collect();
+        coords.sort_by_key(|c| (c.x, c.z));
+
+        let empty = BTreeMap::new();
+        let mut changed = 0;
+
+        for c in coords {
+            let Some(chunk) = self.get_chunk_mut(&c) else {
+                continue;
+            };
+            let (bx, bz) = (c.x * w, c.z * l);
+            let (xs, zs) = bounds.get_chunk_ranges(&c);
+            let ys = bounds.get_y_range();
+            let mut chunk_changed = 0;
+
+            for y in ys.start.max(0) as i16..ys.end.min(chunk.height as i32) as i16 {
+                let (i, sy) = (
+                    y / CHUNK_SECTION_HEIGHT as i16,
+                    y % CHUNK_SECTION_HEIGHT as i16,
+                );
+
+                for z in zs.clone() {
+                    for x in xs.clone() {
+                        let (x, z) = (x as i8, z as i8);
+                        let pos = Vec3i::new(bx + x as i32, y as i32, bz + z as i32);
+
+                        let (block, new) = match chunk.chunk_sections.get(i as usize) {
+                            Some(s) => {
+                                let block = s.get_block(x, sy, z);
+                                let state = s.get_state(x, sy, z).unwrap_or(&empty);
+                                let new = edit(&pos, block, state)
+                                    .filter(|p| p.block != block || p.state != *state);
+                                (block, new)
+                            }
+                            // no need to create a section just to put air in it
+                            None => (
+                                Block::Air,
+                                edit(&pos, Block::Air, &empty)
+                                    .filter(|p| p.block != Block::Air || !p.state.is_empty()),
+                            ),
+                        };
+                        let Some(new) = new else {
+                            continue;
+                        };
+
+                        let section = chunk.get_or_create_chunk_section_mut(y);
+                        let index = ChunkSection::get_index(x, sy, z);
+                        section.blocks.set(index, &new.block);
+                        section.data.set(index, &new.state);
+
+                        if new.block != block {
+                            chunk.remove_block_entity(pos);
+                        }
+                        chunk_changed += 1;
+                    }
+                }
+            }
+
+            if chunk_changed != 0 {
+                chunk.recalc_heightmap();
+                if has_light(chunk) {
+                    recalc_sky_light(chunk);
+                }
+            }
+            changed += chunk_changed;
+        }
+
+        changed
+    }
+
+    /// Sets every block in a box
+    pub fn set_box(&mut self, bounds: Bounds, pattern: &BlockPattern) -> usize {
+        self.edit_box(bounds, |_, _, _| Some(pattern.clone()))
+    }
+
+    /// Replaces the blocks in a box that match a mask
+    pub fn replace_blocks(&mut self, bounds: Bounds, mask: &Mask, pattern: &BlockPattern) -> usize {
+        self.edit_box(bounds, |_, block, state| {
+            mask.matches(block, state).then(|| pattern.clone())
+        })
+    }
+
+    /// Fills the air in a box, leaving every other block alone
+    pub fn fill_box(&mut self, bounds: Bounds, pattern: &BlockPattern) -> usize {
+        self.edit_box(bounds, |_, block, _| {
+            (block == Block::Air).then(|| pattern.clone())
+        })
+    }
+
+    /// Sets the four sides of a box, without its floor and ceiling
+    pub fn set_walls(&mut self, bounds: Bounds, pattern: &BlockPattern) -> usize {
+        self.edit_box(bounds, |pos, _, _| {
+            is_on_edge(&bounds, pos, false).then(|| pattern.clone())
+        })
+    }
+
+    /// Sets all six faces of a box and empties the inside of it
+    pub fn set_hollow_box(&mut self, bounds: Bounds, pattern: &BlockPattern) -> usize {
+        let air = BlockPattern::new(Block::Air);
+
+        self.edit_box(bounds, |pos, _, _| {
+            Some(if is_on_edge(&bounds, pos, true) {
+                pattern.clone()
+            } else {
+                air.clone()
+            })
+        })
+    }
+
+    /// Pastes a level (e.g. one from [`Level::copy_region`]) with its lowest corner at `x`, `y`, `z`
+    ///
+    /// Only the source's finite bounds are pasted (the box around all chunks for infinite levels), along with
+    /// the block entities inside of them. With `skip_air` the air in the source doesn't overwrite anything.
+    /// Positions outside of this level's existing chunks are skipped, no chunks are created for them.
+    pub fn paste(&mut self, source: &Level, x: i32, y: i16, z: i32, skip_air: bool) -> usize {
+        let from = source.get_finite_bounds();
+        let (dx, dy, dz) = (x - from.x, y as i32 - from.y as i32, z - from.z);
+        let to = Bounds { x, y, z, ..from };
+
+        let changed = self.edit_box(to, |pos, _, _| {
+            let (sx, sy, sz) = (pos.x - dx, (pos.y - dy) as i16, pos.z - dz);
+            let block = source.get_block(sx, sy, sz);
+            if skip_air && block == Block::Air {
+                return None;
+            }
+
+            Some(BlockPattern {
+                block,
+                state: source.get_states(sx, sy, sz).cloned().unwrap_or_default(),
+            })
+        });
+
+        for chunk in source.get_chunks().values() {
+            for (pos, block_entity) in &chunk.block_entities {
+                if !from.contains(pos.x, pos.y, pos.z) {
+                    continue;
+                }
+
+                let pos = Vec3i::new(pos.x + dx, pos.y + dy, pos.z + dz);
+                let mut block_entity = block_entity.clone();
+                block_entity.x = pos.x;
+                block_entity.y = pos.y;
+                block_entity.z = pos.z;
+
+                if let Some(c) = self.get_chunk_by_block_coords_mut(pos.x, pos.z) {
+                    c.add_block_entity(pos, block_entity);
+                }
+            }
+        }
+
+        changed
+    }
+
+    /// Copies a box to somewhere else in the level, moved by `offset`
+    ///
+    /// The box is copied out first, so the source and destination can overlap. Like [`Level::paste`], positions
+    /// outside of existing chunks are skipped. A destination past the coordinate limits is clamped to them.
+    pub fn copy_box(&mut self, bounds: Bounds, offset: Vec3i, skip_air: bool) -> usize {
+        let clipboard = self.copy_region(
+            bounds.x,
+            bounds.y,
+            bounds.z,
+            bounds.width,
+            bounds.height,
+            bounds.length,
+        );
+
+        let y = (bounds.y as i32 + offset.y).clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        self.paste(
+            &clipboard,
+            bounds.x.saturating_add(offset.x),
+            y,
+            bounds.z.saturating_add(offset.z),
+            skip_air,
+        )
+    }
+}
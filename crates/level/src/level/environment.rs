@@ -0,0 +1,121 @@
+use crate::block::Block;
+use serde::{Deserialize, Serialize};
+
+/// An RGB color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub fn new(r: u8, g: u8, b: u8) -> Rgb {
+        Rgb { r, g, b }
+    }
+
+    /// Gets a color from an int packed as `0xRRGGBB` (e.g. Indev's `SkyColor`)
+    pub fn from_packed(packed: i32) -> Rgb {
+        Rgb {
+            r: (packed >> 16) as u8,
+            g: (packed >> 8) as u8,
+            b: packed as u8,
+        }
+    }
+
+    /// Packs the color into an int as `0xRRGGBB`
+    pub fn to_packed(&self) -> i32 {
+        ((self.r as i32) << 16) | ((self.g as i32) << 8) | self.b as i32
+    }
+
+    /// Gets a color from a `#RRGGBB` (or `RRGGBB`) hex string
+    ///
+    /// # Returns
+    ///
+    /// `None` if the string isn't a valid color
+    pub fn from_hex(hex: &str) -> Option<Rgb> {
+        let hex = hex.trim().trim_start_matches('#');
+        if hex.len() != 6 {
+            return None;
+        }
+
+        i32::from_str_radix(hex, 16).ok().map(Rgb::from_packed)
+    }
+
+    /// Gets the color as a `#RRGGBB` hex string
+    pub fn to_hex(&self) -> String {
+        format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    }
+
+    /// Gets a color from separate components, any component outside of 0-255 (CPE uses -1) means the default color
+    pub fn from_components(r: i16, g: i16, b: i16) -> Option<Rgb> {
+        if [r, g, b].iter().any(|c| !(0..=255).contains(c)) {
+            return None;
+        }
+
+        Some(Rgb::new(r as u8, g as u8, b as u8))
+    }
+}
+
+/// Weather shown in the level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum Weather {
+    Sunny = 0,
+    Raining = 1,
+    Snowing = 2,
+}
+
+impl Weather {
+    pub fn from_u8(v: u8) -> Option<Weather> {
+        match v {
+            0 => Some(Weather::Sunny),
+            1 => Some(Weather::Raining),
+            2 => Some(Weather::Snowing),
+            _ => None,
+        }
+    }
+}
+
+/// Environment settings of a finite level
+///
+/// Every field is optional, `None` means the format's (or client's) default should be used.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Environment {
+    pub sky_color: Option<Rgb>,
+    pub fog_color: Option<Rgb>,
+    pub cloud_color: Option<Rgb>,
+    /// Color of blocks in shadow (CPE)
+    pub shadow_color: Option<Rgb>,
+    /// Color of blocks in sunlight (CPE)
+    pub sunlight_color: Option<Rgb>,
+    /// Indev's sky brightness
+    pub sky_brightness: Option<u8>,
+    pub cloud_height: Option<i16>,
+    /// Block surrounding the level at the edge height (usually water)
+    pub edge_block: Option<Block>,
+    pub edge_height: Option<i16>,
+    /// Block below the edge block along the sides of the level (usually bedrock)
+    pub side_block: Option<Block>,
+    pub side_height: Option<i16>,
+    pub weather: Option<Weather>,
+    /// Texture pack URL (CPE)
+    pub texture_url: Option<String>,
+}
+
+impl Environment {
+    /// Gets the height of the side blocks relative to the edge height (CPE's `SidesOffset`)
+    pub fn get_sides_offset(&self) -> Option<i16> {
+        match (self.side_height, self.edge_height) {
+            (Some(s), Some(e)) => Some(s - e),
+            _ => None,
+        }
+    }
+
+    /// Sets the side height from an offset relative to the edge height (CPE's `SidesOffset`)
+    pub fn set_sides_offset(&mut self, offset: i16) {
+        if let Some(e) = self.edge_height {
+            self.side_height = Some(e + offset);
+        }
+    }
+}
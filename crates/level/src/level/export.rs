@@ -0,0 +1,233 @@
+use crate::block::Block;
+use crate::level::{Bounds, Level};
+
+/// Size limits of a finite format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FiniteLimits {
+    pub max_width: i32,
+    pub max_height: i16,
+    pub max_length: i32,
+    /// Most blocks the format can hold in total, e.g. NBT byte arrays can't go past `i32::MAX`
+    pub max_volume: Option<u64>,
+}
+
+/// What to do when the exported box doesn't fit in the target format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TruncationPolicy {
+    /// Cut the box down to the limits, keeping the spawn inside of it when possible
+    Crop,
+    /// Shrink the whole box by the smallest whole factor that fits, keeping every n-th block
+    Scale,
+    /// Don't export anything
+    Fail,
+}
+
+/// What an export ended up doing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportReport {
+    /// Box that was asked for, or the level's own box
+    pub requested: Bounds,
+    /// Box of the source level that ended up in the export
+    pub exported: Bounds,
+    /// Size of the exported level
+    pub width: i32,
+    pub height: i16,
+    pub length: i32,
+    /// Every n-th block was kept, 1 when the level wasn't scaled
+    pub scale: i32,
+    /// Whether part of the requested box was left out
+    pub cropped: bool,
+}
+
+impl FiniteLimits {
+    /// Checks if a box of the given size fits
+    pub fn fits(&self, width: i32, height: i16, length: i32) -> bool {
+        width <= self.max_width
+            && height <= self.max_height
+            && length <= self.max_length
+            && self
+                .max_volume
+                .is_none_or(|v| width as u64 * height as u64 * length as u64 <= v)
+    }
+
+    /// Checks if a box of the given size fits, the error says which limit it goes past
+    pub fn check(&self, width: i32, height: i16, length: i32) -> Result<(), String> {
+        if width > self.max_width || height > self.max_height || length > self.max_length {
+            return Err(format!(
+                "{}x{}x{} doesn't fit in {}x{}x{}",
+                width, height, length, self.max_width, self.max_height, self.max_length
+            ));
+        }
+        if !self.fits(width, height, length) {
+            return Err(format!(
+                "{}x{}x{} has more than {} blocks",
+                width,
+                height,
+                length,
+                self.max_volume.unwrap_or_default()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Picks where a cropped axis starts, centered on the spawn and kept inside of the box
+fn get_crop_start(start: i32, size: i32, cropped: i32, spawn: i32) -> i32 {
+    (spawn - cropped / 2).clamp(start, start + size - cropped)
+}
+
+impl Level {
+    /// Exports part of the level as a new finite level that fits in a format's limits
+    ///
+    /// Without a selection the level's finite bounds are used (the box around all chunks for infinite levels).
+    /// The new level starts at 0, 0, 0 and has the spawn moved along with it.
+    ///
+    /// # Returns
+    ///
+    /// The exported level and a report of what was done, or an error when the policy is [`TruncationPolicy::Fail`]
+    /// and the box doesn't fit, or when the limits don't fit even a single block.
+    pub fn export_finite(
+        &self,
+        selection: Option<Bounds>,
+        limits: &FiniteLimits,
+        policy: TruncationPolicy,
+    ) -> Result<(Level, ExportReport), String> {
+        let requested = selection.unwrap_or_else(|| self.get_finite_bounds());
+        if requested.width <= 0 || requested.height <= 0 || requested.length <= 0 {
+            return Err("Selection is empty".to_string());
+        }
+
+        let mut exported = requested;
+        let mut scale = 1;
+
+        if !limits.fits(requested.width, requested.height, requested.length) {
+            // cropping and scaling can't get below a single block
+            if policy != TruncationPolicy::Fail && !limits.fits(1, 1, 1) {
+                return Err(format!(
+                    "Nothing fits in {}x{}x{}",
+                    limits.max_width, limits.max_height, limits.max_length
+                ));
+            }
+
+            match policy {
+                TruncationPolicy::Fail => {
+                    limits
+                        .check(requested.width, requested.height, requested.length)
+                        .map_err(|e| format!("Selection of {}", e))?;
+                }
+                TruncationPolicy::Crop => {
+                    exported.width = requested.width.min(limits.max_width);
+                    exported.height = requested.height.min(limits.max_height);
+                    exported.length = requested.length.min(limits.max_length);
+
+                    // only the volume is left, take away from the longest horizontal side first
+                    while !limits.fits(exported.width, exported.height, exported.length) {
+                        if exported.width >= exported.length {
+                            exported.width -= 1;
+                        } else {
+                            exported.length -= 1;
+                        }
+                    }
+
+                    exported.x =
+                        get_crop_start(requested.x, requested.width, exported.width, self.spawn.x);
+                    exported.y = get_crop_start(
+                        requested.y as i32,
+                        requested.height as i32,
+                        exported.height as i32,
+                        self.spawn.y,
+                    ) as i16;
+                    exported.z = get_crop_start(
+                        requested.z,
+                        requested.length,
+                        exported.length,
+                        self.spawn.z,
+                    );
+                }
+                TruncationPolicy::Scale => {
+                    scale = 2;
+                    while !limits.fits(
+                        requested.width.div_ceil(scale),
+                        (requested.height as i32).div_ceil(scale) as i16,
+                        requested.length.div_ceil(scale),
+                    ) {
+                        scale += 1;
+                    }
+                }
+            }
+        }
+
+        let mut out = if scale == 1 {
+            self.copy_region(
+                exported.x,
+                exported.y,
+                exported.z,
+                exported.width,
+                exported.height,
+                exported.length,
+            )
+        } else {
+            self.copy_scaled(&exported, scale)
+        };
+
+        out.time = self.time;
+        out.environment = self.environment.clone();
+        out.custom_data = self.custom_data.clone();
+        out.set_spawn_point(
+            (self.spawn.x - exported.x) / scale,
+            (self.spawn.y - exported.y as i32) / scale,
+            (self.spawn.z - exported.z) / scale,
+        );
+
+        let size = out.get_finite_bounds();
+        let report = ExportReport {
+            requested,
+            exported,
+            width: size.width,
+            height: size.height,
+            length: size.length,
+            scale,
+            cropped: exported != requested,
+        };
+
+        Ok((out, report))
+    }
+
+    /// Copies a box into a new finite level, keeping every `scale`-th block on each axis
+    ///
+    /// Block entities are left out since they can't be scaled.
+    fn copy_scaled(&self, bounds: &Bounds, scale: i32) -> Level {
+        let width = bounds.width.div_ceil(scale);
+        let height = (bounds.height as i32).div_ceil(scale) as i16;
+        let length = bounds.length.div_ceil(scale);
+
+        let mut out = Level::new_with_name(self.name.clone());
+        out.create_finite(width, height, length);
+
+        for y in 0..height {
+            for z in 0..length {
+                for x in 0..width {
+                    let (sx, sy, sz) = (
+                        bounds.x + x * scale,
+                        bounds.y + y * scale as i16,
+                        bounds.z + z * scale,
+                    );
+
+                    let blk = self.get_block(sx, sy, sz);
+                    if blk != Block::Air {
+                        out.set_block(x, y, z, blk);
+                    }
+
+                    if let Some(states) = self.get_states(sx, sy, sz) {
+                        for (k, v) in states {
+                            out.set_data(x, y, z, k.clone(), v.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
@@ -1,15 +1,24 @@
 pub const AUTHOR: &str = "Author";
 pub const CREATION_TIME: &str = "CreationTime";
 
-// Indev
+// Indev, these are read into `Level::environment` now and no longer put into `custom_data`
+#[deprecated(note = "use `Level::environment.sky_brightness`")]
 pub const SKY_BRIGHTNESS: &str = "SkyBrightness";
+#[deprecated(note = "use `Level::environment.sky_color`")]
 pub const SKY_COLOR: &str = "SkyColor";
+#[deprecated(note = "use `Level::environment.fog_color`")]
 pub const FOG_COLOR: &str = "FogColor";
+#[deprecated(note = "use `Level::environment.cloud_color`")]
 pub const CLOUD_COLOR: &str = "CloudColor";
+#[deprecated(note = "use `Level::environment.cloud_height`")]
 pub const CLOUD_HEIGHT: &str = "CloudHeight";
+#[deprecated(note = "use `Level::environment.side_block`")]
 pub const SURROUNDING_GROUND_TYPE: &str = "SurroundingGroundType";
+#[deprecated(note = "use `Level::environment.side_height`")]
 pub const SURROUNDING_GROUND_HEIGHT: &str = "SurroundingGroundHeight";
+#[deprecated(note = "use `Level::environment.edge_block`")]
 pub const SURROUNDING_WATER_TYPE: &str = "SurroundingWaterType";
+#[deprecated(note = "use `Level::environment.edge_height`")]
 pub const SURROUNDING_WATER_HEIGHT: &str = "SurroundingWaterHeight";
 
 // CW
@@ -18,10 +27,50 @@ pub const LAST_MODIFIED: &str = "LastModified";
 pub const LAST_ACCESSED: &str = "LastAccessed";
 pub const SPAWN_YAW: &str = "SpawnYaw";
 pub const SPAWN_PITCH: &str = "SpawnPitch";
+// Every tag inside of Metadata.CPE is stored as "CpeMetadata.<extension>.<key>", nested compounds add more parts
+pub const CPE_METADATA: &str = "CpeMetadata";
+pub const CPE_CLICK_DISTANCE: &str = "ClickDistance";
+pub const CPE_ENV_COLORS: &str = "EnvColors";
+pub const CPE_ENV_MAP_APPEARANCE: &str = "EnvMapAppearance";
+pub const CPE_ENV_MAP_ASPECT: &str = "EnvMapAspect";
+pub const CPE_ENV_WEATHER_TYPE: &str = "EnvWeatherType";
+pub const CPE_BLOCK_DEFINITIONS: &str = "BlockDefinitions";
+// BlockArray3 is kept as-is, we only support 16 bit block IDs
+pub const CW_BLOCK_ARRAY_3: &str = "CwBlockArray3";
 
 // MCGalaxy
 pub const MIN_ACCESS_PERM: &str = "MinAccessPerm";
 pub const MIN_BUILD_PERM: &str = "MinBuildPerm";
+// Raw 0xFC physics section
+pub const MCG_PHYSICS_DATA: &str = "McgPhysicsData";
+// Every level property is stored as "McgProperties.<key>"
+pub const MCG_PROPERTIES: &str = "McgProperties";
+
+// Classic custom blocks
+// Block state key that holds the numeric ID of a Block::Custom
+pub const CUSTOM_BLOCK_ID: &str = "CustomBlockId";
+// Block state key that holds the flattened name of a Block::Custom (e.g. modded blocks)
+pub const CUSTOM_BLOCK_NAME: &str = "CustomBlockName";
+
+// fCraft
+// Every FCM metadata entry is stored as "FcmMetadata.<group>.<key>"
+pub const FCM_METADATA: &str = "FcmMetadata";
+// FCMv2 has no groups, so its entries are put in here
+pub const FCM_ORIGIN: &str = "_Origin";
+pub const FCM_ZONES: &str = "zones";
+pub const FCM_PERMISSIONS: &str = "security";
+pub const FCM_ENVIRONMENT: &str = "environment";
+
+// Schematic
+pub const WE_ORIGIN_X: &str = "WEOriginX";
+pub const WE_ORIGIN_Y: &str = "WEOriginY";
+pub const WE_ORIGIN_Z: &str = "WEOriginZ";
+pub const WE_OFFSET_X: &str = "WEOffsetX";
+pub const WE_OFFSET_Y: &str = "WEOffsetY";
+pub const WE_OFFSET_Z: &str = "WEOffsetZ";
+pub const LITEMATIC_DESCRIPTION: &str = "LitematicDescription";
+// Raw uncompressed NBT with a compound per region name holding its Entities, PendingBlockTicks and PendingFluidTicks
+pub const LITEMATIC_REGION_DATA: &str = "LitematicRegionData";
 
 // Alpha
 pub const LAST_PLAYED: &str = "LastPlayed";
@@ -34,6 +83,8 @@ pub const TIME: &str = "Time";
 pub const LEVEL: &str = "Level";
 pub const DATA: &str = "Data";
 pub const ENTITIES: &str = "Entities";
+// Entities we don't model are stashed as "RawEntities.<format>", see entity::stash
+pub const RAW_ENTITIES: &str = "RawEntities";
 pub const TILE_ENTITIES: &str = "TileEntities";
 pub const BLOCK_LIGHT: &str = "BlockLight";
 pub const SKY_LIGHT: &str = "SkyLight";
@@ -63,3 +114,13 @@ pub const DATA_VERSION: &str = "DataVersion";
 pub const POS_X: &str = "xPos";
 pub const POS_Z: &str = "zPos";
 pub const SECTIONS: &str = "Sections";
+
+// Pocket Edition
+pub const STORAGE_VERSION: &str = "StorageVersion";
+pub const PLATFORM: &str = "Platform";
+pub const DAY_CYCLE_STOP_TIME: &str = "dayCycleStopTime";
+pub const SPAWN_MOBS: &str = "spawnMobs";
+// Raw uncompressed (big-endian) NBT holding the "Player" compound from a PE level.dat
+pub const PE_PLAYER: &str = "PePlayer";
+// Raw biome ids of a chunk, one byte per column (x * 16 + z)
+pub const BIOMES: &str = "Biomes";
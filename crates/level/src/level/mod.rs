@@ -1,16 +1,27 @@
 pub mod chunk;
 pub mod chunk_section;
+pub mod diff;
+pub mod edit;
+pub mod environment;
+pub mod export;
 pub mod metadata;
+pub mod query;
 pub mod region;
+pub mod render;
+pub mod stats;
+pub mod surroundings;
 
 use crate::block::Block;
 use crate::level::chunk::{Chunk, Light, CHUNK_LENGTH, CHUNK_WIDTH};
+use crate::level::environment::Environment;
 use lodestone_common::types::hashmap_ext::Value;
+use lodestone_common::types::vec3i::Vec3i;
 use rayon::iter::IntoParallelRefIterator;
 use rayon::iter::ParallelIterator;
 use serde::{Deserialize, Serialize};
 use std::cmp::min;
 use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
 
 #[derive(Clone, Default, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub struct Coords {
@@ -25,11 +36,65 @@ pub struct Spawn {
     pub z: i32,
 }
 
+/// Exact box a finite level covers
+///
+/// Chunks always hold whole 16x16 columns, so this is what keeps e.g. a 100x64x100 level from becoming 112x64x112.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bounds {
+    pub x: i32,
+    pub y: i16,
+    pub z: i32,
+    pub width: i32,
+    pub height: i16,
+    pub length: i32,
+}
+
+impl Bounds {
+    /// Checks if a block position is inside of the box
+    pub fn contains(&self, x: i32, y: i32, z: i32) -> bool {
+        (self.x as i64..self.x as i64 + self.width as i64).contains(&(x as i64))
+            && self.get_y_range().contains(&y)
+            && (self.z as i64..self.z as i64 + self.length as i64).contains(&(z as i64))
+    }
+
+    /// Gets the X and Z inside of a chunk that the box covers, both are empty if it misses the chunk
+    ///
+    /// Worked out in i64, so boxes reaching past `i32::MAX` don't overflow.
+    pub fn get_chunk_ranges(&self, coords: &Coords) -> (Range<i32>, Range<i32>) {
+        let range = |start: i32, size: i32, chunk: i32, chunk_size: i8| {
+            let base = chunk as i64 * chunk_size as i64;
+            let clamp = |v: i64| v.clamp(0, chunk_size as i64) as i32;
+            let from = clamp(start as i64 - base);
+            let to = clamp(start as i64 + size as i64 - base);
+            from..to.max(from)
+        };
+
+        (
+            range(self.x, self.width, coords.x, CHUNK_WIDTH),
+            range(self.z, self.length, coords.z, CHUNK_LENGTH),
+        )
+    }
+
+    /// Checks if any of the box is inside of a chunk
+    pub fn overlaps_chunk(&self, coords: &Coords) -> bool {
+        let (xs, zs) = self.get_chunk_ranges(coords);
+        !xs.is_empty() && !zs.is_empty()
+    }
+
+    /// Gets the Y the box goes from and to, in i32 so it can't overflow
+    pub fn get_y_range(&self) -> Range<i32> {
+        self.y as i32..self.y as i32 + self.height as i32
+    }
+}
+
 #[derive(Clone)]
 pub struct Level {
     pub name: String,
     pub time: i64,
     pub spawn: Spawn,
+    pub environment: Environment,
+    /// Set for finite levels, infinite levels don't have any
+    pub bounds: Option<Bounds>,
 
     chunks: HashMap<Coords, Chunk>,
 
@@ -43,10 +108,13 @@ impl Level {
             time: 0,
             chunks: HashMap::new(),
             spawn: Spawn { x: 0, y: 0, z: 0 },
+            environment: Environment::default(),
+            bounds: None,
             custom_data: Default::default(),
         }
     }
 
+    /// Creates the chunks for a finite level of the given size and sets its bounds
     pub fn create_finite(&mut self, w: i32, h: i16, l: i32) {
         for cx in 0..w.div_ceil(CHUNK_WIDTH as i32) {
             for cz in 0..l.div_ceil(CHUNK_LENGTH as i32) {
@@ -58,6 +126,15 @@ impl Level {
                 self.add_chunk(coords.clone(), chunk);
             }
         }
+
+        self.bounds = Some(Bounds {
+            x: 0,
+            y: 0,
+            z: 0,
+            width: w,
+            height: h,
+            length: l,
+        });
     }
     pub fn new() -> Level {
         Level {
@@ -65,6 +142,8 @@ impl Level {
             time: 0,
             chunks: HashMap::new(),
             spawn: Spawn { x: 0, y: 0, z: 0 },
+            environment: Environment::default(),
+            bounds: None,
             custom_data: Default::default(),
         }
     }
@@ -243,6 +322,67 @@ impl Level {
         &mut self.chunks
     }
 
+    /// Copies a box out of the level into a new finite level, moving it to 0, 0, 0
+    ///
+    /// Block states and block entities inside of the box are copied along with the blocks.
+    ///
+    /// # Params
+    ///
+    /// * `x`, `y`, `z` - The lowest corner of the box
+    /// * `width`, `height`, `length` - Size of the box
+    pub fn copy_region(
+        &self,
+        x: i32,
+        y: i16,
+        z: i32,
+        width: i32,
+        height: i16,
+        length: i32,
+    ) -> Level {
+        let mut region = Level::new_with_name(self.name.clone());
+        region.create_finite(width, height, length);
+
+        for ry in 0..height {
+            for rz in 0..length {
+                for rx in 0..width {
+                    let blk = self.get_block(x + rx, y + ry, z + rz);
+                    if blk != Block::Air {
+                        region.set_block(rx, ry, rz, blk);
+                    }
+
+                    if let Some(states) = self.get_states(x + rx, y + ry, z + rz) {
+                        for (k, v) in states {
+                            region.set_data(rx, ry, rz, k.clone(), v.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        for chunk in self.chunks.values() {
+            for (pos, block_entity) in &chunk.block_entities {
+                let (rx, ry, rz) = (pos.x - x, pos.y - y as i32, pos.z - z);
+                if !(0..width).contains(&rx)
+                    || !(0..height as i32).contains(&ry)
+                    || !(0..length).contains(&rz)
+                {
+                    continue;
+                }
+
+                let mut block_entity = block_entity.clone();
+                block_entity.x = rx;
+                block_entity.y = ry;
+                block_entity.z = rz;
+
+                if let Some(c) = region.get_chunk_by_block_coords_mut(rx, rz) {
+                    c.add_block_entity(Vec3i::new(rx, ry, rz), block_entity);
+                }
+            }
+        }
+
+        region
+    }
+
     // TODO: this works... but is very slow.
     // upd: slightly faster now
     pub fn get_blockmap(&self) -> Vec<Block> {
@@ -328,6 +468,20 @@ impl Level {
         self.get_max_z() - self.get_min_z()
     }
 
+    /// Gets the box finite formats should write
+    ///
+    /// This is the level's bounds when it has any, otherwise the box around all of its chunks.
+    pub fn get_finite_bounds(&self) -> Bounds {
+        self.bounds.unwrap_or_else(|| Bounds {
+            x: self.get_min_block_x(),
+            y: 0,
+            z: self.get_min_block_z(),
+            width: self.get_block_width(),
+            height: self.get_block_height(),
+            length: self.get_block_length(),
+        })
+    }
+
     #[inline(always)]
     pub fn get_block_width(&self) -> i32 {
         self.get_width() * CHUNK_WIDTH as i32
@@ -0,0 +1,230 @@
+use crate::block::Block;
+use crate::entity::block_entity::BlockEntity;
+use crate::level::chunk::{Chunk, CHUNK_LENGTH, CHUNK_SECTION_HEIGHT, CHUNK_WIDTH};
+use crate::level::chunk_section::ChunkSection;
+use crate::level::{Bounds, Coords, Level};
+use lodestone_common::types::vec3i::Vec3i;
+use std::collections::BTreeMap;
+
+/// Part of a level to look through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Box(Bounds),
+    /// Every block at an X and Z
+    Column {
+        x: i32,
+        z: i32,
+    },
+    /// Every block at most `radius` blocks away from the center
+    Sphere {
+        x: i32,
+        y: i16,
+        z: i32,
+        radius: i32,
+    },
+}
+
+/// A block found by a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockInfo<'a> {
+    pub pos: Vec3i,
+    pub block: Block,
+    /// `None` when the block doesn't have any state
+    pub state: Option<&'a BTreeMap<String, String>>,
+}
+
+impl Region {
+    /// Gets the box around the region
+    pub fn get_box(&self) -> Bounds {
+        match *self {
+            Region::Box(bounds) => bounds,
+            Region::Column { x, z } => Bounds {
+                x,
+                y: 0,
+                z,
+                width: 1,
+                height: i16::MAX,
+                length: 1,
+            },
+            Region::Sphere { x, y, z, radius } => {
+                let size = radius.saturating_mul(2).saturating_add(1);
+                Bounds {
+                    x: x.saturating_sub(radius),
+                    y: (y as i32).saturating_sub(radius).max(i16::MIN as i32) as i16,
+                    z: z.saturating_sub(radius),
+                    width: size,
+                    height: size.min(i16::MAX as i32) as i16,
+                    length: size,
+                }
+            }
+        }
+    }
+
+    pub fn contains(&self, x: i32, y: i16, z: i32) -> bool {
+        match *self {
+            Region::Sphere {
+                x: cx,
+                y: cy,
+                z: cz,
+                radius,
+            } => {
+                let (dx, dy, dz) = (
+                    x as i64 - cx as i64,
+                    y as i64 - cy as i64,
+                    z as i64 - cz as i64,
+                );
+                dx * dx + dy * dy + dz * dz <= radius as i64 * radius as i64
+            }
+            _ => self.get_box().contains(x, y as i32, z),
+        }
+    }
+}
+
+/// Matches block entity IDs from any version, e.g. `Chest` and `minecraft:chest`
+fn is_same_block_entity_id(a: &str, b: &str) -> bool {
+    a.trim_start_matches("minecraft:")
+        .eq_ignore_ascii_case(b.trim_start_matches("minecraft:"))
+}
+
+/// Walks the blocks of a section that are inside of a region, skipping air
+fn iter_section<'a>(
+    section: &'a ChunkSection,
+    coords: &Coords,
+    base_y: i16,
+    region: Region,
+) -> impl Iterator<Item = BlockInfo<'a>> + 'a {
+    let b = region.get_box();
+    let (bx, bz) = (
+        coords.x * CHUNK_WIDTH as i32,
+        coords.z * CHUNK_LENGTH as i32,
+    );
+
+    // the part of the section inside of the box
+    let (xs, zs) = b.get_chunk_ranges(coords);
+    let ys = b.get_y_range();
+    let ys = (ys.start - base_y as i32).max(0)
+        ..(ys.end - base_y as i32).min(CHUNK_SECTION_HEIGHT as i32);
+
+    ys.flat_map(move |y| {
+        let xs = xs.clone();
+        zs.clone()
+            .flat_map(move |z| xs.clone().map(move |x| (x as i8, y as i16, z as i8)))
+    })
+    .filter_map(move |(x, y, z)| {
+        let block = section.get_block(x, y, z);
+        let pos = Vec3i::new(bx + x as i32, (base_y + y) as i32, bz + z as i32);
+
+        if block == Block::Air || !region.contains(pos.x, pos.y as i16, pos.z) {
+            return None;
+        }
+
+        Some(BlockInfo {
+            pos,
+            block,
+            state: section.get_state(x, y, z).filter(|s| !s.is_empty()),
+        })
+    })
+}
+
+impl Level {
+    /// Gets the chunks overlapping a region, sorted by X then Z
+    fn get_region_chunks(&self, region: Region) -> Vec<(&Coords, &Chunk)> {
+        let b = region.get_box();
+
+        let mut chunks: Vec<(&Coords, &Chunk)> = self
+            .get_chunks()
+            .iter()
+            .filter(|(c, _)| b.overlaps_chunk(c))
+            .collect();
+        chunks.sort_by_key(|(c, _)| (c.x, c.z));
+
+        chunks
+    }
+
+    /// Goes through every block in a region that isn't air, chunk by chunk
+    ///
+    /// Walks the chunk sections directly instead of looking up every position like [`Level::get_block`] does.
+    pub fn iter_region(&self, region: Region) -> impl Iterator<Item = BlockInfo<'_>> {
+        self.find_blocks_in_sections(region, |_| true)
+    }
+
+    /// Finds every block in a region that's one of the given blocks
+    ///
+    /// Sections that don't have any of them are skipped without going through them position by position.
+    pub fn find_blocks(&self, region: Region, blocks: &[Block]) -> Vec<BlockInfo<'_>> {
+        self.find_blocks_in_sections(region, |s| s.contains_any(blocks))
+            .filter(|info| blocks.contains(&info.block))
+            .collect()
+    }
+
+    /// Finds every block in a region that matches a predicate
+    pub fn find_blocks_matching<F>(&self, region: Region, predicate: F) -> Vec<BlockInfo<'_>>
+    where
+        F: Fn(&BlockInfo) -> bool,
+    {
+        self.iter_region(region)
+            .filter(|info| predicate(info))
+            .collect()
+    }
+
+    fn find_blocks_in_sections<'a, F>(
+        &'a self,
+        region: Region,
+        section_filter: F,
+    ) -> impl Iterator<Item = BlockInfo<'a>>
+    where
+        F: Fn(&ChunkSection) -> bool + Copy,
+    {
+        let b = region.get_box();
+
+        self.get_region_chunks(region)
+            .into_iter()
+            .flat_map(move |(coords, chunk)| {
+                chunk
+                    .chunk_sections
+                    .iter()
+                    .enumerate()
+                    .map(|(i, s)| (i as i16 * CHUNK_SECTION_HEIGHT as i16, s))
+                    .filter(move |(base_y, s)| {
+                        (*base_y as i32) < b.get_y_range().end
+                            && *base_y as i32 + CHUNK_SECTION_HEIGHT as i32 > b.get_y_range().start
+                            && *base_y < chunk.height
+                            && section_filter(s)
+                    })
+                    .flat_map(move |(base_y, s)| iter_section(s, coords, base_y, region))
+            })
+    }
+
+    /// Finds block entities by ID (e.g. `chest`, matching both `Chest` and `minecraft:chest`), in a region if given
+    pub fn find_block_entities(
+        &self,
+        id: Option<&str>,
+        region: Option<Region>,
+    ) -> Vec<&BlockEntity> {
+        let chunks: Vec<&Chunk> = match region {
+            Some(r) => self
+                .get_region_chunks(r)
+                .into_iter()
+                .map(|(_, c)| c)
+                .collect(),
+            None => {
+                let mut chunks: Vec<(&Coords, &Chunk)> = self.get_chunks().iter().collect();
+                chunks.sort_by_key(|(c, _)| (c.x, c.z));
+                chunks.into_iter().map(|(_, c)| c).collect()
+            }
+        };
+
+        chunks
+            .into_iter()
+            .flat_map(|c| c.block_entities.iter())
+            .filter(|(pos, e)| {
+                id.is_none_or(|id| is_same_block_entity_id(&e.id, id))
+                    && region.is_none_or(|r| {
+                        (i16::MIN as i32..=i16::MAX as i32).contains(&pos.y)
+                            && r.contains(pos.x, pos.y as i16, pos.z)
+                    })
+            })
+            .map(|(_, e)| e)
+            .collect()
+    }
+}
@@ -0,0 +1,212 @@
+use crate::block::palette::MapColor;
+use crate::block::Block;
+use crate::level::chunk::{Light, CHUNK_LENGTH, CHUNK_SECTION_HEIGHT, CHUNK_WIDTH};
+use crate::level::render::MapImage;
+use crate::level::{Bounds, Level};
+
+/// Brightness of the top, +Z (left) and +X (right) faces
+const FACE_SHADES: [f32; 3] = [1.0, 0.8, 0.6];
+
+/// How dark a face with no light at all gets
+const MIN_BRIGHTNESS: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IsometricOptions {
+    /// Half the width of a block in pixels, has to be even
+    pub block_size: u32,
+    /// Shades faces by the sky and block light in front of them, otherwise everything is fully lit
+    pub lighting: bool,
+}
+
+impl Default for IsometricOptions {
+    fn default() -> Self {
+        IsometricOptions {
+            block_size: 4,
+            lighting: true,
+        }
+    }
+}
+
+fn is_opaque(block: Block) -> bool {
+    block.get_map_color().is_some()
+}
+
+/// Gets the light in front of a face, anything outside of the level's sections is open sky
+fn get_face_light(level: &Level, x: i32, y: i16, z: i32) -> u8 {
+    let Some(chunk) = level.get_chunk_by_block_coords(x, z) else {
+        return 15;
+    };
+
+    if y < 0 || y >= chunk.height || chunk.get_chunk_section(y).is_none() {
+        return 15;
+    }
+
+    let (cx, cz) = (
+        x.rem_euclid(CHUNK_WIDTH as i32) as i8,
+        z.rem_euclid(CHUNK_LENGTH as i32) as i8,
+    );
+    chunk
+        .get_light(Light::SKY, cx, y, cz)
+        .max(chunk.get_light(Light::BLOCK, cx, y, cz))
+}
+
+impl Level {
+    /// Renders an isometric view of a box of the level, looking from the +X +Z corner
+    ///
+    /// Without a selection the level's finite bounds are used (the box around all chunks for infinite levels).
+    /// Blocks are drawn with their map colors, blocks without one (air, glass) aren't drawn.
+    pub fn render_isometric(
+        &self,
+        selection: Option<Bounds>,
+        options: &IsometricOptions,
+    ) -> MapImage {
+        let bounds = selection.unwrap_or_else(|| self.get_finite_bounds());
+        let s = options.block_size.max(2) & !1;
+        let (w, h, l) = (
+            bounds.width.max(0) as u32,
+            bounds.height.max(0) as u32,
+            bounds.length.max(0) as u32,
+        );
+
+        let mut img = MapImage::new((w + l) * s, (w + l) * s / 2 + h * s);
+        // x + y + z of whatever was drawn on each pixel, higher is closer
+        let mut depth = vec![i32::MIN; img.width as usize * img.height as usize];
+
+        for (coords, chunk) in self.get_chunks() {
+            let (bx, bz) = (
+                coords.x * CHUNK_WIDTH as i32,
+                coords.z * CHUNK_LENGTH as i32,
+            );
+            if bx + CHUNK_WIDTH as i32 <= bounds.x
+                || bx >= bounds.x + bounds.width
+                || bz + CHUNK_LENGTH as i32 <= bounds.z
+                || bz >= bounds.z + bounds.length
+            {
+                continue;
+            }
+
+            for (i, section) in chunk.chunk_sections.iter().enumerate() {
+                let by = i as i16 * CHUNK_SECTION_HEIGHT as i16;
+
+                for sy in 0..CHUNK_SECTION_HEIGHT as i16 {
+                    let y = by + sy;
+                    if y < bounds.y || y >= bounds.y + bounds.height {
+                        continue;
+                    }
+
+                    for sz in 0..CHUNK_LENGTH {
+                        for sx in 0..CHUNK_WIDTH {
+                            let (x, z) = (bx + sx as i32, bz + sz as i32);
+                            if x < bounds.x
+                                || x >= bounds.x + bounds.width
+                                || z < bounds.z
+                                || z >= bounds.z + bounds.length
+                            {
+                                continue;
+                            }
+
+                            let Some(color) = section.get_block(sx, sy, sz).get_map_color() else {
+                                continue;
+                            };
+
+                            // faces we can see: top, +Z, +X
+                            let faces = [(x, y + 1, z), (x, y, z + 1), (x + 1, y, z)];
+                            let visible = faces.map(|(fx, fy, fz)| {
+                                !is_opaque(self.get_block(fx, fy, fz))
+                                    || fx >= bounds.x + bounds.width
+                                    || fy >= bounds.y + bounds.height
+                                    || fz >= bounds.z + bounds.length
+                            });
+                            if !visible.contains(&true) {
+                                continue;
+                            }
+
+                            let brightness = faces.map(|(fx, fy, fz)| {
+                                let light = if options.lighting {
+                                    get_face_light(self, fx, fy, fz) as f32 / 15.0
+                                } else {
+                                    1.0
+                                };
+                                MIN_BRIGHTNESS + (1.0 - MIN_BRIGHTNESS) * light
+                            });
+
+                            let (rx, ry, rz) = (
+                                (x - bounds.x) as u32,
+                                (y - bounds.y) as u32,
+                                (z - bounds.z) as u32,
+                            );
+                            let left = (rx + l - 1 - rz) * s;
+                            let top = (rx + rz) * s / 2 + (h - 1 - ry) * s;
+
+                            draw_block(
+                                &mut img,
+                                &mut depth,
+                                (left, top, s),
+                                x + y as i32 + z,
+                                color,
+                                visible,
+                                brightness,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        img
+    }
+}
+
+/// Draws the hexagon of a block, with the top face as a diamond and the +Z and +X faces under it
+fn draw_block(
+    img: &mut MapImage,
+    depth: &mut [i32],
+    (left, top, s): (u32, u32, u32),
+    block_depth: i32,
+    color: MapColor,
+    visible: [bool; 3],
+    brightness: [f32; 3],
+) {
+    let rgb = color.get_rgb();
+    let size = s as f32;
+
+    for v in 0..s * 2 {
+        for u in 0..s * 2 {
+            let du = u as f32 + 0.5 - size;
+            let dv = v as f32 + 0.5;
+            if dv < du.abs() / 2.0 || dv > size * 2.0 - du.abs() / 2.0 {
+                continue;
+            }
+
+            let face = if dv <= size - du.abs() / 2.0 {
+                0
+            } else if du < 0.0 {
+                1
+            } else {
+                2
+            };
+            if !visible[face] {
+                continue;
+            }
+
+            let (px, py) = (left + u, top + v);
+            let i = py as usize * img.width as usize + px as usize;
+            if depth[i] > block_depth {
+                continue;
+            }
+            depth[i] = block_depth;
+
+            let b = FACE_SHADES[face] * brightness[face];
+            img.set_pixel(
+                px,
+                py,
+                [
+                    (rgb.r as f32 * b) as u8,
+                    (rgb.g as f32 * b) as u8,
+                    (rgb.b as f32 * b) as u8,
+                    0xFF,
+                ],
+            );
+        }
+    }
+}
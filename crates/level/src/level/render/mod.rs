@@ -0,0 +1,435 @@
+pub mod isometric;
+pub mod slice;
+
+use crate::block::palette::MapColor;
+use crate::level::chunk::{Chunk, CHUNK_LENGTH, CHUNK_SECTION_HEIGHT, CHUNK_WIDTH};
+use crate::level::Level;
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Size of map tiles, the usual size for web map viewers
+pub const DEFAULT_TILE_SIZE: u32 = 256;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// An RGBA image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// A tile of a map tile pyramid, zoom 0 is a single tile holding the whole level
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapTile {
+    pub zoom: u32,
+    pub x: u32,
+    pub y: u32,
+    pub image: MapImage,
+}
+
+/// Top of a column as seen from above
+#[derive(Debug, Clone, Copy)]
+struct MapColumn {
+    color: MapColor,
+    height: f32,
+    /// How many water blocks are on top, 0 if the column isn't water
+    water_depth: f32,
+}
+
+fn write_png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+
+    let mut crc = Crc::new();
+    crc.update(&out[start..]);
+    out.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+impl MapImage {
+    /// Creates a fully transparent image
+    pub fn new(width: u32, height: u32) -> MapImage {
+        MapImage {
+            width,
+            height,
+            pixels: vec![0u8; width as usize * height as usize * 4],
+        }
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, rgba: [u8; 4]) {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        self.pixels[i..i + 4].copy_from_slice(&rgba);
+    }
+
+    /// Checks if every pixel is fully transparent
+    pub fn is_empty(&self) -> bool {
+        self.pixels.chunks_exact(4).all(|p| p[3] == 0)
+    }
+
+    /// Encodes the image as an 8-bit RGBA PNG
+    pub fn to_png(&self) -> Vec<u8> {
+        let row = self.width as usize * 4;
+
+        // every scanline starts with its filter type, we don't filter
+        let mut enc = ZlibEncoder::new(
+            Vec::with_capacity(self.pixels.len() / 4),
+            Compression::default(),
+        );
+        for line in self.pixels.chunks_exact(row.max(1)) {
+            enc.write_all(&[0])
+                .expect("Could not compress PNG scanline");
+            enc.write_all(line)
+                .expect("Could not compress PNG scanline");
+        }
+        let idat = enc.finish().expect("Could not compress PNG data");
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&self.width.to_be_bytes());
+        ihdr.extend_from_slice(&self.height.to_be_bytes());
+        // bit depth, color type (RGBA), compression, filter, interlace
+        ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut out = Vec::with_capacity(idat.len() + 64);
+        out.extend_from_slice(&PNG_SIGNATURE);
+        write_png_chunk(&mut out, b"IHDR", &ihdr);
+        write_png_chunk(&mut out, b"IDAT", &idat);
+        write_png_chunk(&mut out, b"IEND", &[]);
+
+        out
+    }
+
+    /// Halves the size of a 2x2 grid of images (top left, top right, bottom left, bottom right) of the same size
+    fn downscale(size: u32, parts: [Option<&MapImage>; 4]) -> MapImage {
+        let mut out = MapImage::new(size, size);
+        let half = size / 2;
+
+        for (i, part) in parts.iter().enumerate() {
+            let Some(part) = part else {
+                continue;
+            };
+            let (ox, oy) = ((i as u32 % 2) * half, (i as u32 / 2) * half);
+
+            for y in 0..half {
+                for x in 0..half {
+                    let mut sum = [0u32; 4];
+                    let mut opaque = 0;
+                    for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                        let p = part.get_pixel(x * 2 + dx, y * 2 + dy);
+                        if p[3] != 0 {
+                            opaque += 1;
+                            for c in 0..3 {
+                                sum[c] += p[c] as u32;
+                            }
+                        }
+                        sum[3] += p[3] as u32;
+                    }
+
+                    // nothing to average, keep it transparent
+                    if opaque == 0 {
+                        continue;
+                    }
+
+                    out.set_pixel(
+                        ox + x,
+                        oy + y,
+                        [
+                            (sum[0] / opaque) as u8,
+                            (sum[1] / opaque) as u8,
+                            (sum[2] / opaque) as u8,
+                            (sum[3] / 4) as u8,
+                        ],
+                    );
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// Finds the first block with a map color from the top of a column, going through water to find its depth
+fn get_map_column(chunk: &Chunk, x: i8, z: i8) -> Option<MapColumn> {
+    let mut y = chunk.height - 1;
+
+    while y >= 0 {
+        if chunk.get_chunk_section(y).is_none() {
+            y -= y % CHUNK_SECTION_HEIGHT as i16 + 1;
+            continue;
+        }
+
+        if let Some(color) = chunk.get_block(x, y, z).get_map_color() {
+            let mut water_depth = 0;
+            if color == MapColor::Water {
+                while y - water_depth >= 0
+                    && chunk.get_block(x, y - water_depth, z).get_map_color()
+                        == Some(MapColor::Water)
+                {
+                    water_depth += 1;
+                }
+            }
+
+            return Some(MapColumn {
+                color,
+                height: y as f32,
+                water_depth: water_depth as f32,
+            });
+        }
+
+        y -= 1;
+    }
+
+    None
+}
+
+impl Level {
+    fn get_map_column(&self, x: i32, z: i32) -> Option<MapColumn> {
+        let chunk = self.get_chunk_by_block_coords(x, z)?;
+        get_map_column(
+            chunk,
+            x.rem_euclid(CHUNK_WIDTH as i32) as i8,
+            z.rem_euclid(CHUNK_LENGTH as i32) as i8,
+        )
+    }
+
+    /// Gets the column of the map pixel covering `scale` x `scale` blocks, picking the most common color in them
+    fn get_map_area(&self, x: i32, z: i32, scale: u32) -> Option<MapColumn> {
+        if scale == 1 {
+            return self.get_map_column(x, z);
+        }
+
+        let mut counts: Vec<(MapColor, u32)> = Vec::new();
+        let (mut height, mut water_depth, mut columns) = (0.0, 0.0, 0);
+
+        for dz in 0..scale as i32 {
+            for dx in 0..scale as i32 {
+                let Some(col) = self.get_map_column(x + dx, z + dz) else {
+                    continue;
+                };
+
+                match counts.iter_mut().find(|(c, _)| *c == col.color) {
+                    Some((_, n)) => *n += 1,
+                    None => counts.push((col.color, 1)),
+                }
+                height += col.height;
+                water_depth += col.water_depth;
+                columns += 1;
+            }
+        }
+
+        let (color, _) = counts.iter().rev().max_by_key(|(_, n)| *n)?;
+        Some(MapColumn {
+            color: *color,
+            height: height / columns as f32,
+            water_depth: water_depth / columns as f32,
+        })
+    }
+
+    /// Gets the map colors and shades of an area, like in-game maps do
+    ///
+    /// Land is shaded by comparing its height with the pixel north of it and water gets darker the deeper it is.
+    /// Pixels without any blocks are `None`.
+    ///
+    /// # Params
+    ///
+    /// * `x`, `z` - The block at the top left of the area
+    /// * `width`, `length` - Size of the area in pixels
+    /// * `scale` - Blocks per pixel on each axis
+    pub fn get_map_colors(
+        &self,
+        x: i32,
+        z: i32,
+        width: u32,
+        length: u32,
+        scale: u32,
+    ) -> Vec<Option<(MapColor, u8)>> {
+        let scale = scale.max(1);
+        let s = scale as i32;
+        let mut colors = Vec::with_capacity(width as usize * length as usize);
+
+        let mut north: Vec<Option<MapColumn>> = (0..width as i32)
+            .map(|px| self.get_map_area(x + px * s, z - s, scale))
+            .collect();
+
+        for py in 0..length as i32 {
+            let row: Vec<Option<MapColumn>> = (0..width as i32)
+                .map(|px| self.get_map_area(x + px * s, z + py * s, scale))
+                .collect();
+
+            for px in 0..width as i32 {
+                let Some(col) = row[px as usize] else {
+                    colors.push(None);
+                    continue;
+                };
+
+                let dither = ((x.div_euclid(s) + z.div_euclid(s) + px + py) & 1) as f32;
+                let shade = if col.color == MapColor::Water {
+                    let d = col.water_depth * 0.1 + dither * 0.2;
+                    if d < 0.5 {
+                        2
+                    } else if d > 0.9 {
+                        0
+                    } else {
+                        1
+                    }
+                } else {
+                    let north_height = north[px as usize].map_or(col.height, |n| n.height);
+                    let d =
+                        (col.height - north_height) * 4.0 / (s + 4) as f32 + (dither - 0.5) * 0.4;
+                    if d > 0.6 {
+                        2
+                    } else if d < -0.6 {
+                        0
+                    } else {
+                        1
+                    }
+                };
+
+                colors.push(Some((col.color, shade)));
+            }
+
+            north = row;
+        }
+
+        colors
+    }
+
+    /// Renders a top-down map of an area, with north (-Z) at the top
+    ///
+    /// Uses the same colors and shading as in-game maps, see [`Level::get_map_colors`]. Columns without any blocks
+    /// are left transparent.
+    ///
+    /// # Params
+    ///
+    /// * `x`, `z` - The block at the top left of the map
+    /// * `width`, `length` - Size of the map in blocks, one pixel per block
+    pub fn render_map(&self, x: i32, z: i32, width: u32, length: u32) -> MapImage {
+        let mut img = MapImage::new(width, length);
+
+        for (i, pixel) in self
+            .get_map_colors(x, z, width, length, 1)
+            .into_iter()
+            .enumerate()
+        {
+            if let Some((color, shade)) = pixel {
+                let rgb = color.get_shaded_rgb(shade);
+                img.set_pixel(
+                    i as u32 % width,
+                    i as u32 / width,
+                    [rgb.r, rgb.g, rgb.b, 0xFF],
+                );
+            }
+        }
+
+        img
+    }
+
+    /// Renders the level's finite bounds (the box around all chunks for infinite levels) as a tile pyramid
+    ///
+    /// The highest zoom has one pixel per block, each zoom below it halves that until the whole level fits in one
+    /// tile at zoom 0. Empty tiles are left out, as is everything when `tile_size` is 0.
+    pub fn render_map_tiles(&self, tile_size: u32) -> Vec<MapTile> {
+        if tile_size == 0 {
+            return Vec::new();
+        }
+
+        let bounds = self.get_finite_bounds();
+        let size = bounds.width.max(bounds.length).max(1) as u32;
+        let max_zoom = size
+            .div_ceil(tile_size)
+            .next_power_of_two()
+            .trailing_zeros();
+
+        let tiles_x = (bounds.width.max(0) as u32).div_ceil(tile_size);
+        let tiles_y = (bounds.length.max(0) as u32).div_ceil(tile_size);
+
+        let mut zoom: HashMap<(u32, u32), MapImage> = (0..tiles_x * tiles_y)
+            .into_par_iter()
+            .filter_map(|i| {
+                let (tx, ty) = (i % tiles_x, i / tiles_x);
+                let img = self.render_map(
+                    bounds.x + (tx * tile_size) as i32,
+                    bounds.z + (ty * tile_size) as i32,
+                    tile_size,
+                    tile_size,
+                );
+
+                (!img.is_empty()).then_some(((tx, ty), img))
+            })
+            .collect();
+
+        let mut tiles = Vec::new();
+        for z in (0..=max_zoom).rev() {
+            let mut below: HashMap<(u32, u32), MapImage> = HashMap::new();
+
+            if z != 0 {
+                let parents: Vec<(u32, u32)> = {
+                    let mut p: Vec<(u32, u32)> = zoom.keys().map(|(x, y)| (x / 2, y / 2)).collect();
+                    p.sort();
+                    p.dedup();
+                    p
+                };
+
+                for (px, py) in parents {
+                    let parts = [(0, 0), (1, 0), (0, 1), (1, 1)]
+                        .map(|(dx, dy)| zoom.get(&(px * 2 + dx, py * 2 + dy)));
+                    below.insert((px, py), MapImage::downscale(tile_size, parts));
+                }
+            }
+
+            let mut level_tiles: Vec<MapTile> = zoom
+                .into_iter()
+                .map(|((x, y), image)| MapTile {
+                    zoom: z,
+                    x,
+                    y,
+                    image,
+                })
+                .collect();
+            level_tiles.sort_by_key(|t| (t.x, t.y));
+            tiles.extend(level_tiles);
+
+            zoom = below;
+        }
+
+        tiles
+    }
+
+    /// Writes a tile pyramid as `zoom/x/y.png` files for web map viewers
+    ///
+    /// # Returns
+    ///
+    /// How many tiles were written
+    pub fn write_map_tiles(&self, path: &Path, tile_size: u32) -> Result<usize, String> {
+        let tiles = self.render_map_tiles(tile_size);
+
+        for tile in &tiles {
+            let dir = path.join(tile.zoom.to_string()).join(tile.x.to_string());
+            fs::create_dir_all(&dir).map_err(|e| format!("Could not create {:?}: {}", dir, e))?;
+
+            let file = dir.join(format!("{}.png", tile.y));
+            fs::write(&file, tile.image.to_png())
+                .map_err(|e| format!("Could not write {:?}: {}", file, e))?;
+        }
+
+        Ok(tiles.len())
+    }
+}
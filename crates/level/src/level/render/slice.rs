@@ -0,0 +1,61 @@
+use crate::level::render::MapImage;
+use crate::level::{Bounds, Level};
+
+/// A vertical cut through a level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlicePlane {
+    /// Cut at a Z coordinate, X goes to the right
+    Xy { z: i32 },
+    /// Cut at an X coordinate, Z goes to the right
+    Zy { x: i32 },
+}
+
+impl Level {
+    /// Renders a vertical slice of the level, with the top of the level at the top of the image
+    ///
+    /// Without a selection the level's finite bounds are used (the box around all chunks for infinite levels),
+    /// only the part of the selection along the slice is used. Each block is `scale` x `scale` pixels in its map
+    /// color, blocks without one (air, glass) are left transparent.
+    pub fn render_slice(
+        &self,
+        plane: SlicePlane,
+        selection: Option<Bounds>,
+        scale: u32,
+    ) -> MapImage {
+        let bounds = selection.unwrap_or_else(|| self.get_finite_bounds());
+        let scale = scale.max(1);
+
+        let (start, size) = match plane {
+            SlicePlane::Xy { .. } => (bounds.x, bounds.width),
+            SlicePlane::Zy { .. } => (bounds.z, bounds.length),
+        };
+        let (size, height) = (size.max(0) as u32, bounds.height.max(0) as u32);
+
+        let mut img = MapImage::new(size * scale, height * scale);
+
+        for ry in 0..height {
+            let y = bounds.y + ry as i16;
+            for rh in 0..size {
+                let h = start + rh as i32;
+                let blk = match plane {
+                    SlicePlane::Xy { z } => self.get_block(h, y, z),
+                    SlicePlane::Zy { x } => self.get_block(x, y, h),
+                };
+
+                let Some(color) = blk.get_map_color() else {
+                    continue;
+                };
+                let rgb = color.get_rgb();
+
+                let top = (height - 1 - ry) * scale;
+                for py in top..top + scale {
+                    for px in rh * scale..(rh + 1) * scale {
+                        img.set_pixel(px, py, [rgb.r, rgb.g, rgb.b, 0xFF]);
+                    }
+                }
+            }
+        }
+
+        img
+    }
+}
@@ -0,0 +1,274 @@
+use crate::block::Block;
+use crate::level::chunk::{Chunk, CHUNK_LENGTH, CHUNK_SECTION_HEIGHT, CHUNK_WIDTH};
+use crate::level::chunk_section::ChunkSection;
+use crate::level::{Bounds, Coords, Level};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const LAYER_SIZE: usize = CHUNK_WIDTH as usize * CHUNK_LENGTH as usize;
+
+/// Block counts of a single chunk
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkStats {
+    pub coords: Coords,
+    pub blocks: HashMap<Block, u64>,
+    /// Every block that isn't air
+    pub total: u64,
+}
+
+/// Block census of a whole level
+///
+/// Air isn't counted, so totals are of blocks that are actually there.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LevelStats {
+    pub blocks: HashMap<Block, u64>,
+    /// Count of each block at every Y level, indexed by Y
+    pub blocks_per_y: HashMap<Block, Vec<u64>>,
+    /// Sorted by X then Z
+    pub chunks: Vec<ChunkStats>,
+    /// Block entity counts by ID
+    pub block_entities: HashMap<String, u64>,
+    /// Box around every block that isn't air, `None` when the level is empty
+    pub bounds: Option<Bounds>,
+    pub chunk_count: usize,
+    pub section_count: usize,
+}
+
+/// Everything counted in one chunk, merged into [`LevelStats`] afterwards
+struct ChunkCensus {
+    stats: ChunkStats,
+    blocks_per_y: HashMap<Block, Vec<u64>>,
+    /// Lowest and highest X, Y and Z with a block in them
+    min: (i32, i16, i32),
+    max: (i32, i16, i32),
+    sections: usize,
+}
+
+impl ChunkCensus {
+    fn add(&mut self, block: Block, count: u64) {
+        *self.stats.blocks.entry(block).or_default() += count;
+        self.stats.total += count;
+    }
+
+    fn add_at_y(&mut self, block: Block, y: i16, count: u64, height: i16) {
+        let per_y = self
+            .blocks_per_y
+            .entry(block)
+            .or_insert_with(|| vec![0; height.max(0) as usize]);
+        if let Some(n) = per_y.get_mut(y as usize) {
+            *n += count;
+        }
+    }
+
+    fn extend_bounds(&mut self, min: (i32, i16, i32), max: (i32, i16, i32)) {
+        self.min = (
+            self.min.0.min(min.0),
+            self.min.1.min(min.1),
+            self.min.2.min(min.2),
+        );
+        self.max = (
+            self.max.0.max(max.0),
+            self.max.1.max(max.1),
+            self.max.2.max(max.2),
+        );
+    }
+
+    fn count_section(&mut self, section: &ChunkSection, base_y: i16, height: i16) {
+        let (bx, bz) = (
+            self.stats.coords.x * CHUNK_WIDTH as i32,
+            self.stats.coords.z * CHUNK_LENGTH as i32,
+        );
+        let top = (base_y + CHUNK_SECTION_HEIGHT as i16).min(height);
+
+        if top <= base_y {
+            return;
+        }
+
+        // most sections underground or in the sky are a single block, no need to look at each one
+        if let Some(block) = section.get_uniform_block() {
+            if block == Block::Air {
+                return;
+            }
+
+            self.add(block, (top - base_y) as u64 * LAYER_SIZE as u64);
+            for y in base_y..top {
+                self.add_at_y(block, y, LAYER_SIZE as u64, height);
+            }
+            self.extend_bounds(
+                (bx, base_y, bz),
+                (
+                    bx + CHUNK_WIDTH as i32 - 1,
+                    top - 1,
+                    bz + CHUNK_LENGTH as i32 - 1,
+                ),
+            );
+            return;
+        }
+
+        // the palette already knows how many of each block there are, unless the top of the section is cut off
+        let whole = top - base_y == CHUNK_SECTION_HEIGHT as i16;
+        if whole {
+            for entry in section.blocks.iter_palette_entries() {
+                if entry.value != Block::Air {
+                    self.add(entry.value, entry.count as u64);
+                }
+            }
+        }
+
+        // YZX ordering
+        for (i, block) in section.blocks.iter().enumerate() {
+            let y = base_y + (i / LAYER_SIZE) as i16;
+            if *block == Block::Air || y >= top {
+                continue;
+            }
+
+            let (x, z) = (
+                bx + (i % CHUNK_WIDTH as usize) as i32,
+                bz + (i / CHUNK_WIDTH as usize % CHUNK_LENGTH as usize) as i32,
+            );
+            if !whole {
+                self.add(*block, 1);
+            }
+            self.add_at_y(*block, y, 1, height);
+            self.extend_bounds((x, y, z), (x, y, z));
+        }
+    }
+
+    fn new(coords: &Coords, chunk: &Chunk) -> ChunkCensus {
+        let mut census = ChunkCensus {
+            stats: ChunkStats {
+                coords: coords.clone(),
+                ..Default::default()
+            },
+            blocks_per_y: HashMap::new(),
+            min: (i32::MAX, i16::MAX, i32::MAX),
+            max: (i32::MIN, i16::MIN, i32::MIN),
+            sections: chunk.chunk_sections.len(),
+        };
+
+        for (i, section) in chunk.chunk_sections.iter().enumerate() {
+            census.count_section(
+                section,
+                i as i16 * CHUNK_SECTION_HEIGHT as i16,
+                chunk.height,
+            );
+        }
+
+        census
+    }
+}
+
+impl LevelStats {
+    /// Gets how many of a block there are
+    pub fn get_count(&self, block: Block) -> u64 {
+        self.blocks.get(&block).copied().unwrap_or(0)
+    }
+
+    /// Gets how many of a block there are at a Y level
+    pub fn get_count_at_y(&self, block: Block, y: i16) -> u64 {
+        self.blocks_per_y
+            .get(&block)
+            .and_then(|per_y| per_y.get(y as usize))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Gets how many blocks that aren't air there are
+    pub fn get_total(&self) -> u64 {
+        self.blocks.values().sum()
+    }
+
+    /// Gets how much of the level (not counting air) is made of the given blocks, from 0 to 1
+    pub fn get_fraction(&self, blocks: &[Block]) -> f64 {
+        let total = self.get_total();
+        if total == 0 {
+            return 0.0;
+        }
+
+        blocks.iter().map(|b| self.get_count(*b)).sum::<u64>() as f64 / total as f64
+    }
+
+    /// Gets the chunks that have at least one of a block
+    pub fn get_chunks_with(&self, block: Block) -> Vec<Coords> {
+        self.chunks
+            .iter()
+            .filter(|c| c.blocks.contains_key(&block))
+            .map(|c| c.coords.clone())
+            .collect()
+    }
+}
+
+impl Level {
+    /// Counts every block in the level, going through chunks in parallel
+    pub fn get_stats(&self) -> LevelStats {
+        let mut censuses: Vec<ChunkCensus> = self
+            .get_chunks()
+            .par_iter()
+            .map(|(coords, chunk)| ChunkCensus::new(coords, chunk))
+            .collect();
+        censuses.sort_by_key(|c| (c.stats.coords.x, c.stats.coords.z));
+
+        let mut stats = LevelStats {
+            chunk_count: censuses.len(),
+            ..Default::default()
+        };
+        let mut min = (i32::MAX, i16::MAX, i32::MAX);
+        let mut max = (i32::MIN, i16::MIN, i32::MIN);
+
+        for census in censuses {
+            stats.section_count += census.sections;
+
+            for (block, count) in &census.stats.blocks {
+                *stats.blocks.entry(*block).or_default() += count;
+            }
+
+            for (block, per_y) in census.blocks_per_y {
+                let total = stats.blocks_per_y.entry(block).or_default();
+                if total.len() < per_y.len() {
+                    total.resize(per_y.len(), 0);
+                }
+                for (y, count) in per_y.into_iter().enumerate() {
+                    total[y] += count;
+                }
+            }
+
+            if census.stats.total != 0 {
+                min = (
+                    min.0.min(census.min.0),
+                    min.1.min(census.min.1),
+                    min.2.min(census.min.2),
+                );
+                max = (
+                    max.0.max(census.max.0),
+                    max.1.max(census.max.1),
+                    max.2.max(census.max.2),
+                );
+            }
+
+            stats.chunks.push(census.stats);
+        }
+
+        for chunk in self.get_chunks().values() {
+            for block_entity in chunk.block_entities.values() {
+                *stats
+                    .block_entities
+                    .entry(block_entity.id.clone())
+                    .or_default() += 1;
+            }
+        }
+
+        if max.0 >= min.0 {
+            stats.bounds = Some(Bounds {
+                x: min.0,
+                y: min.1,
+                z: min.2,
+                width: max.0 - min.0 + 1,
+                height: max.1 - min.1 + 1,
+                length: max.2 - min.2 + 1,
+            });
+        }
+
+        stats
+    }
+}
@@ -0,0 +1,123 @@
+use crate::block::Block;
+use crate::level::chunk::{Chunk, CHUNK_LENGTH, CHUNK_WIDTH};
+use crate::level::{metadata, Coords, Level};
+use lodestone_common::types::hashmap_ext::HashMapExt;
+
+/// Chunks added on each side of a level by default
+pub const DEFAULT_SURROUNDINGS_RADIUS: i32 = 8;
+
+/// Terrain put around a finite level when converting it to an infinite format
+///
+/// Without it the level ends up as a floating island, with the game generating new terrain around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Surroundings {
+    /// Block the ground is made of, grass gets dirt under it like in Indev
+    pub ground_block: Block,
+    /// Height of the top of the ground
+    pub ground_height: i16,
+    pub water_block: Block,
+    /// Height of the top of the water, there's no water when it's not above the ground
+    pub water_height: i16,
+    /// Chunks added on each side of the level
+    pub radius: i32,
+}
+
+impl Surroundings {
+    /// Gets the surroundings from a level's environment (Indev's `SurroundingGround`/`SurroundingWater`)
+    ///
+    /// Anything the level doesn't have uses the same defaults as `write_indev`.
+    pub fn from_environment(level: &Level) -> Surroundings {
+        let height = level.get_max_block_y();
+        let env = &level.environment;
+
+        Surroundings {
+            ground_block: env.side_block.unwrap_or(Block::Grass),
+            ground_height: env.side_height.unwrap_or(height / 3),
+            // Indev stores flowing water (8), which would start spreading in an infinite world
+            water_block: match env.edge_block {
+                Some(Block::FlowingWater) | None => Block::Water,
+                Some(Block::FlowingLava) => Block::Lava,
+                Some(b) => b,
+            },
+            water_height: env.edge_height.unwrap_or(height / 2),
+            radius: DEFAULT_SURROUNDINGS_RADIUS,
+        }
+    }
+
+    /// Gets the block at a height in a surrounding column
+    pub fn get_block(&self, y: i16) -> Block {
+        if y == 0 {
+            Block::Bedrock
+        } else if y < self.ground_height - 1 {
+            match self.ground_block {
+                Block::Grass => Block::Dirt,
+                b => b,
+            }
+        } else if y < self.ground_height {
+            self.ground_block
+        } else if y < self.water_height {
+            self.water_block
+        } else {
+            Block::Air
+        }
+    }
+
+    fn fill_column(&self, chunk: &mut Chunk, x: i8, z: i8) {
+        let top = self.ground_height.max(self.water_height).min(chunk.height);
+        for y in 0..top.max(1) {
+            chunk.set_block(x, y, z, self.get_block(y));
+        }
+    }
+}
+
+impl Level {
+    /// Surrounds a finite level with ground and water, like it was in-game
+    ///
+    /// Chunks are added `radius` chunks out from the level's bounds, the columns of its own chunks that are outside of
+    /// the bounds get filled too. Every chunk is marked as populated so the game doesn't decorate them.
+    pub fn add_surroundings(&mut self, surroundings: &Surroundings) {
+        let bounds = self.get_finite_bounds();
+        let height = self.get_max_block_y();
+        let (w, l) = (CHUNK_WIDTH as i32, CHUNK_LENGTH as i32);
+
+        let min_x = bounds.x.div_euclid(w) - surroundings.radius;
+        let min_z = bounds.z.div_euclid(l) - surroundings.radius;
+        let max_x = (bounds.x + bounds.width - 1).div_euclid(w) + surroundings.radius;
+        let max_z = (bounds.z + bounds.length - 1).div_euclid(l) + surroundings.radius;
+
+        let mut template = Chunk::new(height);
+        for x in 0..CHUNK_WIDTH {
+            for z in 0..CHUNK_LENGTH {
+                surroundings.fill_column(&mut template, x, z);
+            }
+        }
+
+        let inside_x = bounds.x..bounds.x + bounds.width;
+        let inside_z = bounds.z..bounds.z + bounds.length;
+
+        for cx in min_x..=max_x {
+            for cz in min_z..=max_z {
+                let coords = Coords { x: cx, z: cz };
+                let Some(chunk) = self.get_chunk_mut(&coords) else {
+                    self.add_chunk(coords, template.clone());
+                    continue;
+                };
+
+                for x in 0..CHUNK_WIDTH {
+                    for z in 0..CHUNK_LENGTH {
+                        let (gx, gz) = (cx * w + x as i32, cz * l + z as i32);
+                        if !inside_x.contains(&gx) || !inside_z.contains(&gz) {
+                            surroundings.fill_column(chunk, x, z);
+                        }
+                    }
+                }
+            }
+        }
+
+        for chunk in self.get_chunks_mut().values_mut() {
+            chunk
+                .custom_data
+                .set_value(metadata::TERRAIN_POPULATED.to_string(), 1i8);
+        }
+    }
+}
@@ -1,20 +1,47 @@
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use lodestone_common::types::hashmap_ext::HashMapExt;
 use lodestone_common::util::McVersion;
 use lodestone_level::block::conversion::{get_internal_block_id, get_version_block_id};
 use lodestone_level::block::BlockId;
 use lodestone_level::level::chunk::{Chunk, Light, CHUNK_LENGTH, CHUNK_WIDTH};
 use lodestone_level::level::region::ChunkLocation;
-use lodestone_level::level::{Coords, Level};
+use lodestone_level::level::{metadata, Coords, Level};
 use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator, IndexedParallelIterator};
 use rayon::prelude::IntoParallelRefIterator;
-use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::io::{Cursor, Seek, SeekFrom, Write};
 
 const MCPE_ALPHA_LEVEL_CHUNK_HEIGHT: u16 = 128;
+/// chunks.dat always holds a 32x32 chunk (256x256 block) world
+pub const MCPE_ALPHA_LEVEL_CHUNKS: i32 = 32;
+/// Biome written for columns we have no biome for
+const MCPE_DEFAULT_BIOME: u8 = 1;
 
 pub trait Alpha081Level {
+    /// Reads chunks.dat from any PE alpha version, `version` picks which block ids are used
+    ///
+    /// Chunks from before 0.8 (no length in front of the data, no biomes) are read too.
     fn read_alpha081_chunks_dat(version: McVersion, data: Vec<u8>) -> Result<Level, String>;
     fn read_alpha081_chunks_dat_into_existing(&mut self, version: McVersion, data: Vec<u8>);
-    fn write_alpha081_chunks_dat(&mut self, version: McVersion) -> Vec<u8>;
+    /// Writes the level as chunks.dat, with the chunk at `origin` becoming chunk 0, 0
+    ///
+    /// Chunks that don't fit in the 32x32 chunk area are dropped and returned (in level coordinates).
+    /// Without an origin, [`Alpha081Level::get_alpha081_origin`] is used.
+    fn write_alpha081_chunks_dat(&mut self, version: McVersion, origin: Option<Coords>) -> (Vec<u8>, Vec<Coords>);
+    /// Gets the chunk that becomes 0, 0 in chunks.dat
+    ///
+    /// Levels that already fit are kept where they are, others are moved into the area or centered on it if they're too big.
+    fn get_alpha081_origin(&self) -> Coords;
+}
+
+/// Origin on one axis for chunks going from min to max
+fn get_alpha081_axis_origin(min: i32, max: i32) -> i32 {
+    if min >= 0 && max < MCPE_ALPHA_LEVEL_CHUNKS {
+        0
+    } else if max - min < MCPE_ALPHA_LEVEL_CHUNKS {
+        min
+    } else {
+        (min + max + 1).div_euclid(2) - MCPE_ALPHA_LEVEL_CHUNKS / 2
+    }
 }
 
 pub trait Alpha081Chunk {
@@ -37,14 +64,11 @@ impl Alpha081Level for Level {
             let size = c.read_u8().expect("Size in location");
             let offset = c.read_u24::<LittleEndian>().expect("Offset in location");
 
+            // 0.8 always uses 0x15 sectors, but older versions and third-party tools don't always agree so any size is taken
             if size == 0 {
                 continue;
             }
 
-            if size != 0x15 {
-                continue; /* TODO: ERROR somehow? */
-            }
-
             *l.1 = (ChunkLocation { offset, size }, Coords {x: (l.0 % 32) as i32, z: (l.0 / 32) as i32} );
         }
 
@@ -54,16 +78,39 @@ impl Alpha081Level for Level {
                 if l.0.size == 0 {
                     return None;
                 }
-                let mut c = c.clone();
-                c.set_position((l.0.offset as u64) * 0x1000 + 4);
-                
-                // Third-party tools (some PocketMine converter?) don't seem to write the minimum bytes required. Minecraft appears to ignore this, and directaxe does so too.
-                // So allocate the minimum needed by the data
-                let mut chunk_data: Vec<u8> = vec![0; 82180 - 4];
-                c.read_exact(&mut chunk_data).expect("Failed to read chunk data");
-
-                let ch = Chunk::read_alpha081(version, &chunk_data).expect("Failed to read chunk");
-                Some((l.1.clone(), ch))
+                let data = c.get_ref();
+                let start = (l.0.offset as usize) * 0x1000;
+                if start >= data.len() {
+                    log::warn!("Chunk {:?} starts past the end of chunks.dat, skipping it", l.1);
+                    return None;
+                }
+
+                // 0.8 puts the chunk length in front of the data, older worlds may not have it.
+                // Block data never looks like a valid length, so we can tell the two apart
+                let mut start = start;
+                if let Some(length) = data.get(start..start + 4) {
+                    let length = u32::from_le_bytes(length.try_into().unwrap()) as usize;
+                    if (MCPE_CHUNKS_DAT_MIN_CHUNK_LENGTH..=MCPE_CHUNKS_DAT_CHUNK_LENGTH + 4).contains(&length) {
+                        start += 4;
+                    }
+                }
+
+                // Third-party tools (some PocketMine converter?) don't seem to write the minimum bytes required, and worlds from
+                // before biomes were added don't have them at all. Minecraft appears to ignore this, and directaxe does so too.
+                let end = data.len().min(start + MCPE_CHUNKS_DAT_CHUNK_LENGTH);
+                let chunk_data = &data[start..end];
+                if chunk_data.len() < MCPE_CHUNKS_DAT_MIN_CHUNK_LENGTH {
+                    log::warn!("Chunk {:?} is only {} bytes long, skipping it", l.1, chunk_data.len());
+                    return None;
+                }
+
+                match Chunk::read_alpha081(version, chunk_data) {
+                    Ok(ch) => Some((l.1.clone(), ch)),
+                    Err(e) => {
+                        log::warn!("Failed to read chunk {:?}: {}", l.1, e);
+                        None
+                    }
+                }
             })
             .collect();
 
@@ -72,22 +119,46 @@ impl Alpha081Level for Level {
             self.add_chunk(coords, chunk);
         }
     }
-    fn write_alpha081_chunks_dat(&mut self, version: McVersion) -> Vec<u8> {
+    fn write_alpha081_chunks_dat(&mut self, version: McVersion, origin: Option<Coords>) -> (Vec<u8>, Vec<Coords>) {
+        let origin = origin.unwrap_or_else(|| self.get_alpha081_origin());
         let out: Vec<u8> = Vec::with_capacity(32* 32 * 4 + (0x1000 * 0x15 * self.get_chunk_count()));
         let mut c = Cursor::new(out);
-        // TODO: Region file says it will need a chunk writing limit, when that's done implement it here
-        for (num, (coords, chunk)) in self.get_chunks_mut().iter_mut().enumerate() {
-            /* TODO: Complain and somehow clamp coordinates if they're not in range? */
-            c.set_position((coords.x + coords.z * 32) as u64* 4);
+        let mut dropped = Vec::new();
+
+        // Sorted so the same level always gives the same file
+        let mut chunks: Vec<(&Coords, &mut Chunk)> = self.get_chunks_mut().iter_mut().collect();
+        chunks.sort_by_key(|(coords, _)| (coords.z, coords.x));
+
+        let mut num = 0;
+        for (coords, chunk) in chunks {
+            let (x, z) = (coords.x - origin.x, coords.z - origin.z);
+            if !(0..MCPE_ALPHA_LEVEL_CHUNKS).contains(&x) || !(0..MCPE_ALPHA_LEVEL_CHUNKS).contains(&z) {
+                dropped.push(coords.clone());
+                continue;
+            }
+
+            c.set_position((x + z * MCPE_ALPHA_LEVEL_CHUNKS) as u64* 4);
             c.write_u8(0x15).expect("Failed to write chunk size");
             c.write_u24::<LittleEndian>(num as u32 * 0x15 + 1).expect("Failed to write chunk offset");
             c.set_position((num as u64 * 0x15 + 1) * 0x1000);
 
             let mut ch = chunk.write_alpha081(version);
+            // Each chunk starts with its length, the reader skips over it
+            c.write_u32::<LittleEndian>(ch.len() as u32).expect("Failed to write chunk length");
             c.write_all(ch.as_mut_slice()).expect("Failed to write chunk data");
-
+            num += 1;
         }
-        c.into_inner()
+        (c.into_inner(), dropped)
+    }
+    fn get_alpha081_origin(&self) -> Coords {
+        let chunks = self.get_chunks().keys();
+        let (min_x, max_x) = chunks.clone().fold((i32::MAX, i32::MIN), |(min, max), c| (min.min(c.x), max.max(c.x)));
+        let (min_z, max_z) = chunks.fold((i32::MAX, i32::MIN), |(min, max), c| (min.min(c.z), max.max(c.z)));
+        if min_x > max_x {
+            return Coords::default();
+        }
+
+        Coords { x: get_alpha081_axis_origin(min_x, max_x), z: get_alpha081_axis_origin(min_z, max_z) }
     }
 }
 
@@ -95,7 +166,10 @@ impl Alpha081Level for Level {
 const MCPE_CHUNKS_DAT_DATA_OFFSET: usize = CHUNK_WIDTH as usize * CHUNK_LENGTH as usize * MCPE_ALPHA_LEVEL_CHUNK_HEIGHT as usize;
 const MCPE_CHUNKS_DAT_SKYLIGHT_OFFSET: usize = MCPE_CHUNKS_DAT_DATA_OFFSET + MCPE_CHUNKS_DAT_DATA_OFFSET / 2;
 const MCPE_CHUNKS_DAT_BLOCKLIGHT_OFFSET: usize = MCPE_CHUNKS_DAT_SKYLIGHT_OFFSET + MCPE_CHUNKS_DAT_DATA_OFFSET / 2;
-//const MCPE_CHUNKS_DAT_BIOME_OFFSET: usize = MCPE_CHUNKS_DAT_BLOCKLIGHT_OFFSET + MCPE_CHUNKS_DAT_DATA_OFFSET / 2;
+const MCPE_CHUNKS_DAT_BIOME_OFFSET: usize = MCPE_CHUNKS_DAT_BLOCKLIGHT_OFFSET + MCPE_CHUNKS_DAT_DATA_OFFSET / 2;
+/// Everything up to the biomes has to be there for a chunk to be readable
+const MCPE_CHUNKS_DAT_MIN_CHUNK_LENGTH: usize = MCPE_CHUNKS_DAT_BIOME_OFFSET;
+const MCPE_CHUNKS_DAT_CHUNK_LENGTH: usize = MCPE_CHUNKS_DAT_BIOME_OFFSET + CHUNK_WIDTH as usize * CHUNK_LENGTH as usize;
 
 impl Alpha081Chunk for Chunk {
     fn read_alpha081(version: McVersion, data: &[u8]) -> Result<Chunk, String> {
@@ -110,7 +184,6 @@ impl Alpha081Chunk for Chunk {
                     let meta_index  = MCPE_CHUNKS_DAT_DATA_OFFSET + block_index / 2;
                     let skylight_index  = MCPE_CHUNKS_DAT_SKYLIGHT_OFFSET + block_index / 2;
                     let blocklight_index  = MCPE_CHUNKS_DAT_BLOCKLIGHT_OFFSET + block_index / 2;
-                    //let biome_index = MCPE_CHUNKS_DAT_BIOME_OFFSET + flat_index;
 
                     let half_index = block_index % 2;
 
@@ -118,11 +191,12 @@ impl Alpha081Chunk for Chunk {
                     let block_meta = (data[meta_index] >> (4 * half_index)) & 0x0f;
                     let block_skylight = (data[skylight_index] >> (4 * half_index)) & 0x0f;
                     let block_blocklight = (data[blocklight_index] >> (4 * half_index)) & 0x0f;
-                    // TODO: Biome data when dexrn adds it.
                     if block_id != 0 {
                         c.get_or_create_chunk_section_mut(y as i16);
                         
-                        let blk = get_internal_block_id(version, &BlockId::NumericWithData(block_id as u16, block_meta as u16));
+                        // Most blocks don't have an entry per data value, fall back to the plain id
+                        let blk = get_internal_block_id(version, &BlockId::NumericWithData(block_id as u16, block_meta as u16))
+                            .or_else(|| get_internal_block_id(version, &BlockId::Numeric(block_id as u16)));
                         match blk {
                             Some(blk) => {
                                 c.set_block(x as i8, y as i16, z as i8, blk);
@@ -136,6 +210,11 @@ impl Alpha081Chunk for Chunk {
             }
                 }
 
+        // Kept per column until levels have biomes
+        if let Some(biomes) = data.get(MCPE_CHUNKS_DAT_BIOME_OFFSET..MCPE_CHUNKS_DAT_BIOME_OFFSET + CHUNK_WIDTH as usize * CHUNK_LENGTH as usize) {
+            c.custom_data.set_value(metadata::BIOMES.to_string(), biomes.to_vec());
+        }
+
         Ok(c)
     }
     fn write_alpha081(&mut self, version: McVersion) -> Vec<u8> {
@@ -147,15 +226,20 @@ impl Alpha081Chunk for Chunk {
         let mut meta = vec![vec![vec![0 as u8; MCPE_ALPHA_LEVEL_CHUNK_HEIGHT as usize / 2]; CHUNK_LENGTH as usize]; CHUNK_WIDTH as usize];
         let mut skylight = vec![vec![vec![0 as u8; MCPE_ALPHA_LEVEL_CHUNK_HEIGHT as usize / 2]; CHUNK_LENGTH as usize]; CHUNK_WIDTH as usize];
         let mut blocklight = vec![vec![vec![0 as u8; MCPE_ALPHA_LEVEL_CHUNK_HEIGHT as usize / 2]; CHUNK_LENGTH as usize]; CHUNK_WIDTH as usize];
-        let mut biome = vec![vec![0 as u8; CHUNK_LENGTH as usize]; CHUNK_WIDTH as usize];
+        let biome = self.custom_data
+            .get_value::<Vec<u8>, _>(metadata::BIOMES)
+            .filter(|b| b.len() == CHUNK_WIDTH as usize * CHUNK_LENGTH as usize)
+            .unwrap_or(vec![MCPE_DEFAULT_BIOME; CHUNK_WIDTH as usize * CHUNK_LENGTH as usize]);
         for x in 0..CHUNK_WIDTH as i8 {
             for z in 0..CHUNK_LENGTH as i8 {
                 for y in 0..MCPE_ALPHA_LEVEL_CHUNK_HEIGHT as i16 {
                     let byte_index = y % 2 * 4;
                     let blk_internal = self.get_block(x, y, z);
                     let blk = get_version_block_id(version, &blk_internal);
+                    if let BlockId::NumericWithData(_, data) = blk {
+                        meta[x as usize][z as usize][y as usize / 2] |= (data as u8 & 0x0f) << byte_index;
+                    }
                     blocks[x as usize][z as usize][y as usize] = usize::try_from(blk).unwrap_or(0) as u8;
-                    // TODO: Meta
                     skylight[x as usize][z as usize][y as usize /2] |= self.get_light(Light::SKY, x, y, z) << byte_index;
                     blocklight[x as usize][z as usize][y as usize /2] |= self.get_light(Light::BLOCK, x, y, z) << byte_index;
                 }
@@ -166,7 +250,7 @@ impl Alpha081Chunk for Chunk {
         c.write_all(&meta.into_iter().flatten().flatten().collect::<Vec::<u8>>().as_slice()).expect("Failed to write meta");
         c.write_all(&skylight.into_iter().flatten().flatten().collect::<Vec::<u8>>().as_slice()).expect("Failed to write skylight");
         c.write_all(&blocklight.into_iter().flatten().flatten().collect::<Vec::<u8>>().as_slice()).expect("Failed to write blocklight");
-        c.write_all(&biome.into_iter().flatten().collect::<Vec<u8>>().as_slice()).expect("Failed to write biome data");
+        c.write_all(biome.as_slice()).expect("Failed to write biome data");
 
         c.into_inner()
     }
@@ -0,0 +1,72 @@
+use quartz_nbt::{NbtCompound, NbtList, NbtTag};
+
+/// PE 0.8 entity IDs along with the Java ID of the same entity
+///
+/// Everything else the two share (Motion, Rotation, Health, Item, Color...) is laid out the same way.
+pub const PE_ENTITY_IDS: &[(i32, &str)] = &[
+    (10, "Chicken"),
+    (11, "Cow"),
+    (12, "Pig"),
+    (13, "Sheep"),
+    (32, "Zombie"),
+    (33, "Creeper"),
+    (34, "Skeleton"),
+    (35, "Spider"),
+    (36, "PigZombie"),
+    (64, "Item"),
+    (65, "PrimedTnt"),
+    (66, "FallingSand"),
+    (80, "Arrow"),
+    (81, "Snowball"),
+    (83, "Painting"),
+    (84, "Minecart"),
+];
+
+/// Swaps the floats of a list for doubles or the other way around
+fn convert_list(entity: &mut NbtCompound, key: &str, to_double: bool) {
+    let Ok(list) = entity.get::<_, &NbtList>(key) else {
+        return;
+    };
+
+    let list: Vec<NbtTag> = list
+        .iter()
+        .map(|v| match (v, to_double) {
+            (NbtTag::Float(v), true) => NbtTag::Double(*v as f64),
+            (NbtTag::Double(v), false) => NbtTag::Float(*v as f32),
+            (v, _) => v.clone(),
+        })
+        .collect();
+    entity.insert(key, NbtList::from(list));
+}
+
+/// Converts a PE `entities.dat` entity into a Java chunk entity, `None` if Java doesn't have it
+pub fn pe_entity_to_java(entity: &NbtCompound) -> Option<NbtCompound> {
+    let id: i32 = entity.get("id").ok()?;
+    let (_, name) = PE_ENTITY_IDS.iter().find(|(pe, _)| *pe == id)?;
+
+    let mut java = entity.clone();
+    java.insert("id", *name);
+    for key in ["Pos", "Motion"] {
+        convert_list(&mut java, key, true);
+    }
+    Some(java)
+}
+
+/// Converts a Java chunk entity into a PE `entities.dat` entity, `None` if PE doesn't have it
+///
+/// Only the pre-1.11 IDs (e.g. `PigZombie`) are known, along with the namespaced IDs that are just those in
+/// lowercase (e.g. `minecraft:pig`).
+pub fn java_entity_to_pe(entity: &NbtCompound) -> Option<NbtCompound> {
+    let name: &str = entity.get("id").ok()?;
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    let (id, _) = PE_ENTITY_IDS
+        .iter()
+        .find(|(_, java)| java.eq_ignore_ascii_case(name))?;
+
+    let mut pe = entity.clone();
+    pe.insert("id", *id);
+    for key in ["Pos", "Motion"] {
+        convert_list(&mut pe, key, false);
+    }
+    Some(pe)
+}
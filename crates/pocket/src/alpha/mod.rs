@@ -1 +1,3 @@
-pub mod alpha_081;
\ No newline at end of file
+pub mod alpha_081;
+pub mod entity;
+pub mod world;
@@ -0,0 +1,431 @@
+use crate::alpha::alpha_081::{Alpha081Level, MCPE_ALPHA_LEVEL_CHUNKS};
+use crate::alpha::entity::{java_entity_to_pe, pe_entity_to_java};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use lodestone_common::io::nbt::{self, NbtFlavor};
+use lodestone_common::types::hashmap_ext::HashMapExt;
+use lodestone_common::types::vec3i::Vec3i;
+use lodestone_common::util::McVersion;
+use lodestone_level::entity::block_entity::{BlockEntity, HasBlockEntity};
+use lodestone_level::entity::stash::{get_stashed_entities, stash_entities, EntityFormat};
+use lodestone_level::level::chunk::{CHUNK_LENGTH, CHUNK_WIDTH};
+use lodestone_level::level::{metadata, Coords, Level};
+use quartz_nbt::io::{self, Flavor};
+use quartz_nbt::{NbtCompound, NbtList, NbtTag};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+/// level.dat storage version written by 0.8.1
+pub const PE_STORAGE_VERSION: i32 = 3;
+pub const PE_ENTITIES_MAGIC: &[u8; 4] = b"ENT\0";
+pub const PE_ENTITIES_VERSION: i32 = 1;
+/// Width and length of a PE 0.8 world in blocks
+pub const PE_WORLD_SIZE: i32 = MCPE_ALPHA_LEVEL_CHUNKS * CHUNK_WIDTH as i32;
+
+pub trait PeWorldLevel {
+    /// Reads a PE 0.8 world folder made of `chunks.dat`, `level.dat` and `entities.dat`
+    ///
+    /// `entities.dat` is optional, worlds without any mobs or tile entities don't have one.
+    fn read_pe_world_dir(version: McVersion, path: &Path) -> Result<Level, String>;
+    /// Writes `chunks.dat`, `level.dat` and `entities.dat` into the given folder
+    fn write_pe_world_dir(&mut self, version: McVersion, path: &Path) -> Result<(), String>;
+
+    fn read_pe_level_dat(data: Vec<u8>) -> Result<Level, String>;
+    /// Reads a PE level.dat into an existing level
+    ///
+    /// The player is kept as-is and only written back out to PE, the Java level.dat writers don't write one yet.
+    fn read_pe_level_dat_into_existing(&mut self, data: Vec<u8>) -> Result<(), String>;
+    fn write_pe_level_dat(&self) -> Vec<u8>;
+
+    /// Reads the entities and tile entities of a PE entities.dat into a level that already has its chunks
+    ///
+    /// Entities are kept as-is for PE, the ones Java has too are also converted and kept in their chunks for the
+    /// Java chunk writers.
+    fn read_pe_entities_dat_into_existing(&mut self, data: Vec<u8>) -> Result<(), String>;
+    /// Writes entities.dat, Java chunk entities are converted when the level didn't come from PE
+    fn write_pe_entities_dat(&self) -> Vec<u8>;
+}
+
+/// Reads the 8 byte header (version + length) and the LE NBT following it
+fn read_pe_file(c: &mut Cursor<Vec<u8>>) -> Result<(i32, NbtCompound), String> {
+    let version = c.read_i32::<LittleEndian>().map_err(|e| e.to_string())?;
+    let length = c.read_i32::<LittleEndian>().map_err(|e| e.to_string())?;
+
+    let mut data = vec![0u8; length.max(0) as usize];
+    c.read_exact(&mut data).map_err(|e| e.to_string())?;
+
+    let (root, _) = nbt::read_nbt(&mut Cursor::new(data), NbtFlavor::LittleEndian)?;
+    Ok((version, root))
+}
+
+fn write_pe_file(out: &mut Vec<u8>, version: i32, root: &NbtCompound) {
+    let mut data = Vec::new();
+    nbt::write_nbt(&mut data, "", root, NbtFlavor::LittleEndian);
+
+    out.write_i32::<LittleEndian>(version)
+        .expect("Failed to write version");
+    out.write_i32::<LittleEndian>(data.len() as i32)
+        .expect("Failed to write length");
+    out.extend_from_slice(&data);
+}
+
+/// Keeps a tag around as raw uncompressed NBT until we have a proper entity/player model
+fn store_raw(level: &mut Level, key: &str, name: &str, tag: NbtTag) {
+    let mut root = NbtCompound::new();
+    root.insert(name, tag);
+
+    let mut raw = Vec::new();
+    io::write_nbt(&mut raw, None, &root, Flavor::Uncompressed).expect("Raw NBT data");
+    level.custom_data.set_value(key.to_string(), raw);
+}
+
+fn load_raw(level: &Level, key: &str, name: &str) -> Option<NbtTag> {
+    level
+        .custom_data
+        .get_value::<Vec<u8>, _>(key)
+        .and_then(|raw| io::read_nbt(&mut Cursor::new(raw), Flavor::Uncompressed).ok())
+        .and_then(|(root, _)| root.get::<_, &NbtTag>(name).ok().cloned())
+}
+
+/// Moves an entity's `Pos` by a block offset
+fn shift_pos(entity: &mut NbtCompound, offset: (i32, i32)) {
+    if let Ok(pos) = entity.get_mut::<_, &mut NbtList>("Pos") {
+        for (i, delta) in [(0, offset.0), (2, offset.1)] {
+            match pos.get_mut::<&mut NbtTag>(i) {
+                Ok(NbtTag::Float(v)) => *v += delta as f32,
+                Ok(NbtTag::Double(v)) => *v += delta as f64,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Gets the X and Z of the block an entity is in
+fn get_block_pos(entity: &NbtCompound) -> Option<(i32, i32)> {
+    let pos = entity.get::<_, &NbtList>("Pos").ok()?;
+    let get = |i| match pos.get::<&NbtTag>(i).ok()? {
+        NbtTag::Float(v) => Some(v.floor() as i32),
+        NbtTag::Double(v) => Some(v.floor() as i32),
+        _ => None,
+    };
+
+    Some((get(0)?, get(2)?))
+}
+
+/// Writes level.dat with the spawn and player moved by a block offset
+fn write_level_dat(level: &Level, offset: (i32, i32)) -> Vec<u8> {
+    let mut root = NbtCompound::new();
+
+    let i32_value = |key: &str, default: i32| {
+        level
+            .custom_data
+            .get_value::<i32, &str>(key)
+            .unwrap_or(default)
+    };
+    let i64_value = |key: &str, default: i64| {
+        level
+            .custom_data
+            .get_value::<i64, &str>(key)
+            .unwrap_or(default)
+    };
+
+    root.insert(metadata::GAME_TYPE, i32_value(metadata::GAME_TYPE, 0));
+    root.insert(metadata::LAST_PLAYED, i64_value(metadata::LAST_PLAYED, 0));
+    root.insert(metadata::LEVEL_NAME, level.name.as_str());
+    root.insert(metadata::PLATFORM, i32_value(metadata::PLATFORM, 2));
+    root.insert(metadata::RANDOM_SEED, i64_value(metadata::RANDOM_SEED, 0));
+    root.insert(metadata::SIZE_ON_DISK, i64_value(metadata::SIZE_ON_DISK, 0));
+    root.insert(metadata::SPAWN_X, level.spawn.x + offset.0);
+    root.insert(metadata::SPAWN_Y, level.spawn.y);
+    root.insert(metadata::SPAWN_Z, level.spawn.z + offset.1);
+    root.insert(metadata::STORAGE_VERSION, PE_STORAGE_VERSION);
+    root.insert(metadata::TIME, level.time);
+    root.insert(
+        metadata::DAY_CYCLE_STOP_TIME,
+        i32_value(metadata::DAY_CYCLE_STOP_TIME, -1),
+    );
+    root.insert(
+        metadata::SPAWN_MOBS,
+        level
+            .custom_data
+            .get_value::<bool, &str>(metadata::SPAWN_MOBS)
+            .unwrap_or(true),
+    );
+
+    if let Some(NbtTag::Compound(mut player)) = load_raw(level, metadata::PE_PLAYER, "Player") {
+        shift_pos(&mut player, offset);
+        root.insert("Player", player);
+    }
+
+    let mut out = Vec::new();
+    write_pe_file(
+        &mut out,
+        i32_value(metadata::STORAGE_VERSION, PE_STORAGE_VERSION),
+        &root,
+    );
+    out
+}
+
+/// Writes entities.dat with everything moved by a block offset
+///
+/// Tile entities that end up outside of the world are dropped.
+fn write_entities_dat(level: &Level, offset: (i32, i32)) -> Vec<u8> {
+    let mut tile_entities = NbtList::new();
+    for chunk in level.get_chunks().values() {
+        for block_entity in chunk.block_entities.values() {
+            let mut nbt = match &block_entity.data {
+                HasBlockEntity::NotFound(_, data) => data.clone(),
+                HasBlockEntity::Found(_) => block_entity
+                    .to_nbt(McVersion::Alpha1_2_6)
+                    .unwrap_or_default(),
+            };
+
+            let (x, z) = (block_entity.x + offset.0, block_entity.z + offset.1);
+            if !(0..PE_WORLD_SIZE).contains(&x) || !(0..PE_WORLD_SIZE).contains(&z) {
+                continue;
+            }
+
+            nbt.insert("id", &block_entity.id);
+            nbt.insert("x", x);
+            nbt.insert("y", block_entity.y);
+            nbt.insert("z", z);
+            tile_entities.push(nbt);
+        }
+    }
+
+    // levels that didn't come from PE get the entities PE has out of their Java chunks
+    let mut pe_entities = get_stashed_entities(&level.custom_data, EntityFormat::PocketAlpha);
+    if pe_entities.is_empty() {
+        for chunk in level.get_chunks().values() {
+            for entity in get_stashed_entities(&chunk.custom_data, EntityFormat::Java).iter() {
+                if let NbtTag::Compound(entity) = entity
+                    && let Some(entity) = java_entity_to_pe(entity)
+                {
+                    pe_entities.push(entity);
+                }
+            }
+        }
+    }
+
+    let mut entities = NbtList::new();
+    for entity in pe_entities {
+        if let NbtTag::Compound(mut entity) = entity {
+            shift_pos(&mut entity, offset);
+            if get_block_pos(&entity).is_some_and(|(x, z)| {
+                (0..PE_WORLD_SIZE).contains(&x) && (0..PE_WORLD_SIZE).contains(&z)
+            }) {
+                entities.push(entity);
+            }
+        }
+    }
+
+    let mut root = NbtCompound::new();
+    root.insert(metadata::ENTITIES, entities);
+    root.insert(metadata::TILE_ENTITIES, tile_entities);
+
+    let mut out = PE_ENTITIES_MAGIC.to_vec();
+    write_pe_file(&mut out, PE_ENTITIES_VERSION, &root);
+    out
+}
+
+impl PeWorldLevel for Level {
+    fn read_pe_world_dir(version: McVersion, path: &Path) -> Result<Level, String> {
+        let level_dat = path.join("level.dat");
+        if !level_dat.is_file() {
+            return Err("level.dat does not exist or is not a file!".to_string());
+        }
+
+        let chunks_dat = path.join("chunks.dat");
+        if !chunks_dat.is_file() {
+            return Err("chunks.dat does not exist or is not a file!".to_string());
+        }
+
+        log::debug!("Reading level.dat");
+        let mut lvl = Self::read_pe_level_dat(fs::read(level_dat).map_err(|e| e.to_string())?)?;
+
+        log::debug!("Reading chunks.dat");
+        lvl.read_alpha081_chunks_dat_into_existing(
+            version,
+            fs::read(chunks_dat).map_err(|e| e.to_string())?,
+        );
+
+        let entities_dat = path.join("entities.dat");
+        if entities_dat.is_file() {
+            log::debug!("Reading entities.dat");
+            lvl.read_pe_entities_dat_into_existing(
+                fs::read(entities_dat).map_err(|e| e.to_string())?,
+            )?;
+        }
+
+        Ok(lvl)
+    }
+
+    fn write_pe_world_dir(&mut self, version: McVersion, path: &Path) -> Result<(), String> {
+        fs::create_dir_all(path).map_err(|e| e.to_string())?;
+
+        // spawn, mobs and tile entities get moved along with the chunks
+        let origin = self.get_alpha081_origin();
+        let offset = (
+            -origin.x * CHUNK_WIDTH as i32,
+            -origin.z * CHUNK_LENGTH as i32,
+        );
+
+        let (chunks, dropped) = self.write_alpha081_chunks_dat(version, Some(origin));
+        for coords in dropped {
+            log::warn!(
+                "Chunk {:?} doesn't fit in the PE world, dropping it",
+                coords
+            );
+        }
+
+        fs::write(path.join("chunks.dat"), chunks).map_err(|e| e.to_string())?;
+        fs::write(path.join("level.dat"), write_level_dat(self, offset))
+            .map_err(|e| e.to_string())?;
+        fs::write(path.join("entities.dat"), write_entities_dat(self, offset))
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    fn read_pe_level_dat(data: Vec<u8>) -> Result<Level, String> {
+        let mut lvl = Level::new();
+        lvl.read_pe_level_dat_into_existing(data)?;
+        Ok(lvl)
+    }
+
+    fn read_pe_level_dat_into_existing(&mut self, data: Vec<u8>) -> Result<(), String> {
+        let (storage_version, root) = read_pe_file(&mut Cursor::new(data))?;
+
+        if let Ok(name) = root.get::<_, &str>(metadata::LEVEL_NAME) {
+            self.name = name.to_string();
+        }
+        self.time = root.get(metadata::TIME).unwrap_or(0);
+        self.set_spawn_point(
+            root.get(metadata::SPAWN_X).unwrap_or(0),
+            root.get(metadata::SPAWN_Y).unwrap_or(64),
+            root.get(metadata::SPAWN_Z).unwrap_or(0),
+        );
+
+        self.custom_data
+            .set_value::<i32>(metadata::STORAGE_VERSION.to_string(), storage_version);
+        for key in [
+            metadata::GAME_TYPE,
+            metadata::PLATFORM,
+            metadata::DAY_CYCLE_STOP_TIME,
+        ] {
+            if let Ok(value) = root.get::<_, i32>(key) {
+                self.custom_data.set_value::<i32>(key.to_string(), value);
+            }
+        }
+        for key in [
+            metadata::RANDOM_SEED,
+            metadata::LAST_PLAYED,
+            metadata::SIZE_ON_DISK,
+        ] {
+            if let Ok(value) = root.get::<_, i64>(key) {
+                self.custom_data.set_value::<i64>(key.to_string(), value);
+            }
+        }
+        if let Ok(spawn_mobs) = root.get::<_, bool>(metadata::SPAWN_MOBS) {
+            self.custom_data
+                .set_value::<bool>(metadata::SPAWN_MOBS.to_string(), spawn_mobs);
+        }
+
+        if let Ok(player) = root.get::<_, &NbtCompound>("Player") {
+            store_raw(
+                self,
+                metadata::PE_PLAYER,
+                "Player",
+                NbtTag::Compound(player.clone()),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn write_pe_level_dat(&self) -> Vec<u8> {
+        write_level_dat(self, (0, 0))
+    }
+
+    fn read_pe_entities_dat_into_existing(&mut self, data: Vec<u8>) -> Result<(), String> {
+        let mut c = Cursor::new(data);
+
+        let mut magic = [0u8; 4];
+        c.read_exact(&mut magic).map_err(|e| e.to_string())?;
+        if &magic != PE_ENTITIES_MAGIC {
+            return Err("entities.dat is missing its ENT header!".to_string());
+        }
+
+        let (_, root) = read_pe_file(&mut c)?;
+
+        if let Ok(entities) = root.get::<_, &NbtList>(metadata::ENTITIES) {
+            stash_entities(&mut self.custom_data, EntityFormat::PocketAlpha, entities);
+
+            // the ones Java has too also go into their chunks, for the Java chunk writers
+            let mut java_entities: HashMap<Coords, NbtList> = HashMap::new();
+            for entity in entities.iter() {
+                let NbtTag::Compound(entity) = entity else {
+                    continue;
+                };
+                let Some(entity) = pe_entity_to_java(entity) else {
+                    continue;
+                };
+                let Some((x, z)) = get_block_pos(&entity) else {
+                    continue;
+                };
+
+                java_entities
+                    .entry(Coords {
+                        x: x.div_euclid(CHUNK_WIDTH as i32),
+                        z: z.div_euclid(CHUNK_LENGTH as i32),
+                    })
+                    .or_default()
+                    .push(entity);
+            }
+
+            for (coords, entities) in java_entities {
+                if let Some(chunk) = self.get_chunk_mut(&coords) {
+                    stash_entities(&mut chunk.custom_data, EntityFormat::Java, &entities);
+                }
+            }
+        }
+
+        if let Ok(tile_entities) = root.get::<_, &NbtList>(metadata::TILE_ENTITIES) {
+            for tile_entity in tile_entities.iter() {
+                let NbtTag::Compound(data) = tile_entity else {
+                    continue;
+                };
+                let (Ok(id), Ok(x), Ok(y), Ok(z)) = (
+                    data.get::<_, &str>("id"),
+                    data.get::<_, i32>("x"),
+                    data.get::<_, i32>("y"),
+                    data.get::<_, i32>("z"),
+                ) else {
+                    continue;
+                };
+
+                // PE tile entity ids and tags match Java's alpha ones, so they're kept whole
+                let block_entity = BlockEntity {
+                    id: id.to_string(),
+                    x,
+                    y,
+                    z,
+                    components: Default::default(),
+                    keep_packed: false,
+                    data: HasBlockEntity::NotFound(id.to_string(), data.clone()),
+                };
+
+                if let Some(c) = self.get_chunk_by_block_coords_mut(x, z) {
+                    c.add_block_entity(Vec3i::new(x, y, z), block_entity);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_pe_entities_dat(&self) -> Vec<u8> {
+        write_entities_dat(self, (0, 0))
+    }
+}
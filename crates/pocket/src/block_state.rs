@@ -0,0 +1,112 @@
+use lodestone_common::util::McVersion;
+use lodestone_level::block::conversion::{
+    format_block_state, get_internal_block_id_by_name, get_named_block, parse_block_state,
+    set_named_block,
+};
+use lodestone_level::level::Level;
+use quartz_nbt::{NbtCompound, NbtList, NbtTag};
+use std::collections::BTreeMap;
+
+/// Block state version written in palettes, 1.18.10
+pub const BLOCK_STATE_VERSION: i32 = 17959425;
+
+/// Java's waterlogged property, Bedrock puts water in the second block layer instead
+pub(crate) const WATERLOGGED: &str = "waterlogged";
+
+/// Bedrock block states are typed, bools are stored as bytes
+fn read_state_value(tag: &NbtTag) -> Option<String> {
+    match tag {
+        NbtTag::Byte(0) => Some("false".to_string()),
+        NbtTag::Byte(1) => Some("true".to_string()),
+        NbtTag::Byte(v) => Some(v.to_string()),
+        NbtTag::Int(v) => Some(v.to_string()),
+        NbtTag::String(v) => Some(v.clone()),
+        _ => None,
+    }
+}
+
+fn write_state_value(value: &str) -> NbtTag {
+    match value {
+        "false" => NbtTag::Byte(0),
+        "true" => NbtTag::Byte(1),
+        v => match v.parse::<i32>() {
+            Ok(v) => NbtTag::Int(v),
+            Err(_) => NbtTag::String(v.to_string()),
+        },
+    }
+}
+
+pub(crate) fn read_palette_entry(state: &NbtCompound) -> (String, BTreeMap<String, String>) {
+    let name: &str = state.get("name").unwrap_or("minecraft:air");
+    let states = state
+        .get::<_, &NbtCompound>("states")
+        .map(|s| {
+            s.inner()
+                .iter()
+                .filter_map(|(k, v)| Some((k.clone(), read_state_value(v)?)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    (name.to_string(), states)
+}
+
+pub(crate) fn read_palette(palette: &NbtList) -> Vec<(String, BTreeMap<String, String>)> {
+    palette
+        .iter()
+        .map(|state| match state {
+            NbtTag::Compound(state) => read_palette_entry(state),
+            _ => ("minecraft:air".to_string(), BTreeMap::new()),
+        })
+        .collect()
+}
+
+pub(crate) fn write_palette_entry(name: &str, states: &BTreeMap<String, String>) -> NbtCompound {
+    let mut tag = NbtCompound::new();
+    tag.insert("name", name);
+
+    let mut states_tag = NbtCompound::new();
+    for (k, v) in states {
+        states_tag.insert(k.clone(), write_state_value(v));
+    }
+    tag.insert("states", states_tag);
+    tag.insert("version", BLOCK_STATE_VERSION);
+
+    tag
+}
+
+/// Sets a block from a Bedrock palette entry
+///
+/// Known blocks are registered together with their states, anything else is kept as a custom block.
+pub(crate) fn set_bedrock_block(
+    level: &mut Level,
+    version: McVersion,
+    x: i32,
+    y: i16,
+    z: i32,
+    name: &str,
+    states: &BTreeMap<String, String>,
+) {
+    let state = format_block_state(name, states);
+    match get_internal_block_id_by_name(version, &state) {
+        Some(_) => set_named_block(level, version, x, y, z, &state, &BTreeMap::new()),
+        None => set_named_block(level, version, x, y, z, name, states),
+    }
+}
+
+/// Gets a block as a Bedrock name and states, along with whether it's waterlogged
+pub(crate) fn get_bedrock_block(
+    level: &Level,
+    version: McVersion,
+    x: i32,
+    y: i16,
+    z: i32,
+) -> (String, BTreeMap<String, String>, bool) {
+    let (name, mut properties) = get_named_block(level, version, x, y, z);
+    let waterlogged = properties.remove(WATERLOGGED).as_deref() == Some("true");
+
+    let (name, mut states) = parse_block_state(&name);
+    states.extend(properties);
+
+    (name.to_string(), states, waterlogged)
+}
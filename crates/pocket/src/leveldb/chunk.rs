@@ -0,0 +1,627 @@
+use crate::alpha::alpha_081::Alpha081Chunk;
+use crate::alpha::world::PeWorldLevel;
+use crate::block_state::{
+    get_bedrock_block, read_palette_entry, set_bedrock_block, write_palette_entry, WATERLOGGED,
+};
+use crate::leveldb::table::Compression;
+use crate::leveldb::LevelDb;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use lodestone_common::io::nbt::{self, NbtFlavor};
+use lodestone_common::types::hashmap_ext::HashMapExt;
+use lodestone_common::types::vec3i::Vec3i;
+use lodestone_common::util::McVersion;
+use lodestone_level::block::conversion::{format_block_state, get_internal_block_id};
+use lodestone_level::block::{Block, BlockId};
+use lodestone_level::entity::block_entity::{BlockEntity, HasBlockEntity};
+use lodestone_level::entity::stash::{get_stashed_entities, stash_entities, EntityFormat};
+use lodestone_level::level::chunk::{Chunk, CHUNK_LENGTH, CHUNK_SECTION_HEIGHT, CHUNK_WIDTH};
+use lodestone_level::level::{metadata, Coords, Level};
+use quartz_nbt::{NbtList, NbtTag};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+// Chunk record tags, the last byte of a chunk key (before the sub-chunk index)
+pub const TAG_DATA_3D: u8 = 0x2B;
+pub const TAG_VERSION: u8 = 0x2C;
+pub const TAG_DATA_2D: u8 = 0x2D;
+pub const TAG_SUB_CHUNK_PREFIX: u8 = 0x2F;
+pub const TAG_LEGACY_TERRAIN: u8 = 0x30;
+pub const TAG_BLOCK_ENTITY: u8 = 0x31;
+pub const TAG_ENTITY: u8 = 0x32;
+pub const TAG_FINALIZED_STATE: u8 = 0x36;
+pub const TAG_LEGACY_VERSION: u8 = 0x76;
+
+/// Chunk version written with `LegacyTerrain`, 0.9.0
+pub const LEGACY_CHUNK_VERSION: u8 = 2;
+/// Chunk version written with sub-chunks, 1.18.0
+pub const SUB_CHUNK_CHUNK_VERSION: u8 = 39;
+/// Sub-chunk format with a storage count, the v9 one adds the sub-chunk's y index
+pub const SUB_CHUNK_VERSION: u8 = 8;
+/// Fully generated and populated
+pub const FINALIZED_STATE_DONE: i32 = 2;
+
+/// level.dat storage versions the game expects for each terrain layout
+pub const LEGACY_STORAGE_VERSION: i32 = 4;
+pub const SUB_CHUNK_STORAGE_VERSION: i32 = 9;
+
+/// Blocks, data, sky light, block light, height map and biome colours
+pub const LEGACY_TERRAIN_LENGTH: usize = 83200;
+const LEGACY_TERRAIN_LIGHT_END: usize = 81920;
+/// Each column's biome colour is the biome ID followed by its RGB colour
+const LEGACY_TERRAIN_BIOMES_START: usize = LEGACY_TERRAIN_LIGHT_END + COLUMNS;
+const COLUMNS: usize = CHUNK_WIDTH as usize * CHUNK_LENGTH as usize;
+pub const LEGACY_TERRAIN_HEIGHT: i16 = 128;
+pub const SUB_CHUNK_WORLD_HEIGHT: i16 = 320;
+
+const SUB_CHUNK_SIZE: usize = 4096;
+const PALETTE_BITS: [u8; 8] = [1, 2, 3, 4, 5, 6, 8, 16];
+
+pub trait LevelDbLevel {
+    /// Reads a LevelDB world folder (`level.dat` and `db/`)
+    ///
+    /// `LegacyTerrain` chunks are read as numeric ids (capped at the newest PE alpha version),
+    /// sub-chunk palettes by their Bedrock names. Only the overworld is read, sub-chunks below y=0 are dropped.
+    fn read_leveldb_world_dir(version: McVersion, path: &Path) -> Result<Level, String>;
+    /// Writes a LevelDB world folder
+    ///
+    /// Versions before Bedrock 1.18.10 get `LegacyTerrain` chunks, newer ones get sub-chunks.
+    fn write_leveldb_world_dir(&mut self, version: McVersion, path: &Path) -> Result<(), String>;
+
+    fn read_leveldb_chunks(version: McVersion, db: &LevelDb) -> Result<Level, String>;
+    fn read_leveldb_chunks_into_existing(
+        &mut self,
+        version: McVersion,
+        db: &LevelDb,
+    ) -> Result<(), String>;
+    fn write_leveldb_chunks(&mut self, version: McVersion, db: &mut LevelDb);
+}
+
+pub fn chunk_key(coords: &Coords, tag: u8, sub_chunk: Option<i8>) -> Vec<u8> {
+    let mut key = Vec::with_capacity(10);
+    key.extend_from_slice(&coords.x.to_le_bytes());
+    key.extend_from_slice(&coords.z.to_le_bytes());
+    key.push(tag);
+    if let Some(y) = sub_chunk {
+        key.push(y as u8);
+    }
+    key
+}
+
+/// Splits an overworld chunk key into its coordinates, tag and sub-chunk index
+pub fn parse_chunk_key(key: &[u8]) -> Option<(Coords, u8, Option<i8>)> {
+    if key.len() != 9 && key.len() != 10 {
+        return None;
+    }
+
+    let coords = Coords {
+        x: i32::from_le_bytes(key[0..4].try_into().ok()?),
+        z: i32::from_le_bytes(key[4..8].try_into().ok()?),
+    };
+    Some((coords, key[8], key.get(9).map(|y| *y as i8)))
+}
+
+fn is_name_version(version: McVersion) -> bool {
+    version >= McVersion::Bedrock1_18_10
+}
+
+/// Index of a block inside a sub-chunk, columns are stored y first
+fn sub_chunk_index(x: usize, y: usize, z: usize) -> usize {
+    (x << 8) | (z << 4) | y
+}
+
+struct BlockStorage {
+    indices: Vec<u16>,
+    palette: Vec<(String, BTreeMap<String, String>)>,
+}
+
+fn read_block_storage(c: &mut Cursor<&[u8]>) -> Result<BlockStorage, String> {
+    let header = c.read_u8().map_err(|e| e.to_string())?;
+    if header & 1 != 0 {
+        return Err("Runtime id palettes can't be read from disk".to_string());
+    }
+
+    let bits = (header >> 1) as usize;
+    if bits != 0 && !PALETTE_BITS.contains(&(bits as u8)) {
+        return Err(format!(
+            "Unknown block storage size of {} bits per block",
+            bits
+        ));
+    }
+
+    let mut indices = vec![0u16; SUB_CHUNK_SIZE];
+    // 0 bits means every block is the first palette entry
+    if let Some(per_word) = 32usize.checked_div(bits) {
+        let mask = (1u32 << bits) - 1;
+
+        for word_index in 0..SUB_CHUNK_SIZE.div_ceil(per_word) {
+            let word = c.read_u32::<LittleEndian>().map_err(|e| e.to_string())?;
+            for i in 0..per_word {
+                let Some(index) = indices.get_mut(word_index * per_word + i) else {
+                    break;
+                };
+                *index = ((word >> (i * bits)) & mask) as u16;
+            }
+        }
+    }
+
+    let count = c.read_i32::<LittleEndian>().map_err(|e| e.to_string())?;
+    let mut palette = Vec::with_capacity(count.max(0) as usize);
+    for _ in 0..count {
+        let (state, _) = nbt::read_nbt(c, NbtFlavor::LittleEndian)?;
+        palette.push(read_palette_entry(&state));
+    }
+
+    Ok(BlockStorage { indices, palette })
+}
+
+fn write_block_storage(out: &mut Vec<u8>, storage: &BlockStorage) {
+    let bits = PALETTE_BITS
+        .iter()
+        .map(|b| *b as usize)
+        .find(|b| (1usize << b) >= storage.palette.len())
+        .unwrap_or(16);
+    let per_word = 32 / bits;
+
+    out.push((bits << 1) as u8);
+    for word in storage.indices.chunks(per_word) {
+        let word = word
+            .iter()
+            .enumerate()
+            .fold(0u32, |w, (i, index)| w | ((*index as u32) << (i * bits)));
+        out.write_u32::<LittleEndian>(word)
+            .expect("Failed to write block storage");
+    }
+
+    out.write_i32::<LittleEndian>(storage.palette.len() as i32)
+        .expect("Failed to write palette size");
+    for (name, states) in &storage.palette {
+        nbt::write_nbt(
+            out,
+            "",
+            &write_palette_entry(name, states),
+            NbtFlavor::LittleEndian,
+        );
+    }
+}
+
+/// Reads a sub-chunk record into block storages, older versions are numeric ids and data
+fn read_sub_chunk(
+    level: &mut Level,
+    version: McVersion,
+    coords: &Coords,
+    y_index: i8,
+    data: &[u8],
+) -> Result<(), String> {
+    let mut c = Cursor::new(data);
+    let sub_chunk_version = c.read_u8().map_err(|e| e.to_string())?;
+
+    let mut y_index = y_index;
+    let storages = match sub_chunk_version {
+        1 => 1,
+        8 => c.read_u8().map_err(|e| e.to_string())?,
+        9 => {
+            let count = c.read_u8().map_err(|e| e.to_string())?;
+            y_index = c.read_i8().map_err(|e| e.to_string())?;
+            count
+        }
+        0 | 2..=7 => return read_legacy_sub_chunk(level, version, coords, y_index, &data[1..]),
+        v => return Err(format!("Unknown sub-chunk version {}", v)),
+    };
+
+    if y_index < 0 {
+        log::warn!("Dropping sub-chunk below y=0 in chunk {:?}", coords);
+        return Ok(());
+    }
+
+    let mut layers = Vec::with_capacity(storages as usize);
+    for _ in 0..storages {
+        layers.push(read_block_storage(&mut c)?);
+    }
+
+    let base_y = y_index as i32 * CHUNK_SECTION_HEIGHT as i32;
+    for x in 0..CHUNK_WIDTH as usize {
+        for z in 0..CHUNK_LENGTH as usize {
+            for y in 0..CHUNK_SECTION_HEIGHT as usize {
+                let i = sub_chunk_index(x, y, z);
+                let (wx, wy, wz) = (
+                    coords.x * CHUNK_WIDTH as i32 + x as i32,
+                    (base_y + y as i32) as i16,
+                    coords.z * CHUNK_LENGTH as i32 + z as i32,
+                );
+
+                let Some(blocks) = layers.first() else {
+                    return Ok(());
+                };
+                let Some((name, states)) = blocks.palette.get(blocks.indices[i] as usize) else {
+                    continue;
+                };
+                if name == "minecraft:air" {
+                    continue;
+                }
+                set_bedrock_block(level, version, wx, wy, wz, name, states);
+
+                let waterlogged = layers
+                    .get(1)
+                    .and_then(|l| l.palette.get(l.indices[i] as usize))
+                    .is_some_and(|(name, _)| {
+                        name == "minecraft:water" || name == "minecraft:flowing_water"
+                    });
+                if waterlogged && level.get_block(wx, wy, wz) != Block::Air {
+                    level.set_data(wx, wy, wz, WATERLOGGED.to_string(), "true".to_string());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Pre-1.2.13 sub-chunks, 4096 block ids followed by 2048 bytes of data nibbles
+fn read_legacy_sub_chunk(
+    level: &mut Level,
+    version: McVersion,
+    coords: &Coords,
+    y_index: i8,
+    data: &[u8],
+) -> Result<(), String> {
+    if data.len() < SUB_CHUNK_SIZE + SUB_CHUNK_SIZE / 2 {
+        return Err("Sub-chunk is too short".to_string());
+    }
+    if y_index < 0 {
+        return Ok(());
+    }
+
+    let version = version.min(McVersion::MCPEAlpha0_8_1);
+    let base_y = y_index as i32 * CHUNK_SECTION_HEIGHT as i32;
+    for x in 0..CHUNK_WIDTH as usize {
+        for z in 0..CHUNK_LENGTH as usize {
+            for y in 0..CHUNK_SECTION_HEIGHT as usize {
+                let i = sub_chunk_index(x, y, z);
+                let id = data[i] as u16;
+                if id == 0 {
+                    continue;
+                }
+                let meta = ((data[SUB_CHUNK_SIZE + i / 2] >> (4 * (i % 2))) & 0x0f) as u16;
+
+                let blk = get_internal_block_id(version, &BlockId::NumericWithData(id, meta))
+                    .or_else(|| get_internal_block_id(version, &BlockId::Numeric(id)));
+                if let Some(blk) = blk {
+                    level.set_block(
+                        coords.x * CHUNK_WIDTH as i32 + x as i32,
+                        (base_y + y as i32) as i16,
+                        coords.z * CHUNK_LENGTH as i32 + z as i32,
+                        blk,
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_sub_chunk(
+    level: &Level,
+    version: McVersion,
+    coords: &Coords,
+    y_index: i8,
+) -> Option<Vec<u8>> {
+    let mut blocks = BlockStorage {
+        indices: vec![0; SUB_CHUNK_SIZE],
+        palette: vec![("minecraft:air".to_string(), BTreeMap::new())],
+    };
+    let mut liquids = BlockStorage {
+        indices: vec![0; SUB_CHUNK_SIZE],
+        palette: vec![("minecraft:air".to_string(), BTreeMap::new())],
+    };
+    let mut lookup: HashMap<String, u16> = HashMap::new();
+    let mut has_water = false;
+
+    let base_y = y_index as i32 * CHUNK_SECTION_HEIGHT as i32;
+    for x in 0..CHUNK_WIDTH as usize {
+        for z in 0..CHUNK_LENGTH as usize {
+            for y in 0..CHUNK_SECTION_HEIGHT as usize {
+                let (wx, wy, wz) = (
+                    coords.x * CHUNK_WIDTH as i32 + x as i32,
+                    (base_y + y as i32) as i16,
+                    coords.z * CHUNK_LENGTH as i32 + z as i32,
+                );
+                if level.get_block(wx, wy, wz) == Block::Air {
+                    continue;
+                }
+
+                let (name, states, waterlogged) = get_bedrock_block(level, version, wx, wy, wz);
+                let i = sub_chunk_index(x, y, z);
+                blocks.indices[i] = *lookup
+                    .entry(format_block_state(&name, &states))
+                    .or_insert_with(|| {
+                        blocks.palette.push((name, states));
+                        blocks.palette.len() as u16 - 1
+                    });
+
+                if waterlogged {
+                    if !has_water {
+                        liquids.palette.push((
+                            "minecraft:water".to_string(),
+                            BTreeMap::from([("liquid_depth".to_string(), "0".to_string())]),
+                        ));
+                        has_water = true;
+                    }
+                    liquids.indices[i] = 1;
+                }
+            }
+        }
+    }
+
+    if blocks.palette.len() == 1 {
+        return None;
+    }
+
+    let mut out = vec![SUB_CHUNK_VERSION, if has_water { 2 } else { 1 }];
+    write_block_storage(&mut out, &blocks);
+    if has_water {
+        write_block_storage(&mut out, &liquids);
+    }
+    Some(out)
+}
+
+fn read_block_entities(level: &mut Level, data: &[u8]) -> Result<(), String> {
+    let mut c = Cursor::new(data);
+
+    while (c.position() as usize) < data.len() {
+        let (nbt, _) = nbt::read_nbt(&mut c, NbtFlavor::LittleEndian)?;
+        let (Ok(id), Ok(x), Ok(y), Ok(z)) = (
+            nbt.get::<_, &str>("id"),
+            nbt.get::<_, i32>("x"),
+            nbt.get::<_, i32>("y"),
+            nbt.get::<_, i32>("z"),
+        ) else {
+            continue;
+        };
+
+        let block_entity = BlockEntity {
+            id: id.to_string(),
+            x,
+            y,
+            z,
+            components: Default::default(),
+            keep_packed: false,
+            data: HasBlockEntity::NotFound(id.to_string(), nbt.clone()),
+        };
+
+        if let Some(chunk) = level.get_chunk_by_block_coords_mut(x, z) {
+            chunk.add_block_entity(Vec3i::new(x, y, z), block_entity);
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a chunk's Entity record, which is just one little-endian compound after another
+fn read_entities(data: &[u8]) -> Result<NbtList, String> {
+    let mut c = Cursor::new(data);
+    let mut entities = NbtList::new();
+
+    while (c.position() as usize) < data.len() {
+        let (nbt, _) = nbt::read_nbt(&mut c, NbtFlavor::LittleEndian)?;
+        entities.push(nbt);
+    }
+
+    Ok(entities)
+}
+
+fn write_entities(entities: &NbtList) -> Vec<u8> {
+    let mut out = Vec::new();
+
+    for entity in entities.iter() {
+        if let NbtTag::Compound(entity) = entity {
+            nbt::write_nbt(&mut out, "", entity, NbtFlavor::LittleEndian);
+        }
+    }
+
+    out
+}
+
+fn write_block_entities(chunk: &Chunk) -> Vec<u8> {
+    let mut out = Vec::new();
+
+    for block_entity in chunk.block_entities.values() {
+        let mut nbt = match &block_entity.data {
+            HasBlockEntity::NotFound(_, data) => data.clone(),
+            HasBlockEntity::Found(_) => block_entity
+                .to_nbt(McVersion::Alpha1_2_6)
+                .unwrap_or_default(),
+        };
+
+        nbt.insert("id", &block_entity.id);
+        nbt.insert("x", block_entity.x);
+        nbt.insert("y", block_entity.y);
+        nbt.insert("z", block_entity.z);
+        nbt::write_nbt(&mut out, "", &nbt, NbtFlavor::LittleEndian);
+    }
+
+    out
+}
+
+impl LevelDbLevel for Level {
+    fn read_leveldb_world_dir(version: McVersion, path: &Path) -> Result<Level, String> {
+        let mut lvl = match fs::read(path.join("level.dat")) {
+            Ok(data) => Level::read_pe_level_dat(data)?,
+            Err(_) => Level::new(),
+        };
+
+        let db = LevelDb::read_dir(&path.join("db"))?;
+        lvl.read_leveldb_chunks_into_existing(version, &db)?;
+
+        Ok(lvl)
+    }
+
+    fn write_leveldb_world_dir(&mut self, version: McVersion, path: &Path) -> Result<(), String> {
+        fs::create_dir_all(path).map_err(|e| e.to_string())?;
+
+        let mut db = LevelDb::new();
+        self.write_leveldb_chunks(version, &mut db);
+
+        let compression = match is_name_version(version) {
+            true => Compression::ZlibRaw,
+            false => Compression::Zlib,
+        };
+        db.write_dir(&path.join("db"), compression)?;
+
+        // worlds converted from older saves have a storage version the game won't open
+        let min_storage_version = match is_name_version(version) {
+            true => SUB_CHUNK_STORAGE_VERSION,
+            false => LEGACY_STORAGE_VERSION,
+        };
+        let storage_version = self
+            .custom_data
+            .get_value::<i32, &str>(metadata::STORAGE_VERSION)
+            .unwrap_or(0)
+            .max(min_storage_version);
+
+        let mut level_dat = self.write_pe_level_dat();
+        level_dat[..4].copy_from_slice(&storage_version.to_le_bytes());
+        fs::write(path.join("level.dat"), level_dat).map_err(|e| e.to_string())?;
+        fs::write(path.join("levelname.txt"), &self.name).map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    fn read_leveldb_chunks(version: McVersion, db: &LevelDb) -> Result<Level, String> {
+        let mut lvl = Level::new();
+        lvl.read_leveldb_chunks_into_existing(version, db)?;
+        Ok(lvl)
+    }
+
+    fn read_leveldb_chunks_into_existing(
+        &mut self,
+        version: McVersion,
+        db: &LevelDb,
+    ) -> Result<(), String> {
+        let mut block_entities = Vec::new();
+
+        for (key, value) in db.entries.iter() {
+            let Some((coords, tag, sub_chunk)) = parse_chunk_key(key) else {
+                continue;
+            };
+
+            match (tag, sub_chunk) {
+                (TAG_LEGACY_TERRAIN, None) => {
+                    if value.len() < LEGACY_TERRAIN_LENGTH {
+                        return Err(format!("LegacyTerrain in chunk {:?} is too short", coords));
+                    }
+
+                    let mut chunk =
+                        Chunk::read_alpha081(version.min(McVersion::MCPEAlpha0_8_1), value)?;
+                    // The chunks.dat biomes sit where LegacyTerrain has its height map
+                    let biomes: Vec<u8> = value[LEGACY_TERRAIN_BIOMES_START..LEGACY_TERRAIN_LENGTH]
+                        .chunks_exact(4)
+                        .map(|colour| colour[0])
+                        .collect();
+                    chunk
+                        .custom_data
+                        .set_value(metadata::BIOMES.to_string(), biomes);
+                    self.add_chunk(coords, chunk);
+                }
+                (TAG_SUB_CHUNK_PREFIX, Some(y)) => {
+                    self.add_chunk(coords.clone(), Chunk::new(SUB_CHUNK_WORLD_HEIGHT));
+                    read_sub_chunk(
+                        self,
+                        version.max(McVersion::Bedrock1_18_10),
+                        &coords,
+                        y,
+                        value,
+                    )?;
+                }
+                (TAG_BLOCK_ENTITY, None) => block_entities.push(value),
+                (TAG_ENTITY, None) => {
+                    if let Some(chunk) = self.get_chunk_mut(&coords) {
+                        let entities = read_entities(value)?;
+                        stash_entities(
+                            &mut chunk.custom_data,
+                            EntityFormat::BedrockChunk,
+                            &entities,
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // block entities go in last, their chunk has to exist first
+        for data in block_entities {
+            read_block_entities(self, data)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_leveldb_chunks(&mut self, version: McVersion, db: &mut LevelDb) {
+        let mut coords: Vec<Coords> = self.get_chunks().keys().cloned().collect();
+        coords.sort_by_key(|c| (c.x, c.z));
+
+        for coords in coords {
+            if is_name_version(version) {
+                db.put(
+                    chunk_key(&coords, TAG_VERSION, None),
+                    vec![SUB_CHUNK_CHUNK_VERSION],
+                );
+                db.put(
+                    chunk_key(&coords, TAG_FINALIZED_STATE, None),
+                    FINALIZED_STATE_DONE.to_le_bytes().to_vec(),
+                );
+
+                let sections = self
+                    .get_chunk(&coords)
+                    .map_or(0, |c| c.chunk_sections.len());
+                for y in 0..sections.min(i8::MAX as usize) as i8 {
+                    if let Some(sub_chunk) = write_sub_chunk(self, version, &coords, y) {
+                        db.put(chunk_key(&coords, TAG_SUB_CHUNK_PREFIX, Some(y)), sub_chunk);
+                    }
+                }
+            } else {
+                let chunk = self.get_chunk_mut(&coords).unwrap();
+                let mut terrain = chunk.write_alpha081(version.min(McVersion::MCPEAlpha0_8_1));
+                terrain.truncate(LEGACY_TERRAIN_LIGHT_END);
+                terrain.extend(
+                    chunk
+                        .get_heightmap()
+                        .iter()
+                        .map(|h| (*h).clamp(0, u8::MAX as i16) as u8),
+                );
+                // Columns without a biome get plains, the game recolours them all on load anyway
+                let biomes = chunk
+                    .custom_data
+                    .get_value::<Vec<u8>, _>(metadata::BIOMES)
+                    .filter(|b| b.len() == COLUMNS)
+                    .unwrap_or(vec![1; COLUMNS]);
+                for biome in biomes {
+                    terrain.extend_from_slice(&[biome, 0x8D, 0xB3, 0x60]);
+                }
+
+                db.put(
+                    chunk_key(&coords, TAG_LEGACY_VERSION, None),
+                    vec![LEGACY_CHUNK_VERSION],
+                );
+                db.put(chunk_key(&coords, TAG_LEGACY_TERRAIN, None), terrain);
+            }
+
+            let chunk = self.get_chunk(&coords).unwrap();
+            if !chunk.block_entities.is_empty() {
+                db.put(
+                    chunk_key(&coords, TAG_BLOCK_ENTITY, None),
+                    write_block_entities(chunk),
+                );
+            }
+            let entities = get_stashed_entities(&chunk.custom_data, EntityFormat::BedrockChunk);
+            if !entities.is_empty() {
+                db.put(
+                    chunk_key(&coords, TAG_ENTITY, None),
+                    write_entities(&entities),
+                );
+            }
+        }
+    }
+}
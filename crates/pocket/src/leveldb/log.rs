@@ -0,0 +1,156 @@
+use crate::leveldb::{crc32c, mask_crc, read_slice, write_slice, TYPE_DELETION, TYPE_VALUE};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Cursor, Read};
+
+pub const LOG_BLOCK_SIZE: usize = 32768;
+/// CRC, length and record type
+pub const LOG_HEADER_LENGTH: usize = 7;
+
+const RECORD_ZERO: u8 = 0;
+const RECORD_FULL: u8 = 1;
+const RECORD_FIRST: u8 = 2;
+const RECORD_MIDDLE: u8 = 3;
+const RECORD_LAST: u8 = 4;
+
+/// Reads the records of a log (or MANIFEST) file, joining records that were split over blocks
+pub fn read_log_records(data: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    let mut records = Vec::new();
+    let mut record: Vec<u8> = Vec::new();
+    let mut pos = 0;
+
+    while pos + LOG_HEADER_LENGTH <= data.len() {
+        let block_left = LOG_BLOCK_SIZE - pos % LOG_BLOCK_SIZE;
+        if block_left < LOG_HEADER_LENGTH {
+            // trailer padding
+            pos += block_left;
+            continue;
+        }
+
+        let length = u16::from_le_bytes([data[pos + 4], data[pos + 5]]) as usize;
+        let kind = data[pos + 6];
+        let start = pos + LOG_HEADER_LENGTH;
+        if start + length > data.len() {
+            // the game was probably closed mid-write
+            break;
+        }
+
+        let fragment = &data[start..start + length];
+        let crc = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+        let mut crc_data = vec![kind];
+        crc_data.extend_from_slice(fragment);
+        // zero records are padding from preallocated files, they don't have a CRC
+        if kind != RECORD_ZERO && mask_crc(crc32c(&crc_data)) != crc {
+            // same as above, whatever comes after a torn write can't be trusted either
+            log::warn!("Log record CRC doesn't match, skipping the rest of the log");
+            break;
+        }
+        pos = start + length;
+
+        match kind {
+            RECORD_ZERO => continue,
+            RECORD_FULL => records.push(fragment.to_vec()),
+            RECORD_FIRST => record = fragment.to_vec(),
+            RECORD_MIDDLE => record.extend_from_slice(fragment),
+            RECORD_LAST => {
+                record.extend_from_slice(fragment);
+                records.push(std::mem::take(&mut record));
+            }
+            kind => return Err(format!("Unknown log record type {}", kind)),
+        }
+    }
+
+    Ok(records)
+}
+
+pub fn write_log_records(records: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = Vec::new();
+
+    for record in records {
+        let mut left: &[u8] = record;
+        let mut first = true;
+
+        loop {
+            let block_left = LOG_BLOCK_SIZE - out.len() % LOG_BLOCK_SIZE;
+            if block_left < LOG_HEADER_LENGTH {
+                out.resize(out.len() + block_left, 0);
+                continue;
+            }
+
+            let length = left.len().min(block_left - LOG_HEADER_LENGTH);
+            let last = length == left.len();
+            let kind = match (first, last) {
+                (true, true) => RECORD_FULL,
+                (true, false) => RECORD_FIRST,
+                (false, false) => RECORD_MIDDLE,
+                (false, true) => RECORD_LAST,
+            };
+
+            let (fragment, rest) = left.split_at(length);
+            let mut crc_data = vec![kind];
+            crc_data.extend_from_slice(fragment);
+
+            out.write_u32::<LittleEndian>(mask_crc(crc32c(&crc_data)))
+                .expect("Failed to write record CRC");
+            out.write_u16::<LittleEndian>(length as u16)
+                .expect("Failed to write record length");
+            out.push(kind);
+            out.extend_from_slice(fragment);
+
+            left = rest;
+            first = false;
+            if last {
+                break;
+            }
+        }
+    }
+
+    out
+}
+
+/// A put, or a deletion when there's no value
+pub type BatchOp = (Vec<u8>, Option<Vec<u8>>);
+
+/// Reads a write batch log record along with its starting sequence number
+pub fn read_write_batch(data: &[u8]) -> Result<(u64, Vec<BatchOp>), String> {
+    let mut c = Cursor::new(data);
+    let seq = c.read_u64::<LittleEndian>().map_err(|e| e.to_string())?;
+    let count = c.read_u32::<LittleEndian>().map_err(|e| e.to_string())?;
+
+    let mut ops = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let mut kind = [0u8];
+        c.read_exact(&mut kind).map_err(|e| e.to_string())?;
+
+        match kind[0] {
+            TYPE_VALUE => ops.push((read_slice(&mut c)?, Some(read_slice(&mut c)?))),
+            TYPE_DELETION => ops.push((read_slice(&mut c)?, None)),
+            kind => return Err(format!("Unknown write batch entry type {}", kind)),
+        }
+    }
+
+    Ok((seq, ops))
+}
+
+pub fn write_write_batch(seq: u64, ops: &[BatchOp]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.write_u64::<LittleEndian>(seq)
+        .expect("Failed to write sequence");
+    out.write_u32::<LittleEndian>(ops.len() as u32)
+        .expect("Failed to write count");
+
+    for (key, value) in ops {
+        match value {
+            Some(value) => {
+                out.push(TYPE_VALUE);
+                write_slice(&mut out, key);
+                write_slice(&mut out, value);
+            }
+            None => {
+                out.push(TYPE_DELETION);
+                write_slice(&mut out, key);
+            }
+        }
+    }
+
+    out
+}
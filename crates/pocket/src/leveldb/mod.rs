@@ -0,0 +1,343 @@
+//! A small LevelDB implementation covering what Mojang's fork needs for world saves.
+//!
+//! The whole database is read into memory. Writing always produces a fresh, fully compacted
+//! database made of a single table, which the game is happy to open and compact further itself.
+
+pub mod chunk;
+pub mod log;
+pub mod table;
+
+use crate::leveldb::log::{read_log_records, read_write_batch, write_log_records};
+use crate::leveldb::table::{read_table, write_table, Compression, Entry};
+use lodestone_common::io::{read_var_u64, write_var_u64};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+
+pub const COMPARATOR_NAME: &str = "leveldb.BytewiseComparator";
+
+/// Internal key value types
+pub const TYPE_DELETION: u8 = 0;
+pub const TYPE_VALUE: u8 = 1;
+
+// Version edit tags as found in the MANIFEST
+const TAG_COMPARATOR: u32 = 1;
+const TAG_LOG_NUMBER: u32 = 2;
+const TAG_NEXT_FILE_NUMBER: u32 = 3;
+const TAG_LAST_SEQUENCE: u32 = 4;
+const TAG_COMPACT_POINTER: u32 = 5;
+const TAG_DELETED_FILE: u32 = 6;
+const TAG_NEW_FILE: u32 = 7;
+const TAG_PREV_LOG_NUMBER: u32 = 9;
+
+#[derive(Default, Clone)]
+pub struct LevelDb {
+    pub entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl LevelDb {
+    pub fn new() -> LevelDb {
+        LevelDb::default()
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&Vec<u8>> {
+        self.entries.get(key)
+    }
+
+    pub fn put(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.entries.insert(key, value);
+    }
+
+    pub fn delete(&mut self, key: &[u8]) {
+        self.entries.remove(key);
+    }
+
+    /// Reads every live table and log of the database in the given folder
+    pub fn read_dir(path: &Path) -> Result<LevelDb, String> {
+        let current = fs::read_to_string(path.join("CURRENT"))
+            .map_err(|e| format!("Could not read CURRENT: {}", e))?;
+        let manifest = fs::read(path.join(current.trim()))
+            .map_err(|e| format!("Could not read {}: {}", current.trim(), e))?;
+
+        let mut tables: HashSet<u64> = HashSet::new();
+        let mut log_number = 0;
+        let mut prev_log_number = 0;
+        for record in read_log_records(&manifest)? {
+            let edit = read_version_edit(&record)?;
+            if let Some(n) = edit.log_number {
+                log_number = n;
+            }
+            if let Some(n) = edit.prev_log_number {
+                prev_log_number = n;
+            }
+            for n in edit.deleted_files {
+                tables.remove(&n);
+            }
+            for file in edit.new_files {
+                tables.insert(file.number);
+            }
+        }
+
+        // user key -> (sequence, value), deletions are kept as None so older values stay hidden
+        let mut merged: HashMap<Vec<u8>, (u64, Option<Vec<u8>>)> = HashMap::new();
+        let mut merge = |key: Vec<u8>, seq: u64, value: Option<Vec<u8>>| {
+            let entry = merged.entry(key).or_insert((seq, None));
+            if seq >= entry.0 {
+                *entry = (seq, value);
+            }
+        };
+
+        for number in tables {
+            let data = [format!("{:06}.ldb", number), format!("{:06}.sst", number)]
+                .iter()
+                .find_map(|name| fs::read(path.join(name)).ok())
+                .ok_or(format!("Table {:06} is missing!", number))?;
+
+            for (internal_key, value) in read_table(&data)? {
+                let (key, seq, kind) = split_internal_key(&internal_key)?;
+                merge(key.to_vec(), seq, (kind == TYPE_VALUE).then_some(value));
+            }
+        }
+
+        let mut logs: Vec<u64> = fs::read_dir(path)
+            .map_err(|e| e.to_string())?
+            .filter_map(|e| e.ok()?.file_name().into_string().ok())
+            .filter_map(|name| name.strip_suffix(".log")?.parse::<u64>().ok())
+            .filter(|n| *n >= log_number || *n == prev_log_number)
+            .collect();
+        logs.sort();
+
+        for number in logs {
+            let data =
+                fs::read(path.join(format!("{:06}.log", number))).map_err(|e| e.to_string())?;
+            for record in read_log_records(&data)? {
+                let (seq, ops) = read_write_batch(&record)?;
+                for (i, (key, value)) in ops.into_iter().enumerate() {
+                    merge(key, seq + i as u64, value);
+                }
+            }
+        }
+
+        Ok(LevelDb {
+            entries: merged
+                .into_iter()
+                .filter_map(|(k, (_, v))| Some((k, v?)))
+                .collect(),
+        })
+    }
+
+    /// Writes the database as a single table into the given folder
+    ///
+    /// Any database files already in the folder are removed first, otherwise LevelDB would replay old logs on top.
+    pub fn write_dir(&self, path: &Path, compression: Compression) -> Result<(), String> {
+        fs::create_dir_all(path).map_err(|e| e.to_string())?;
+
+        for entry in fs::read_dir(path).map_err(|e| e.to_string())? {
+            let entry = entry.map_err(|e| e.to_string())?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == "CURRENT"
+                || name.starts_with("MANIFEST-")
+                || name.ends_with(".ldb")
+                || name.ends_with(".sst")
+                || name.ends_with(".log")
+            {
+                fs::remove_file(entry.path()).map_err(|e| e.to_string())?;
+            }
+        }
+
+        let entries: Vec<Entry> = self
+            .entries
+            .iter()
+            .map(|(k, v)| (make_internal_key(k, 0, TYPE_VALUE), v.clone()))
+            .collect();
+
+        const TABLE_NUMBER: u64 = 2;
+        const LOG_NUMBER: u64 = 3;
+        const MANIFEST_NUMBER: u64 = 4;
+
+        let mut edit = VersionEdit {
+            comparator: Some(COMPARATOR_NAME.to_string()),
+            log_number: Some(LOG_NUMBER),
+            next_file_number: Some(MANIFEST_NUMBER + 1),
+            last_sequence: Some(0),
+            ..Default::default()
+        };
+
+        if !entries.is_empty() {
+            let table = write_table(&entries, compression);
+            fs::write(path.join(format!("{:06}.ldb", TABLE_NUMBER)), &table)
+                .map_err(|e| e.to_string())?;
+
+            edit.new_files.push(FileMetaData {
+                level: 0,
+                number: TABLE_NUMBER,
+                size: table.len() as u64,
+                smallest: entries.first().unwrap().0.clone(),
+                largest: entries.last().unwrap().0.clone(),
+            });
+        }
+
+        fs::write(path.join(format!("{:06}.log", LOG_NUMBER)), []).map_err(|e| e.to_string())?;
+
+        let manifest = format!("MANIFEST-{:06}", MANIFEST_NUMBER);
+        fs::write(
+            path.join(&manifest),
+            write_log_records(&[write_version_edit(&edit)]),
+        )
+        .map_err(|e| e.to_string())?;
+        fs::write(path.join("CURRENT"), format!("{}\n", manifest)).map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct FileMetaData {
+    pub level: u32,
+    pub number: u64,
+    pub size: u64,
+    pub smallest: Vec<u8>,
+    pub largest: Vec<u8>,
+}
+
+#[derive(Default)]
+pub struct VersionEdit {
+    pub comparator: Option<String>,
+    pub log_number: Option<u64>,
+    pub prev_log_number: Option<u64>,
+    pub next_file_number: Option<u64>,
+    pub last_sequence: Option<u64>,
+    pub deleted_files: Vec<u64>,
+    pub new_files: Vec<FileMetaData>,
+}
+
+pub fn read_version_edit(data: &[u8]) -> Result<VersionEdit, String> {
+    let mut c = Cursor::new(data);
+    let mut edit = VersionEdit::default();
+
+    while (c.position() as usize) < data.len() {
+        match read_var_u64(&mut c, 10)? as u32 {
+            TAG_COMPARATOR => {
+                edit.comparator = Some(String::from_utf8_lossy(&read_slice(&mut c)?).to_string())
+            }
+            TAG_LOG_NUMBER => edit.log_number = Some(read_var_u64(&mut c, 10)?),
+            TAG_PREV_LOG_NUMBER => edit.prev_log_number = Some(read_var_u64(&mut c, 10)?),
+            TAG_NEXT_FILE_NUMBER => edit.next_file_number = Some(read_var_u64(&mut c, 10)?),
+            TAG_LAST_SEQUENCE => edit.last_sequence = Some(read_var_u64(&mut c, 10)?),
+            TAG_COMPACT_POINTER => {
+                read_var_u64(&mut c, 10)?;
+                read_slice(&mut c)?;
+            }
+            TAG_DELETED_FILE => {
+                read_var_u64(&mut c, 10)?;
+                edit.deleted_files.push(read_var_u64(&mut c, 10)?);
+            }
+            TAG_NEW_FILE => edit.new_files.push(FileMetaData {
+                level: read_var_u64(&mut c, 10)? as u32,
+                number: read_var_u64(&mut c, 10)?,
+                size: read_var_u64(&mut c, 10)?,
+                smallest: read_slice(&mut c)?,
+                largest: read_slice(&mut c)?,
+            }),
+            tag => return Err(format!("Unknown version edit tag {}", tag)),
+        }
+    }
+
+    Ok(edit)
+}
+
+pub fn write_version_edit(edit: &VersionEdit) -> Vec<u8> {
+    let mut out = Vec::new();
+
+    if let Some(comparator) = &edit.comparator {
+        write_var_u64(&mut out, TAG_COMPARATOR as u64);
+        write_slice(&mut out, comparator.as_bytes());
+    }
+    for (tag, value) in [
+        (TAG_LOG_NUMBER, edit.log_number),
+        (TAG_PREV_LOG_NUMBER, edit.prev_log_number),
+        (TAG_NEXT_FILE_NUMBER, edit.next_file_number),
+        (TAG_LAST_SEQUENCE, edit.last_sequence),
+    ] {
+        if let Some(value) = value {
+            write_var_u64(&mut out, tag as u64);
+            write_var_u64(&mut out, value);
+        }
+    }
+    for number in &edit.deleted_files {
+        write_var_u64(&mut out, TAG_DELETED_FILE as u64);
+        write_var_u64(&mut out, 0);
+        write_var_u64(&mut out, *number);
+    }
+    for file in &edit.new_files {
+        write_var_u64(&mut out, TAG_NEW_FILE as u64);
+        write_var_u64(&mut out, file.level as u64);
+        write_var_u64(&mut out, file.number);
+        write_var_u64(&mut out, file.size);
+        write_slice(&mut out, &file.smallest);
+        write_slice(&mut out, &file.largest);
+    }
+
+    out
+}
+
+/// Appends the 8 byte (sequence << 8 | type) trailer LevelDB stores with every key
+pub fn make_internal_key(key: &[u8], seq: u64, kind: u8) -> Vec<u8> {
+    let mut out = key.to_vec();
+    out.extend_from_slice(&((seq << 8) | kind as u64).to_le_bytes());
+    out
+}
+
+pub fn split_internal_key(key: &[u8]) -> Result<(&[u8], u64, u8), String> {
+    if key.len() < 8 {
+        return Err("Internal key is too short".to_string());
+    }
+
+    let (key, trailer) = key.split_at(key.len() - 8);
+    let trailer = u64::from_le_bytes(trailer.try_into().unwrap());
+    Ok((key, trailer >> 8, trailer as u8))
+}
+
+/// Reads a varint length prefixed byte string
+pub(crate) fn read_slice<R: Read>(r: &mut R) -> Result<Vec<u8>, String> {
+    let mut data = vec![0u8; read_var_u64(r, 10)? as usize];
+    r.read_exact(&mut data).map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+pub(crate) fn write_slice<W: Write>(w: &mut W, data: &[u8]) {
+    write_var_u64(w, data.len() as u64);
+    w.write_all(data).expect("Slice");
+}
+
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x82F63B78
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+pub fn crc32c(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, b| {
+        CRC32C_TABLE[((crc ^ *b as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// LevelDB stores CRCs masked, as CRCs of data that itself contains CRCs are weak
+pub fn mask_crc(crc: u32) -> u32 {
+    crc.rotate_right(15).wrapping_add(0xa282ead8)
+}
@@ -0,0 +1,311 @@
+use crate::leveldb::{crc32c, mask_crc};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use flate2::read::{DeflateDecoder, ZlibDecoder};
+use flate2::write::{DeflateEncoder, ZlibEncoder};
+use lodestone_common::io::{read_var_u64, write_var_u64};
+use std::io::{Cursor, Read, Write};
+
+pub const TABLE_MAGIC: u64 = 0xdb4775248b80fb57;
+pub const FOOTER_LENGTH: usize = 48;
+/// Block type byte and CRC
+pub const BLOCK_TRAILER_LENGTH: usize = 5;
+
+/// Uncompressed size we try to keep data blocks under
+const BLOCK_SIZE: usize = 4096;
+const RESTART_INTERVAL: usize = 16;
+
+/// Block compression types, Mojang's fork adds the two zlib ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Compression {
+    None = 0,
+    Snappy = 1,
+    Zlib = 2,
+    ZlibRaw = 4,
+}
+
+impl TryFrom<u8> for Compression {
+    type Error = String;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Snappy),
+            2 => Ok(Compression::Zlib),
+            4 => Ok(Compression::ZlibRaw),
+            v => Err(format!("Unknown block compression {}", v)),
+        }
+    }
+}
+
+/// A key and value, keys being internal keys
+pub type Entry = (Vec<u8>, Vec<u8>);
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlockHandle {
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl BlockHandle {
+    fn read<R: Read>(r: &mut R) -> Result<BlockHandle, String> {
+        Ok(BlockHandle {
+            offset: read_var_u64(r, 10)?,
+            size: read_var_u64(r, 10)?,
+        })
+    }
+
+    fn write<W: Write>(&self, w: &mut W) {
+        write_var_u64(w, self.offset);
+        write_var_u64(w, self.size);
+    }
+}
+
+/// Reads all entries of a table, keys are internal keys
+pub fn read_table(data: &[u8]) -> Result<Vec<Entry>, String> {
+    if data.len() < FOOTER_LENGTH {
+        return Err("Table is too small".to_string());
+    }
+
+    let mut footer = Cursor::new(&data[data.len() - FOOTER_LENGTH..]);
+    let _metaindex = BlockHandle::read(&mut footer)?;
+    let index = BlockHandle::read(&mut footer)?;
+
+    footer.set_position((FOOTER_LENGTH - 8) as u64);
+    if footer
+        .read_u64::<LittleEndian>()
+        .map_err(|e| e.to_string())?
+        != TABLE_MAGIC
+    {
+        return Err("Table has a bad magic number".to_string());
+    }
+
+    let mut entries = Vec::new();
+    for (_, handle) in read_block(&read_block_contents(data, index)?)? {
+        let handle = BlockHandle::read(&mut Cursor::new(handle))?;
+        entries.extend(read_block(&read_block_contents(data, handle)?)?);
+    }
+
+    Ok(entries)
+}
+
+/// Writes a table from entries sorted by internal key
+pub fn write_table(entries: &[Entry], compression: Compression) -> Vec<u8> {
+    let mut out = Vec::new();
+
+    let mut index = BlockBuilder::new(1);
+    let mut block = BlockBuilder::new(RESTART_INTERVAL);
+    let mut last_key: &[u8] = &[];
+
+    for (key, value) in entries {
+        block.add(key, value);
+        last_key = key;
+
+        if block.size() >= BLOCK_SIZE {
+            let handle = write_block(&mut out, &block.finish(), compression);
+            index.add(last_key, &handle);
+            block = BlockBuilder::new(RESTART_INTERVAL);
+        }
+    }
+
+    if !block.is_empty() {
+        let handle = write_block(&mut out, &block.finish(), compression);
+        index.add(last_key, &handle);
+    }
+
+    let metaindex = write_block_raw(&mut out, &BlockBuilder::new(1).finish(), compression);
+    let index = write_block_raw(&mut out, &index.finish(), compression);
+
+    let mut footer = Vec::with_capacity(FOOTER_LENGTH);
+    metaindex.write(&mut footer);
+    index.write(&mut footer);
+    footer.resize(FOOTER_LENGTH - 8, 0);
+    footer
+        .write_u64::<LittleEndian>(TABLE_MAGIC)
+        .expect("Failed to write table magic");
+    out.extend_from_slice(&footer);
+
+    out
+}
+
+fn read_block_contents(data: &[u8], handle: BlockHandle) -> Result<Vec<u8>, String> {
+    let start = handle.offset as usize;
+    let Some(end) = start
+        .checked_add(handle.size as usize)
+        .filter(|end| end.saturating_add(BLOCK_TRAILER_LENGTH) <= data.len())
+    else {
+        return Err("Block goes past the end of the table".to_string());
+    };
+
+    let contents = &data[start..end];
+    let kind = data[end];
+    let crc = u32::from_le_bytes(
+        data[end + 1..end + BLOCK_TRAILER_LENGTH]
+            .try_into()
+            .unwrap(),
+    );
+    let mut crc_data = contents.to_vec();
+    crc_data.push(kind);
+    if mask_crc(crc32c(&crc_data)) != crc {
+        return Err("Block CRC doesn't match its contents".to_string());
+    }
+
+    match Compression::try_from(kind)? {
+        Compression::None => Ok(contents.to_vec()),
+        Compression::Zlib => {
+            let mut out = Vec::new();
+            ZlibDecoder::new(contents)
+                .read_to_end(&mut out)
+                .map_err(|e| e.to_string())?;
+            Ok(out)
+        }
+        Compression::ZlibRaw => {
+            let mut out = Vec::new();
+            DeflateDecoder::new(contents)
+                .read_to_end(&mut out)
+                .map_err(|e| e.to_string())?;
+            Ok(out)
+        }
+        Compression::Snappy => Err("Snappy compressed blocks are not supported".to_string()),
+    }
+}
+
+/// Parses the prefix compressed entries of a block
+fn read_block(block: &[u8]) -> Result<Vec<Entry>, String> {
+    if block.len() < 4 {
+        return Err("Block is too small".to_string());
+    }
+
+    let restarts = u32::from_le_bytes(block[block.len() - 4..].try_into().unwrap()) as usize;
+    let end = block
+        .len()
+        .checked_sub(4 + restarts * 4)
+        .ok_or("Block has too many restarts")?;
+
+    let mut c = Cursor::new(&block[..end]);
+    let mut entries = Vec::new();
+    let mut key: Vec<u8> = Vec::new();
+
+    while (c.position() as usize) < end {
+        let shared = read_var_u64(&mut c, 10)? as usize;
+        let non_shared = read_var_u64(&mut c, 10)? as usize;
+        let value_length = read_var_u64(&mut c, 10)? as usize;
+
+        key.truncate(shared);
+        let mut rest = vec![0u8; non_shared];
+        c.read_exact(&mut rest).map_err(|e| e.to_string())?;
+        key.extend_from_slice(&rest);
+
+        let mut value = vec![0u8; value_length];
+        c.read_exact(&mut value).map_err(|e| e.to_string())?;
+
+        entries.push((key.clone(), value));
+    }
+
+    Ok(entries)
+}
+
+fn write_block(out: &mut Vec<u8>, block: &[u8], compression: Compression) -> Vec<u8> {
+    let mut handle = Vec::new();
+    write_block_raw(out, block, compression).write(&mut handle);
+    handle
+}
+
+fn write_block_raw(out: &mut Vec<u8>, block: &[u8], compression: Compression) -> BlockHandle {
+    let contents = match compression {
+        Compression::Zlib => {
+            let mut e = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            e.write_all(block).expect("Failed to compress block");
+            e.finish().expect("Failed to compress block")
+        }
+        Compression::ZlibRaw => {
+            let mut e = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            e.write_all(block).expect("Failed to compress block");
+            e.finish().expect("Failed to compress block")
+        }
+        // we can't write snappy, so these get stored as-is
+        Compression::None | Compression::Snappy => block.to_vec(),
+    };
+    let kind = match compression {
+        Compression::Snappy => Compression::None,
+        c => c,
+    } as u8;
+
+    let handle = BlockHandle {
+        offset: out.len() as u64,
+        size: contents.len() as u64,
+    };
+
+    out.extend_from_slice(&contents);
+    out.push(kind);
+
+    let mut crc_data = contents;
+    crc_data.push(kind);
+    out.write_u32::<LittleEndian>(mask_crc(crc32c(&crc_data)))
+        .expect("Failed to write block CRC");
+
+    handle
+}
+
+struct BlockBuilder {
+    data: Vec<u8>,
+    restarts: Vec<u32>,
+    restart_interval: usize,
+    counter: usize,
+    last_key: Vec<u8>,
+}
+
+impl BlockBuilder {
+    fn new(restart_interval: usize) -> BlockBuilder {
+        BlockBuilder {
+            data: Vec::new(),
+            restarts: vec![0],
+            restart_interval,
+            counter: 0,
+            last_key: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, key: &[u8], value: &[u8]) {
+        let mut shared = 0;
+        if self.counter < self.restart_interval {
+            shared = self
+                .last_key
+                .iter()
+                .zip(key)
+                .take_while(|(a, b)| a == b)
+                .count();
+        } else {
+            self.restarts.push(self.data.len() as u32);
+            self.counter = 0;
+        }
+
+        write_var_u64(&mut self.data, shared as u64);
+        write_var_u64(&mut self.data, (key.len() - shared) as u64);
+        write_var_u64(&mut self.data, value.len() as u64);
+        self.data.extend_from_slice(&key[shared..]);
+        self.data.extend_from_slice(value);
+
+        self.last_key = key.to_vec();
+        self.counter += 1;
+    }
+
+    fn size(&self) -> usize {
+        self.data.len() + self.restarts.len() * 4 + 4
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut out = self.data;
+        for restart in &self.restarts {
+            out.write_u32::<LittleEndian>(*restart)
+                .expect("Failed to write restart");
+        }
+        out.write_u32::<LittleEndian>(self.restarts.len() as u32)
+            .expect("Failed to write restart count");
+        out
+    }
+}
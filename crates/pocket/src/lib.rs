@@ -1,4 +1,7 @@
 #![feature(int_roundings)]
 extern crate core;
 
-pub mod alpha;
\ No newline at end of file
+pub mod alpha;
+pub mod block_state;
+pub mod leveldb;
+pub mod mcstructure;
@@ -0,0 +1,274 @@
+use crate::block_state::{
+    get_bedrock_block, read_palette, set_bedrock_block, write_palette_entry, BLOCK_STATE_VERSION,
+    WATERLOGGED,
+};
+use lodestone_common::io::nbt::{self, NbtFlavor};
+use lodestone_common::types::hashmap_ext::HashMapExt;
+use lodestone_common::types::vec3i::Vec3i;
+use lodestone_common::util::McVersion;
+use lodestone_level::block::conversion::{
+    format_block_state, get_version_block_name, parse_block_state,
+};
+use lodestone_level::block::Block;
+use lodestone_level::entity::block_entity::{BlockEntity, HasBlockEntity};
+use lodestone_level::entity::stash::{get_stashed_entities, stash_entities, EntityFormat};
+use lodestone_level::level::{metadata, Level};
+use quartz_nbt::{NbtCompound, NbtList, NbtTag};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Cursor;
+
+pub const MCSTRUCTURE_FORMAT_VERSION: i32 = 1;
+/// Block state version written in the palette, 1.18.10
+pub const MCSTRUCTURE_BLOCK_VERSION: i32 = BLOCK_STATE_VERSION;
+
+pub trait McStructureLevel {
+    /// Reads a Bedrock `.mcstructure` into a finite level
+    ///
+    /// Water in the second block layer is read as `waterlogged=true`, other second layer blocks are dropped.
+    fn read_mcstructure(version: McVersion, data: Vec<u8>) -> Result<Level, String>;
+    /// Writes out the whole level as a `.mcstructure`
+    ///
+    /// Use [`Level::copy_region`] first to only export part of a level.
+    fn write_mcstructure(&self, version: McVersion) -> Vec<u8>;
+}
+
+/// Reads a list of 3 ints (`size`, `structure_world_origin`)
+fn read_int_list(list: &NbtList) -> Option<(i32, i32, i32)> {
+    let mut ints = list.iter().map(|t| match t {
+        NbtTag::Int(i) => Some(*i),
+        _ => None,
+    });
+
+    Some((ints.next()??, ints.next()??, ints.next()??))
+}
+
+fn read_layer(layer: Option<&NbtTag>, size: usize) -> Vec<i32> {
+    match layer {
+        Some(NbtTag::List(list)) => list
+            .iter()
+            .map(|t| match t {
+                NbtTag::Int(i) => *i,
+                _ => -1,
+            })
+            .collect(),
+        _ => vec![-1; size],
+    }
+}
+
+impl McStructureLevel for Level {
+    fn read_mcstructure(version: McVersion, data: Vec<u8>) -> Result<Level, String> {
+        let (nbt, _) = nbt::read_nbt(&mut Cursor::new(data), NbtFlavor::LittleEndian)
+            .map_err(|e| format!("Failed to read mcstructure NBT: {}", e))?;
+
+        let (width, height, length) = nbt
+            .get::<_, &NbtList>("size")
+            .ok()
+            .and_then(read_int_list)
+            .ok_or("Structure has no valid size")?;
+
+        let structure: &NbtCompound = nbt.get("structure").map_err(|e| e.to_string())?;
+        let default: &NbtCompound = structure
+            .get::<_, &NbtCompound>("palette")
+            .and_then(|p| p.get::<_, &NbtCompound>("default"))
+            .map_err(|e| e.to_string())?;
+
+        let palette = read_palette(default.get("block_palette").map_err(|e| e.to_string())?);
+
+        let size = (width * height * length) as usize;
+        let layers: &NbtList = structure.get("block_indices").map_err(|e| e.to_string())?;
+        let blocks = read_layer(layers.as_ref().first(), size);
+        let liquids = read_layer(layers.as_ref().get(1), size);
+
+        let mut level = Level::new();
+        level.create_finite(width, height as i16, length);
+
+        for x in 0..width {
+            for y in 0..height {
+                for z in 0..length {
+                    let i = ((x * height + y) * length + z) as usize;
+
+                    // -1 is a structure void
+                    let Some((name, states)) = blocks
+                        .get(i)
+                        .and_then(|b| usize::try_from(*b).ok())
+                        .and_then(|b| palette.get(b))
+                    else {
+                        continue;
+                    };
+
+                    set_bedrock_block(&mut level, version, x, y as i16, z, name, states);
+
+                    let waterlogged = liquids
+                        .get(i)
+                        .and_then(|b| usize::try_from(*b).ok())
+                        .and_then(|b| palette.get(b))
+                        .is_some_and(|(name, _)| {
+                            name == "minecraft:water" || name == "minecraft:flowing_water"
+                        });
+
+                    if waterlogged && level.get_block(x, y as i16, z) != Block::Air {
+                        level.set_data(x, y as i16, z, WATERLOGGED.to_string(), "true".to_string());
+                    }
+                }
+            }
+        }
+
+        if let Ok(position_data) = default.get::<_, &NbtCompound>("block_position_data") {
+            for (i, data) in position_data.inner() {
+                let (Ok(i), NbtTag::Compound(data)) = (i.parse::<i32>(), data) else {
+                    continue;
+                };
+                let Ok(block_entity) = data.get::<_, &NbtCompound>("block_entity_data") else {
+                    continue;
+                };
+
+                let pos = Vec3i::new(i / (height * length), (i / length) % height, i % length);
+                let id: &str = block_entity.get("id").unwrap_or("");
+
+                let mut data = block_entity.clone();
+                for key in ["id", "x", "y", "z"] {
+                    data.inner_mut().remove(key);
+                }
+
+                let block_entity = BlockEntity {
+                    id: id.to_string(),
+                    x: pos.x,
+                    y: pos.y,
+                    z: pos.z,
+                    components: Default::default(),
+                    keep_packed: false,
+                    data: HasBlockEntity::NotFound(id.to_string(), data),
+                };
+
+                if let Some(c) = level.get_chunk_by_block_coords_mut(pos.x, pos.z) {
+                    c.add_block_entity(pos, block_entity);
+                }
+            }
+        }
+
+        if let Ok(entities) = structure.get::<_, &NbtList>("entities") {
+            stash_entities(&mut level.custom_data, EntityFormat::McStructure, entities);
+        }
+
+        if let Some(origin) = nbt
+            .get::<_, &NbtList>("structure_world_origin")
+            .ok()
+            .and_then(read_int_list)
+        {
+            level
+                .custom_data
+                .set_value(metadata::WE_ORIGIN_X.to_string(), origin.0);
+            level
+                .custom_data
+                .set_value(metadata::WE_ORIGIN_Y.to_string(), origin.1);
+            level
+                .custom_data
+                .set_value(metadata::WE_ORIGIN_Z.to_string(), origin.2);
+        }
+
+        Ok(level)
+    }
+
+    fn write_mcstructure(&self, version: McVersion) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        let mut root = NbtCompound::new();
+
+        let bounds = self.get_finite_bounds();
+        let width = bounds.width;
+        let height = bounds.height as i32;
+        let length = bounds.length;
+
+        let origin: Vec<i32> = [
+            metadata::WE_ORIGIN_X,
+            metadata::WE_ORIGIN_Y,
+            metadata::WE_ORIGIN_Z,
+        ]
+        .iter()
+        .map(|k| self.custom_data.get_value::<i32, _>(k).unwrap_or(0))
+        .collect();
+
+        root.insert("format_version", MCSTRUCTURE_FORMAT_VERSION);
+        root.insert("size", NbtList::from(vec![width, height, length]));
+
+        let mut palette: Vec<NbtCompound> = Vec::new();
+        let mut indices: HashMap<String, i32> = HashMap::new();
+        let mut get_index = |name: &str, states: &BTreeMap<String, String>| {
+            *indices
+                .entry(format_block_state(name, states))
+                .or_insert_with(|| {
+                    palette.push(write_palette_entry(name, states));
+                    palette.len() as i32 - 1
+                })
+        };
+
+        let size = (width * height * length) as usize;
+        let mut blocks = Vec::with_capacity(size);
+        let mut liquids = Vec::with_capacity(size);
+        let mut position_data = NbtCompound::new();
+
+        let water = get_version_block_name(version, &Block::Water).unwrap_or("minecraft:water");
+        let (water_name, water_states) = parse_block_state(water);
+
+        for x in 0..width {
+            for y in 0..height {
+                for z in 0..length {
+                    let (wx, wy, wz) = (x + bounds.x, y + bounds.y as i32, z + bounds.z);
+
+                    let (name, states, waterlogged) =
+                        get_bedrock_block(self, version, wx, wy as i16, wz);
+
+                    blocks.push(get_index(&name, &states));
+                    liquids.push(match waterlogged {
+                        true => get_index(water_name, &water_states),
+                        false => -1,
+                    });
+
+                    if let Some(block_entity) = self
+                        .get_chunk_by_block_coords(wx, wz)
+                        .and_then(|c| c.block_entities.get(&Vec3i::new(wx, wy, wz)))
+                    {
+                        let mut nbt = match &block_entity.data {
+                            HasBlockEntity::NotFound(_, data) => data.clone(),
+                            HasBlockEntity::Found(_) => block_entity
+                                .to_nbt(McVersion::Alpha1_2_6)
+                                .unwrap_or_default(),
+                        };
+
+                        nbt.insert("id", &block_entity.id);
+                        nbt.insert("x", origin[0] + x);
+                        nbt.insert("y", origin[1] + y);
+                        nbt.insert("z", origin[2] + z);
+
+                        let mut data = NbtCompound::new();
+                        data.insert("block_entity_data", nbt);
+                        position_data.insert(((x * height + y) * length + z).to_string(), data);
+                    }
+                }
+            }
+        }
+
+        let mut default = NbtCompound::new();
+        default.insert("block_palette", NbtList::from(palette));
+        default.insert("block_position_data", position_data);
+
+        let mut palettes = NbtCompound::new();
+        palettes.insert("default", default);
+
+        let entities = get_stashed_entities(&self.custom_data, EntityFormat::McStructure);
+
+        let mut structure = NbtCompound::new();
+        structure.insert(
+            "block_indices",
+            NbtList::from(vec![NbtList::from(blocks), NbtList::from(liquids)]),
+        );
+        structure.insert("entities", entities);
+        structure.insert("palette", palettes);
+
+        root.insert("structure", structure);
+        root.insert("structure_world_origin", NbtList::from(origin));
+
+        nbt::write_nbt(&mut out, "", &root, NbtFlavor::LittleEndian);
+
+        out
+    }
+}
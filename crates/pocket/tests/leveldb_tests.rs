@@ -0,0 +1,201 @@
+mod leveldb_tests {
+    use lodestone_common::types::hashmap_ext::HashMapExt;
+    use lodestone_common::types::vec3i::Vec3i;
+    use lodestone_common::util::McVersion;
+    use lodestone_level::block::Block;
+    use lodestone_level::entity::block_entity::{BlockEntity, HasBlockEntity};
+    use lodestone_level::level::{metadata, Coords, Level};
+    use lodestone_pocket::leveldb::chunk::{chunk_key, LevelDbLevel, TAG_SUB_CHUNK_PREFIX};
+    use lodestone_pocket::leveldb::log::{read_log_records, write_log_records, write_write_batch};
+    use lodestone_pocket::leveldb::table::{read_table, write_table, Compression};
+    use lodestone_pocket::leveldb::{crc32c, make_internal_key, LevelDb};
+    use quartz_nbt::NbtCompound;
+    use std::fs;
+    use std::path::Path;
+
+    fn add_chest(level: &mut Level, x: i32, y: i32, z: i32) {
+        let mut chest = NbtCompound::new();
+        chest.insert("CustomName", "loot");
+        if let Some(c) = level.get_chunk_by_block_coords_mut(x, z) {
+            c.add_block_entity(
+                Vec3i::new(x, y, z),
+                BlockEntity {
+                    id: "Chest".to_string(),
+                    x,
+                    y,
+                    z,
+                    components: Default::default(),
+                    keep_packed: false,
+                    data: HasBlockEntity::NotFound("Chest".to_string(), chest),
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn read_leveldb_world() {
+        let path = Path::new("../../internal_tests/pocket/LevelDbWorld");
+        let lvl = match Level::read_leveldb_world_dir(McVersion::Bedrock1_18_10, path) {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("uh oh {}", e);
+                return;
+            }
+        };
+
+        println!("{} has {} chunks", lvl.name, lvl.get_chunk_count());
+    }
+
+    #[test]
+    fn leveldb_log_replay() {
+        assert_eq!(crc32c(b"123456789"), 0xE3069283);
+
+        let mut db = LevelDb::new();
+        db.put(b"a".to_vec(), b"1".to_vec());
+        db.put(b"b".to_vec(), b"2".to_vec());
+        db.put(b"c".to_vec(), b"3".to_vec());
+
+        let dir = std::env::temp_dir().join("lodestone_leveldb_log_replay");
+        db.write_dir(&dir, Compression::ZlibRaw).unwrap();
+
+        // big enough to be split over log blocks
+        let big = vec![7u8; 40000];
+        let batch = write_write_batch(
+            1,
+            &[
+                (b"b".to_vec(), Some(big.clone())),
+                (b"c".to_vec(), None),
+                (b"d".to_vec(), Some(b"4".to_vec())),
+            ],
+        );
+        fs::write(dir.join("000003.log"), write_log_records(&[batch])).unwrap();
+
+        let read = LevelDb::read_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(read.get(b"a"), Some(&b"1".to_vec()));
+        assert_eq!(read.get(b"b"), Some(&big));
+        assert_eq!(read.get(b"c"), None);
+        assert_eq!(read.get(b"d"), Some(&b"4".to_vec()));
+    }
+
+    #[test]
+    fn legacy_terrain_round_trip() {
+        let mut level = Level::new_with_name("Legacy".to_string());
+        level.create_finite(32, 128, 16);
+        level.set_block(1, 2, 3, Block::Stone);
+        level.set_block(20, 100, 5, Block::Glass);
+        add_chest(&mut level, 20, 101, 5);
+        let mut biomes = vec![1u8; 256];
+        biomes[17] = 12;
+        level
+            .get_chunk_by_block_coords_mut(1, 3)
+            .unwrap()
+            .custom_data
+            .set_value(metadata::BIOMES.to_string(), biomes.clone());
+
+        let dir = std::env::temp_dir().join("lodestone_leveldb_legacy_terrain");
+        level
+            .write_leveldb_world_dir(McVersion::MCPEAlpha0_8_1, &dir)
+            .unwrap();
+        let read = Level::read_leveldb_world_dir(McVersion::MCPEAlpha0_8_1, &dir).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(read.name, "Legacy");
+        assert_eq!(read.get_chunk_count(), 2);
+        assert_eq!(read.get_block(1, 2, 3), Block::Stone);
+        assert_eq!(read.get_block(20, 100, 5), Block::Glass);
+
+        let chunk = read.get_chunk_by_block_coords(20, 5).unwrap();
+        assert!(chunk.block_entities.contains_key(&Vec3i::new(20, 101, 5)));
+        let chunk = read.get_chunk_by_block_coords(1, 3).unwrap();
+        assert_eq!(
+            chunk.custom_data.get_value::<Vec<u8>, _>(metadata::BIOMES),
+            Some(biomes)
+        );
+    }
+
+    #[test]
+    fn sub_chunk_round_trip() {
+        let mut level = Level::new_with_name("Bedrock".to_string());
+        level.create_finite(16, 256, 16);
+        level.set_block(1, 0, 1, Block::Stone);
+        level.set_block(2, 70, 3, Block::OrangeWool);
+        level.set_block(3, 200, 1, Block::SmoothStoneSlab);
+        level.set_data(3, 200, 1, "waterlogged".to_string(), "true".to_string());
+        level.set_block(4, 3, 2, Block::Custom);
+        level.set_data(
+            4,
+            3,
+            2,
+            metadata::CUSTOM_BLOCK_NAME.to_string(),
+            "minecraft:oak_stairs".to_string(),
+        );
+        level.set_data(4, 3, 2, "weirdo_direction".to_string(), "2".to_string());
+        add_chest(&mut level, 5, 1, 5);
+
+        let dir = std::env::temp_dir().join("lodestone_leveldb_sub_chunks");
+        level
+            .write_leveldb_world_dir(McVersion::Bedrock1_18_10, &dir)
+            .unwrap();
+        let read = Level::read_leveldb_world_dir(McVersion::Bedrock1_18_10, &dir).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(read.get_block(1, 0, 1), Block::Stone);
+        assert_eq!(read.get_block(2, 70, 3), Block::OrangeWool);
+        assert_eq!(read.get_block(3, 200, 1), Block::SmoothStoneSlab);
+        assert_eq!(
+            read.get_states(3, 200, 1)
+                .and_then(|s| s.get("waterlogged").cloned()),
+            Some("true".to_string())
+        );
+
+        let stairs = read.get_states(4, 3, 2).cloned().unwrap_or_default();
+        assert_eq!(read.get_block(4, 3, 2), Block::Custom);
+        assert_eq!(
+            stairs.get(metadata::CUSTOM_BLOCK_NAME).map(String::as_str),
+            Some("minecraft:oak_stairs")
+        );
+        assert_eq!(
+            stairs.get("weirdo_direction").map(String::as_str),
+            Some("2")
+        );
+
+        let chunk = read.get_chunk_by_block_coords(5, 5).unwrap();
+        assert!(chunk.block_entities.contains_key(&Vec3i::new(5, 1, 5)));
+    }
+
+    #[test]
+    fn corrupt_data_is_rejected() {
+        let entries = vec![(make_internal_key(b"a", 1, 1), b"1".to_vec())];
+        let mut table = write_table(&entries, Compression::None);
+        assert!(read_table(&table).is_ok());
+        table[0] ^= 0xFF;
+        assert!(read_table(&table).is_err());
+
+        // records after one with a bad CRC are dropped
+        let mut log = write_log_records(&[b"first".to_vec(), b"second".to_vec()]);
+        let last = log.len() - 1;
+        log[last] ^= 0xFF;
+        assert_eq!(read_log_records(&log).unwrap(), vec![b"first".to_vec()]);
+
+        let mut level = Level::new();
+        level.create_finite(16, 256, 16);
+        level.set_block(1, 0, 1, Block::Stone);
+
+        let dir = std::env::temp_dir().join("lodestone_leveldb_corrupt");
+        level
+            .write_leveldb_world_dir(McVersion::Bedrock1_18_10, &dir)
+            .unwrap();
+
+        // a block storage with more bits per block than fit in a word
+        let mut db = LevelDb::read_dir(&dir.join("db")).unwrap();
+        let key = chunk_key(&Coords { x: 0, z: 0 }, TAG_SUB_CHUNK_PREFIX, Some(0));
+        db.put(key, vec![8, 1, 40 << 1]);
+        db.write_dir(&dir.join("db"), Compression::ZlibRaw).unwrap();
+
+        let read = Level::read_leveldb_world_dir(McVersion::Bedrock1_18_10, &dir);
+        fs::remove_dir_all(&dir).ok();
+        assert!(read.is_err());
+    }
+}
@@ -0,0 +1,115 @@
+mod mcstructure_tests {
+    use lodestone_common::types::vec3i::Vec3i;
+    use lodestone_common::util::McVersion;
+    use lodestone_level::block::Block;
+    use lodestone_level::entity::block_entity::{BlockEntity, HasBlockEntity};
+    use lodestone_level::level::metadata;
+    use lodestone_level::level::{Bounds, Level};
+    use lodestone_pocket::mcstructure::McStructureLevel;
+    use quartz_nbt::NbtCompound;
+
+    #[test]
+    fn mcstructure_round_trip() {
+        let mut level = Level::new();
+        level.create_finite(16, 8, 16);
+        level.set_block(1, 0, 1, Block::Stone);
+        level.set_block(2, 1, 3, Block::OrangeWool);
+        level.set_block(3, 2, 1, Block::SmoothStoneSlab);
+        level.set_data(3, 2, 1, "waterlogged".to_string(), "true".to_string());
+        level.set_block(4, 3, 2, Block::Custom);
+        level.set_data(
+            4,
+            3,
+            2,
+            metadata::CUSTOM_BLOCK_NAME.to_string(),
+            "minecraft:oak_stairs".to_string(),
+        );
+        level.set_data(4, 3, 2, "weirdo_direction".to_string(), "2".to_string());
+        level.set_data(4, 3, 2, "upside_down_bit".to_string(), "true".to_string());
+
+        let mut chest = NbtCompound::new();
+        chest.insert("CustomName", "loot");
+        if let Some(c) = level.get_chunk_by_block_coords_mut(5, 5) {
+            c.add_block_entity(
+                Vec3i::new(5, 1, 5),
+                BlockEntity {
+                    id: "Chest".to_string(),
+                    x: 5,
+                    y: 1,
+                    z: 5,
+                    components: Default::default(),
+                    keep_packed: false,
+                    data: HasBlockEntity::NotFound("Chest".to_string(), chest),
+                },
+            );
+        }
+
+        let out = level.write_mcstructure(McVersion::Bedrock1_18_10);
+        let read = Level::read_mcstructure(McVersion::Bedrock1_18_10, out).unwrap();
+
+        assert_eq!(
+            read.get_finite_bounds(),
+            Bounds {
+                x: 0,
+                y: 0,
+                z: 0,
+                width: 16,
+                height: 8,
+                length: 16,
+            }
+        );
+        assert_eq!(read.get_block(1, 0, 1), Block::Stone);
+        assert_eq!(read.get_block(2, 1, 3), Block::OrangeWool);
+        assert_eq!(read.get_block(3, 2, 1), Block::SmoothStoneSlab);
+        assert_eq!(
+            read.get_states(3, 2, 1)
+                .and_then(|s| s.get("waterlogged").cloned()),
+            Some("true".to_string())
+        );
+
+        let stairs = read.get_states(4, 3, 2).cloned().unwrap_or_default();
+        assert_eq!(read.get_block(4, 3, 2), Block::Custom);
+        assert_eq!(
+            stairs.get(metadata::CUSTOM_BLOCK_NAME).map(String::as_str),
+            Some("minecraft:oak_stairs")
+        );
+        assert_eq!(
+            stairs.get("weirdo_direction").map(String::as_str),
+            Some("2")
+        );
+        assert_eq!(
+            stairs.get("upside_down_bit").map(String::as_str),
+            Some("true")
+        );
+
+        let chunk = read.get_chunk_by_block_coords(5, 5).unwrap();
+        let chest = chunk.block_entities.get(&Vec3i::new(5, 1, 5)).unwrap();
+        assert_eq!(chest.id, "Chest");
+    }
+
+    #[test]
+    fn mcstructure_region_round_trip() {
+        let mut level = Level::new();
+        level.create_finite(32, 16, 32);
+        level.set_block(20, 2, 20, Block::Stone);
+        level.set_block(23, 5, 22, Block::OrangeWool);
+
+        let region = level.copy_region(20, 2, 20, 4, 4, 3);
+        let out = region.write_mcstructure(McVersion::Bedrock1_18_10);
+        let read = Level::read_mcstructure(McVersion::Bedrock1_18_10, out).unwrap();
+
+        assert_eq!(
+            read.get_finite_bounds(),
+            Bounds {
+                x: 0,
+                y: 0,
+                z: 0,
+                width: 4,
+                height: 4,
+                length: 3,
+            }
+        );
+        assert_eq!(read.get_block(0, 0, 0), Block::Stone);
+        assert_eq!(read.get_block(3, 3, 2), Block::OrangeWool);
+    }
+}
@@ -0,0 +1,246 @@
+mod pe_world_tests {
+    use lodestone_common::io::nbt::{self, NbtFlavor};
+    use lodestone_common::types::hashmap_ext::HashMapExt;
+    use lodestone_common::types::vec3i::Vec3i;
+    use lodestone_common::util::McVersion;
+    use lodestone_level::block::conversion::get_version_block_id;
+    use lodestone_level::block::{Block, BlockId};
+    use lodestone_level::entity::block_entity::{BlockEntity, HasBlockEntity};
+    use lodestone_level::entity::stash::{get_stashed_entities, EntityFormat};
+    use lodestone_level::level::chunk::Chunk;
+    use lodestone_level::level::{metadata, Coords, Level};
+    use lodestone_pocket::alpha::alpha_081::{Alpha081Chunk, Alpha081Level};
+    use lodestone_pocket::alpha::world::PeWorldLevel;
+    use quartz_nbt::{NbtCompound, NbtList};
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn read_pe_world() {
+        let path = Path::new("../../internal_tests/pocket/NostalgiaPE1");
+        let lvl = match Level::read_pe_world_dir(McVersion::MCPEAlpha0_8_1, path) {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("uh oh {}", e);
+                return;
+            }
+        };
+
+        println!("{} spawn {:?}", lvl.name, lvl.spawn);
+    }
+
+    #[test]
+    fn pe_world_round_trip() {
+        let mut level = Level::new_with_name("Pocket".to_string());
+        level.create_finite(16, 128, 16);
+        level.time = 1234;
+        level.set_spawn_point(3, 70, 4);
+        level
+            .custom_data
+            .set_value::<i64>(metadata::RANDOM_SEED.to_string(), 42);
+        level
+            .custom_data
+            .set_value::<i32>(metadata::GAME_TYPE.to_string(), 1);
+        level.set_block(1, 2, 3, Block::Stone);
+
+        let mut sign = NbtCompound::new();
+        sign.insert("Text1", "hello");
+        if let Some(c) = level.get_chunk_by_block_coords_mut(5, 6) {
+            c.add_block_entity(
+                Vec3i::new(5, 10, 6),
+                BlockEntity {
+                    id: "Sign".to_string(),
+                    x: 5,
+                    y: 10,
+                    z: 6,
+                    components: Default::default(),
+                    keep_packed: false,
+                    data: HasBlockEntity::NotFound("Sign".to_string(), sign),
+                },
+            );
+        }
+
+        let dir = std::env::temp_dir().join("lodestone_pe_world_round_trip");
+        level
+            .write_pe_world_dir(McVersion::MCPEAlpha0_8_1, &dir)
+            .unwrap();
+        let read = Level::read_pe_world_dir(McVersion::MCPEAlpha0_8_1, &dir).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(read.name, "Pocket");
+        assert_eq!(read.time, 1234);
+        assert_eq!((read.spawn.x, read.spawn.y, read.spawn.z), (3, 70, 4));
+        assert_eq!(
+            read.custom_data
+                .get_value::<i64, &str>(metadata::RANDOM_SEED),
+            Some(42)
+        );
+        assert_eq!(
+            read.custom_data.get_value::<i32, &str>(metadata::GAME_TYPE),
+            Some(1)
+        );
+        assert_eq!(read.get_block(1, 2, 3), Block::Stone);
+
+        let chunk = read.get_chunk_by_block_coords(5, 6).unwrap();
+        let sign = chunk.block_entities.get(&Vec3i::new(5, 10, 6)).unwrap();
+        match &sign.data {
+            HasBlockEntity::NotFound(_, data) => {
+                assert_eq!(data.get::<_, &str>("Text1").unwrap(), "hello")
+            }
+            _ => panic!("Sign should be kept as-is"),
+        }
+    }
+
+    #[test]
+    fn chunks_dat_origin_and_data_values() {
+        // 41 chunks wide, so it gets centered and the edges are dropped
+        let mut level = Level::new();
+        for x in 10..=50 {
+            for z in -3..=0 {
+                level.add_chunk(Coords { x, z }, Chunk::new(128));
+            }
+        }
+        level.set_block(20 * 16 + 2, 5, -3 * 16 + 1, Block::OrangeWool);
+        level
+            .get_chunk_mut(&Coords { x: 20, z: -3 })
+            .unwrap()
+            .custom_data
+            .set_value(metadata::BIOMES.to_string(), vec![7u8; 256]);
+
+        let origin = level.get_alpha081_origin();
+        assert_eq!((origin.x, origin.z), (14, -3));
+
+        let (data, dropped) = level.write_alpha081_chunks_dat(McVersion::MCPEAlpha0_8_1, None);
+        assert_eq!(dropped.len(), (41 - 32) * 4);
+        assert!(dropped.iter().all(|c| c.x < 14 || c.x >= 46));
+
+        let read = Level::read_alpha081_chunks_dat(McVersion::MCPEAlpha0_8_1, data).unwrap();
+        assert_eq!(read.get_block(6 * 16 + 2, 5, 1), Block::OrangeWool);
+        assert_eq!(
+            read.get_chunk(&Coords { x: 6, z: 0 })
+                .unwrap()
+                .custom_data
+                .get_value::<Vec<u8>, _>(metadata::BIOMES),
+            Some(vec![7u8; 256])
+        );
+    }
+
+    #[test]
+    fn read_old_chunks_dat() {
+        let mut chunk = Chunk::new(128);
+        chunk.set_block(1, 2, 3, Block::NetherReactorCore);
+        chunk.set_block(1, 3, 3, Block::ActiveNetherReactorCore);
+        chunk.set_block(4, 5, 6, Block::GlowingObsidian);
+        chunk.set_block(7, 8, 9, Block::OrangeWool);
+
+        // Pre-0.8 chunks have no length in front and no biomes at the end
+        let mut data = vec![0u8; 0x1000];
+        data[..4].copy_from_slice(&[0x14, 1, 0, 0]);
+        data.extend_from_slice(&chunk.write_alpha081(McVersion::MCPEAlpha0_5_0)[..81920]);
+
+        let read = Level::read_alpha081_chunks_dat(McVersion::MCPEAlpha0_5_0, data).unwrap();
+        assert_eq!(read.get_block(1, 2, 3), Block::NetherReactorCore);
+        assert_eq!(read.get_block(1, 3, 3), Block::ActiveNetherReactorCore);
+        assert_eq!(read.get_block(4, 5, 6), Block::GlowingObsidian);
+        assert_eq!(read.get_block(7, 8, 9), Block::OrangeWool);
+        assert!(read
+            .get_chunk(&Coords { x: 0, z: 0 })
+            .unwrap()
+            .custom_data
+            .get_value::<Vec<u8>, _>(metadata::BIOMES)
+            .is_none());
+
+        // Glowing obsidian didn't exist yet in 0.4
+        let mut level = Level::new();
+        level.add_chunk(Coords { x: 0, z: 0 }, chunk);
+        let (data, _) = level.write_alpha081_chunks_dat(McVersion::MCPEAlpha0_4_0, None);
+        let read = Level::read_alpha081_chunks_dat(McVersion::MCPEAlpha0_4_0, data).unwrap();
+        assert_eq!(read.get_block(4, 5, 6), Block::Obsidian);
+    }
+
+    #[test]
+    fn pe_entities_to_java() {
+        let mut level = Level::new();
+        level.create_finite(32, 128, 16);
+
+        let mut pig = NbtCompound::new();
+        pig.insert("id", 12);
+        pig.insert("Pos", NbtList::from(vec![21.5f32, 64.0, 6.5]));
+        pig.insert("Health", 10i16);
+        // not an entity Java has
+        let mut unknown = NbtCompound::new();
+        unknown.insert("id", 999);
+        unknown.insert("Pos", NbtList::from(vec![1.5f32, 64.0, 1.5]));
+
+        let mut root = NbtCompound::new();
+        root.insert(metadata::ENTITIES, NbtList::from(vec![pig, unknown]));
+        root.insert(metadata::TILE_ENTITIES, NbtList::new());
+        let mut nbt = Vec::new();
+        nbt::write_nbt(&mut nbt, "", &root, NbtFlavor::LittleEndian);
+
+        let mut data = b"ENT\0".to_vec();
+        data.extend_from_slice(&1i32.to_le_bytes());
+        data.extend_from_slice(&(nbt.len() as i32).to_le_bytes());
+        data.extend_from_slice(&nbt);
+        level.read_pe_entities_dat_into_existing(data).unwrap();
+
+        // only the pig goes to Java, in the chunk it's in
+        let chunk = level.get_chunk(&Coords { x: 1, z: 0 }).unwrap();
+        let entities = get_stashed_entities(&chunk.custom_data, EntityFormat::Java);
+        assert_eq!(entities.len(), 1);
+        let java_pig = entities.get::<&NbtCompound>(0).unwrap();
+        assert_eq!(java_pig.get::<_, &str>("id").unwrap(), "Pig");
+        assert_eq!(java_pig.get::<_, i16>("Health").unwrap(), 10);
+        assert_eq!(
+            java_pig
+                .get::<_, &NbtList>("Pos")
+                .unwrap()
+                .get::<f64>(0)
+                .unwrap(),
+            21.5
+        );
+        let chunk = level.get_chunk(&Coords { x: 0, z: 0 }).unwrap();
+        assert!(get_stashed_entities(&chunk.custom_data, EntityFormat::Java).is_empty());
+
+        // PE gets both back as they were
+        let read = |data| {
+            let mut read = Level::new();
+            read.create_finite(32, 128, 16);
+            read.read_pe_entities_dat_into_existing(data).unwrap();
+            get_stashed_entities(&read.custom_data, EntityFormat::PocketAlpha)
+        };
+        assert_eq!(read(level.write_pe_entities_dat()).len(), 2);
+
+        // a level from Java only has the pig to give
+        level.custom_data.clear();
+        let entities = read(level.write_pe_entities_dat());
+        assert_eq!(entities.len(), 1);
+        let pe_pig = entities.get::<&NbtCompound>(0).unwrap();
+        assert_eq!(pe_pig.get::<_, i32>("id").unwrap(), 12);
+        assert_eq!(
+            pe_pig
+                .get::<_, &NbtList>("Pos")
+                .unwrap()
+                .get::<f32>(0)
+                .unwrap(),
+            21.5
+        );
+    }
+
+    #[test]
+    fn pe_block_ids_skip_java_flattening() {
+        // PE sorts after Java 1.13, but never gets its flattened IDs
+        assert_eq!(
+            get_version_block_id(McVersion::MCPEAlpha0_2_0, &Block::CeramicTile),
+            BlockId::Numeric(42)
+        );
+        assert_eq!(
+            get_version_block_id(McVersion::Release1_13, &Block::CeramicTile),
+            BlockId::NumericAndFlattened(155, "minecraft:chiseled_quartz_block")
+        );
+        assert_eq!(
+            get_version_block_id(McVersion::MCPEAlpha0_8_1, &Block::Stonecutter),
+            BlockId::Numeric(245)
+        );
+    }
+}